@@ -0,0 +1,247 @@
+// biomes as metas, the same way blocks are. a world names its biomes by ids
+// like "minecraft:plains" since 1.18, and by numbers before
+use crate::id::Id;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Meta {
+    inner: u16
+}
+
+impl Meta {
+    crate fn new(inner: u16) -> Meta {
+        Meta { inner }
+    }
+}
+
+// maps biome ids into metas, like `block::IdSystem` for blocks
+pub trait IdSystem {
+    // check if this meta is registered
+    fn has_biome_meta(&self, meta: Meta) -> bool;
+    // panic if biome meta not found
+    fn biome_meta_to_id(&self, meta: Meta) -> Id<'_>;
+    // check if this biome id is registered
+    fn has_biome_id(&self, id: Id) -> bool;
+    // panic if biome id not found
+    fn biome_id_to_meta(&self, id: Id) -> Meta;
+}
+
+#[derive(Debug, Default)]
+pub struct HashSystem {
+    mti: HashMap<Meta, String>,
+    itm: HashMap<String, Meta>,
+    next_inner: u16
+}
+
+impl HashSystem {
+    pub fn new() -> HashSystem {
+        Default::default()
+    }
+
+    pub fn register_biome(&mut self, id: Id) -> Meta {
+        let id = id.to_string();
+        let meta = Meta::new(self.next_inner);
+        self.itm.insert(id.clone(), meta);
+        self.mti.insert(meta, id);
+        self.next_inner += 1;
+        meta
+    }
+}
+
+impl IdSystem for HashSystem {
+
+    fn has_biome_meta(&self, meta: Meta) -> bool {
+        self.mti.contains_key(&meta)
+    }
+
+    fn biome_meta_to_id(&self, meta: Meta) -> Id<'_> {
+        Id::new(self.mti[&meta].clone())
+    }
+
+    fn has_biome_id(&self, id: Id) -> bool {
+        self.itm.contains_key(&id.to_string())
+    }
+
+    fn biome_id_to_meta(&self, id: Id) -> Meta {
+        self.itm[&id.to_string()]
+    }
+}
+
+// a `HashSystem` that can register more ids while shared, like
+// `block::SharedHashSystem`. starts from `global_id_system`
+#[derive(Debug)]
+pub struct SharedHashSystem {
+    inner: RwLock<HashSystem>
+}
+
+impl SharedHashSystem {
+    pub fn new() -> SharedHashSystem {
+        SharedHashSystem {
+            inner: RwLock::new(global_id_system())
+        }
+    }
+
+    // the meta of this id, registering it if it's new
+    pub fn register_biome(&self, id: Id) -> Meta {
+        if let Some(&meta) = self.inner.read().unwrap().itm.get(&id.to_string()) {
+            return meta;
+        }
+        let mut inner = self.inner.write().unwrap();
+        match inner.itm.get(&id.to_string()) {
+            Some(&meta) => meta,
+            None => inner.register_biome(id)
+        }
+    }
+}
+
+impl Default for SharedHashSystem {
+    fn default() -> SharedHashSystem {
+        SharedHashSystem::new()
+    }
+}
+
+impl IdSystem for SharedHashSystem {
+
+    fn has_biome_meta(&self, meta: Meta) -> bool {
+        self.inner.read().unwrap().has_biome_meta(meta)
+    }
+
+    fn biome_meta_to_id(&self, meta: Meta) -> Id<'_> {
+        Id::new(self.inner.read().unwrap().mti[&meta].clone())
+    }
+
+    fn has_biome_id(&self, id: Id) -> bool {
+        self.inner.read().unwrap().has_biome_id(id)
+    }
+
+    fn biome_id_to_meta(&self, id: Id) -> Meta {
+        self.inner.read().unwrap().biome_id_to_meta(id)
+    }
+}
+
+macro_rules! reg_biomes {
+    ($($number: expr, $id_string: expr,)+) => {
+
+// every biome with a number, as stored before 1.18, named as in 1.13 to 1.17
+static LEGACY_BIOMES: &[(i32, &str)] = &[$(($number, $id_string),)+];
+
+// the legacy biomes in order of their numbers, leaving "minecraft:ocean" with a zero meta
+pub fn global_id_system() -> HashSystem {
+    let mut ans = HashSystem::new();
+    $(ans.register_biome(Id::new($id_string));)+
+    ans
+}
+    };
+}
+
+reg_biomes!(
+    0, "minecraft:ocean",
+    1, "minecraft:plains",
+    2, "minecraft:desert",
+    3, "minecraft:mountains",
+    4, "minecraft:forest",
+    5, "minecraft:taiga",
+    6, "minecraft:swamp",
+    7, "minecraft:river",
+    8, "minecraft:nether_wastes",
+    9, "minecraft:the_end",
+    10, "minecraft:frozen_ocean",
+    11, "minecraft:frozen_river",
+    12, "minecraft:snowy_tundra",
+    13, "minecraft:snowy_mountains",
+    14, "minecraft:mushroom_fields",
+    15, "minecraft:mushroom_field_shore",
+    16, "minecraft:beach",
+    17, "minecraft:desert_hills",
+    18, "minecraft:wooded_hills",
+    19, "minecraft:taiga_hills",
+    20, "minecraft:mountain_edge",
+    21, "minecraft:jungle",
+    22, "minecraft:jungle_hills",
+    23, "minecraft:jungle_edge",
+    24, "minecraft:deep_ocean",
+    25, "minecraft:stone_shore",
+    26, "minecraft:snowy_beach",
+    27, "minecraft:birch_forest",
+    28, "minecraft:birch_forest_hills",
+    29, "minecraft:dark_forest",
+    30, "minecraft:snowy_taiga",
+    31, "minecraft:snowy_taiga_hills",
+    32, "minecraft:giant_tree_taiga",
+    33, "minecraft:giant_tree_taiga_hills",
+    34, "minecraft:wooded_mountains",
+    35, "minecraft:savanna",
+    36, "minecraft:savanna_plateau",
+    37, "minecraft:badlands",
+    38, "minecraft:wooded_badlands_plateau",
+    39, "minecraft:badlands_plateau",
+    40, "minecraft:small_end_islands",
+    41, "minecraft:end_midlands",
+    42, "minecraft:end_highlands",
+    43, "minecraft:end_barrens",
+    44, "minecraft:warm_ocean",
+    45, "minecraft:lukewarm_ocean",
+    46, "minecraft:cold_ocean",
+    47, "minecraft:deep_warm_ocean",
+    48, "minecraft:deep_lukewarm_ocean",
+    49, "minecraft:deep_cold_ocean",
+    50, "minecraft:deep_frozen_ocean",
+    127, "minecraft:the_void",
+    129, "minecraft:sunflower_plains",
+    130, "minecraft:desert_lakes",
+    131, "minecraft:gravelly_mountains",
+    132, "minecraft:flower_forest",
+    133, "minecraft:taiga_mountains",
+    134, "minecraft:swamp_hills",
+    140, "minecraft:ice_spikes",
+    149, "minecraft:modified_jungle",
+    151, "minecraft:modified_jungle_edge",
+    155, "minecraft:tall_birch_forest",
+    156, "minecraft:tall_birch_hills",
+    157, "minecraft:dark_forest_hills",
+    158, "minecraft:snowy_taiga_mountains",
+    160, "minecraft:giant_spruce_taiga",
+    161, "minecraft:giant_spruce_taiga_hills",
+    162, "minecraft:modified_gravelly_mountains",
+    163, "minecraft:shattered_savanna",
+    164, "minecraft:shattered_savanna_plateau",
+    165, "minecraft:eroded_badlands",
+    166, "minecraft:modified_wooded_badlands_plateau",
+    167, "minecraft:modified_badlands_plateau",
+    168, "minecraft:bamboo_jungle",
+    169, "minecraft:bamboo_jungle_hills",
+    170, "minecraft:soul_sand_valley",
+    171, "minecraft:crimson_forest",
+    172, "minecraft:warped_forest",
+    173, "minecraft:basalt_deltas",
+    174, "minecraft:dripstone_caves",
+    175, "minecraft:lush_caves",
+);
+
+// the id of a biome number stored before 1.18
+pub fn legacy_biome_id(number: i32) -> Option<&'static str> {
+    LEGACY_BIOMES.iter().find(|&&(n, _)| n == number).map(|&(_, id)| id)
+}
+
+// the number of a biome id, for chunks before 1.18
+pub fn legacy_biome_number(id: &str) -> Option<i32> {
+    LEGACY_BIOMES.iter().find(|&&(_, i)| i == id).map(|&(n, _)| n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn biome_ids() {
+        let ids = SharedHashSystem::new();
+        assert_eq!(ids.biome_id_to_meta(Id::new("minecraft:ocean")), Meta::new(0));
+        let grove = ids.register_biome(Id::new("minecraft:grove"));
+        assert_eq!(ids.register_biome(Id::new("minecraft:grove")), grove);
+        assert_eq!(ids.biome_meta_to_id(grove), "minecraft:grove");
+        assert_eq!(legacy_biome_id(2), Some("minecraft:desert"));
+        assert_eq!(legacy_biome_number("minecraft:ice_spikes"), Some(140));
+        assert_eq!(legacy_biome_id(51), None);
+    }
+}
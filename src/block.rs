@@ -22,6 +22,10 @@ impl Meta {
     crate fn new(inner: u16) -> Meta {
         Meta { inner }
     }
+
+    crate fn to_inner(self) -> u16 {
+        self.inner
+    }
 }
 
 // pub struct Buf {
@@ -115,7 +119,7 @@ pub trait IdSystem {
     // check if this meta is registered
     fn has_block_meta(&self, meta: Meta) -> bool;
     // panic if block meta not found
-    fn block_meta_to_id(&self, meta: Meta) -> Id;
+    fn block_meta_to_id(&self, meta: Meta) -> Id<'_>;
     // check if this block id is registered
     fn has_block_id(&self, id: Id) -> bool;
     // panic if block meta not found
@@ -196,7 +200,7 @@ impl<I: WriteExact> Write for Cursor<I> {
 
 impl<I: IdOperate + ReadExact> Cursor<I> {
     // must ensure that this is a valid position
-    pub fn get_block_id(&mut self, pos: Pos) -> Result<Id> {
+    pub fn get_block_id(&mut self, pos: Pos) -> Result<Id<'_>> {
         let meta = self.get_block_meta(pos)?;
         Ok(self.inner.block_id_system().block_meta_to_id(meta))
     }
@@ -228,6 +232,7 @@ impl<I> Seek for Cursor<I> {
 
 // default system def
 use std::collections::HashMap;
+use std::sync::RwLock;
 #[derive(Debug, Default)]
 pub struct HashSystem {
     mti: HashMap<Meta, String>,
@@ -243,8 +248,8 @@ impl HashSystem {
     pub fn register_block(&mut self, id: Id) -> Meta {
         let id = id.to_string();
         let meta = Meta::new(self.next_inner);
-        self.itm.insert(id.clone(), meta.clone());
-        self.mti.insert(meta.clone(), id);
+        self.itm.insert(id.clone(), meta);
+        self.mti.insert(meta, id);
         self.next_inner += 1;
         meta
     }
@@ -256,7 +261,7 @@ impl IdSystem for HashSystem {
         self.mti.contains_key(&meta)
     }
     
-    fn block_meta_to_id(&self, meta: Meta) -> Id {
+    fn block_meta_to_id(&self, meta: Meta) -> Id<'_> {
         Id::new(self.mti[&meta].clone())
     }   
     
@@ -269,6 +274,59 @@ impl IdSystem for HashSystem {
     }
 }
 
+// a `HashSystem` that can register more ids while shared, so that worlds
+// can give metas to whatever blocks their palettes name.
+// starts from `global_id_system`, leaving "minecraft:air" with a zero meta
+#[derive(Debug)]
+pub struct SharedHashSystem {
+    inner: RwLock<HashSystem>
+}
+
+impl SharedHashSystem {
+    pub fn new() -> SharedHashSystem {
+        SharedHashSystem {
+            inner: RwLock::new(global_id_system())
+        }
+    }
+
+    // the meta of this id, registering it if it's new
+    pub fn register_block(&self, id: Id) -> Meta {
+        if let Some(&meta) = self.inner.read().unwrap().itm.get(&id.to_string()) {
+            return meta;
+        }
+        let mut inner = self.inner.write().unwrap();
+        match inner.itm.get(&id.to_string()) {
+            Some(&meta) => meta,
+            None => inner.register_block(id)
+        }
+    }
+}
+
+impl Default for SharedHashSystem {
+    fn default() -> SharedHashSystem {
+        SharedHashSystem::new()
+    }
+}
+
+impl IdSystem for SharedHashSystem {
+
+    fn has_block_meta(&self, meta: Meta) -> bool {
+        self.inner.read().unwrap().has_block_meta(meta)
+    }
+
+    fn block_meta_to_id(&self, meta: Meta) -> Id<'_> {
+        Id::new(self.inner.read().unwrap().mti[&meta].clone())
+    }
+
+    fn has_block_id(&self, id: Id) -> bool {
+        self.inner.read().unwrap().has_block_id(id)
+    }
+
+    fn block_id_to_meta(&self, id: Id) -> Meta {
+        self.inner.read().unwrap().block_id_to_meta(id)
+    }
+}
+
 macro_rules! reg_blocks {
     ($($id_name: ident, $id_string: expr,)+) => {
 
@@ -288,6 +346,316 @@ reg_blocks!(
     ID_BLOCK_SPONGE, "minecraft:sponge",
 );
 
+// numeric block ids used before 1.13, as stored by McRegion and Alpha worlds.
+// the inner of Meta is `id << 4 | data`, ids are named after their 1.13 blocks.
+// only ids up to 1.1 (the last McRegion version) are known.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LegacyIdSystem;
+
+impl LegacyIdSystem {
+    pub fn new() -> LegacyIdSystem {
+        LegacyIdSystem
+    }
+
+    pub fn meta_from_legacy(id: u8, data: u8) -> Meta {
+        Meta::new(((id as u16) << 4) | (data & 0xF) as u16)
+    }
+
+    pub fn meta_to_legacy(meta: Meta) -> (u8, u8) {
+        ((meta.inner >> 4) as u8, (meta.inner & 0xF) as u8)
+    }
+
+    // variants are named with the properties that tell them apart, like
+    // `minecraft:stone_slab[type=double]`. the id must have all of them, and the
+    // variant with the most wins, so that a double slab is not a slab
+    fn find(id: &str) -> Option<Meta> {
+        let name = block_name(id);
+        let mut ans = None;
+        let mut most = 0;
+        for (legacy_id, (_, names)) in LEGACY_BLOCK_NAMES.iter().enumerate() {
+            for (variant, &variant_id) in names.iter().enumerate() {
+                if block_name(variant_id) != name {
+                    continue;
+                }
+                let properties = block_properties(variant_id);
+                if properties.iter().any(|&(key, value)| block_property(id, key) != Some(value)) {
+                    continue;
+                }
+                if ans.is_none() || properties.len() > most {
+                    ans = Some(LegacyIdSystem::meta_from_legacy(legacy_id as u8, variant as u8));
+                    most = properties.len();
+                }
+            }
+        }
+        ans
+    }
+
+    fn name_of(meta: Meta) -> Option<&'static str> {
+        let (id, data) = LegacyIdSystem::meta_to_legacy(meta);
+        let (mask, names) = LEGACY_BLOCK_NAMES.get(id as usize)?;
+        // bits out of the mask are states like facing, not variants
+        let variant = (data & mask) as usize;
+        Some(names.get(variant).unwrap_or(&names[0]))
+    }
+}
+
+impl IdSystem for LegacyIdSystem {
+
+    fn has_block_meta(&self, meta: Meta) -> bool {
+        meta.inner >> 12 == 0 && LegacyIdSystem::name_of(meta).is_some()
+    }
+
+    fn block_meta_to_id(&self, meta: Meta) -> Id<'_> {
+        Id::new(LegacyIdSystem::name_of(meta).expect("unknown legacy block id"))
+    }
+
+    // ids with block state properties count by their name
+    fn has_block_id(&self, id: Id) -> bool {
+        LegacyIdSystem::find(&id.to_string()).is_some()
+    }
+
+    // properties like `facing` are not kept, so an id with them gets the
+    // data of its plain variant. panic for blocks without a legacy id
+    fn block_id_to_meta(&self, id: Id) -> Meta {
+        LegacyIdSystem::find(&id.to_string()).expect("block id has no legacy id")
+    }
+}
+
+const WOOL_NAMES: &[&str] = &[
+    "minecraft:white_wool", "minecraft:orange_wool", "minecraft:magenta_wool", "minecraft:light_blue_wool",
+    "minecraft:yellow_wool", "minecraft:lime_wool", "minecraft:pink_wool", "minecraft:gray_wool",
+    "minecraft:light_gray_wool", "minecraft:cyan_wool", "minecraft:purple_wool", "minecraft:blue_wool",
+    "minecraft:brown_wool", "minecraft:green_wool", "minecraft:red_wool", "minecraft:black_wool",
+];
+
+const DOUBLE_SLAB_NAMES: &[&str] = &[
+    "minecraft:stone_slab[type=double]", "minecraft:sandstone_slab[type=double]", "minecraft:petrified_oak_slab[type=double]",
+    "minecraft:cobblestone_slab[type=double]", "minecraft:brick_slab[type=double]", "minecraft:stone_brick_slab[type=double]",
+];
+
+// flowing water and lava by their level, still ones take no level
+const FLOWING_WATER_NAMES: &[&str] = &[
+    "minecraft:water[level=0]", "minecraft:water[level=1]", "minecraft:water[level=2]", "minecraft:water[level=3]",
+    "minecraft:water[level=4]", "minecraft:water[level=5]", "minecraft:water[level=6]", "minecraft:water[level=7]",
+    "minecraft:water[level=8]", "minecraft:water[level=9]", "minecraft:water[level=10]", "minecraft:water[level=11]",
+    "minecraft:water[level=12]", "minecraft:water[level=13]", "minecraft:water[level=14]", "minecraft:water[level=15]",
+];
+
+const FLOWING_LAVA_NAMES: &[&str] = &[
+    "minecraft:lava[level=0]", "minecraft:lava[level=1]", "minecraft:lava[level=2]", "minecraft:lava[level=3]",
+    "minecraft:lava[level=4]", "minecraft:lava[level=5]", "minecraft:lava[level=6]", "minecraft:lava[level=7]",
+    "minecraft:lava[level=8]", "minecraft:lava[level=9]", "minecraft:lava[level=10]", "minecraft:lava[level=11]",
+    "minecraft:lava[level=12]", "minecraft:lava[level=13]", "minecraft:lava[level=14]", "minecraft:lava[level=15]",
+];
+
+const SLAB_NAMES: &[&str] = &[
+    "minecraft:stone_slab", "minecraft:sandstone_slab", "minecraft:petrified_oak_slab",
+    "minecraft:cobblestone_slab", "minecraft:brick_slab", "minecraft:stone_brick_slab",
+];
+
+// (variant mask of data, names by variant), indexed by legacy id
+static LEGACY_BLOCK_NAMES: &[(u8, &[&str])] = &[
+    (0, &["minecraft:air"]),
+    (0, &["minecraft:stone"]),
+    (0, &["minecraft:grass_block"]),
+    (0, &["minecraft:dirt"]),
+    (0, &["minecraft:cobblestone"]),
+    (0, &["minecraft:oak_planks"]),
+    (3, &["minecraft:oak_sapling", "minecraft:spruce_sapling", "minecraft:birch_sapling"]),
+    (0, &["minecraft:bedrock"]),
+    (15, FLOWING_WATER_NAMES),
+    (0, &["minecraft:water"]),
+    (15, FLOWING_LAVA_NAMES),
+    (0, &["minecraft:lava"]),
+    (0, &["minecraft:sand"]),
+    (0, &["minecraft:gravel"]),
+    (0, &["minecraft:gold_ore"]),
+    (0, &["minecraft:iron_ore"]),
+    (0, &["minecraft:coal_ore"]),
+    (3, &["minecraft:oak_log", "minecraft:spruce_log", "minecraft:birch_log"]),
+    (3, &["minecraft:oak_leaves", "minecraft:spruce_leaves", "minecraft:birch_leaves"]),
+    (0, &["minecraft:sponge"]),
+    (0, &["minecraft:glass"]),
+    (0, &["minecraft:lapis_ore"]),
+    (0, &["minecraft:lapis_block"]),
+    (0, &["minecraft:dispenser"]),
+    (3, &["minecraft:sandstone", "minecraft:chiseled_sandstone", "minecraft:cut_sandstone"]),
+    (0, &["minecraft:note_block"]),
+    (0, &["minecraft:red_bed"]),
+    (0, &["minecraft:powered_rail"]),
+    (0, &["minecraft:detector_rail"]),
+    (0, &["minecraft:sticky_piston"]),
+    (0, &["minecraft:cobweb"]),
+    (3, &["minecraft:dead_bush", "minecraft:grass", "minecraft:fern"]),
+    (0, &["minecraft:dead_bush"]), // the same as the shrub of id 31
+    (0, &["minecraft:piston"]),
+    (0, &["minecraft:piston_head"]),
+    (15, WOOL_NAMES),
+    (0, &["minecraft:moving_piston"]),
+    (0, &["minecraft:dandelion"]),
+    (0, &["minecraft:poppy"]),
+    (0, &["minecraft:brown_mushroom"]),
+    (0, &["minecraft:red_mushroom"]),
+    (0, &["minecraft:gold_block"]),
+    (0, &["minecraft:iron_block"]),
+    (7, DOUBLE_SLAB_NAMES),
+    (7, SLAB_NAMES),
+    (0, &["minecraft:bricks"]),
+    (0, &["minecraft:tnt"]),
+    (0, &["minecraft:bookshelf"]),
+    (0, &["minecraft:mossy_cobblestone"]),
+    (0, &["minecraft:obsidian"]),
+    (0, &["minecraft:torch"]),
+    (0, &["minecraft:fire"]),
+    (0, &["minecraft:spawner"]),
+    (0, &["minecraft:oak_stairs"]),
+    (0, &["minecraft:chest"]),
+    (0, &["minecraft:redstone_wire"]),
+    (0, &["minecraft:diamond_ore"]),
+    (0, &["minecraft:diamond_block"]),
+    (0, &["minecraft:crafting_table"]),
+    (0, &["minecraft:wheat"]),
+    (0, &["minecraft:farmland"]),
+    (0, &["minecraft:furnace"]),
+    (0, &["minecraft:furnace[lit=true]"]),
+    (0, &["minecraft:sign"]),
+    (0, &["minecraft:oak_door"]),
+    (0, &["minecraft:ladder"]),
+    (0, &["minecraft:rail"]),
+    (0, &["minecraft:cobblestone_stairs"]),
+    (0, &["minecraft:wall_sign"]),
+    (0, &["minecraft:lever"]),
+    (0, &["minecraft:stone_pressure_plate"]),
+    (0, &["minecraft:iron_door"]),
+    (0, &["minecraft:oak_pressure_plate"]),
+    (0, &["minecraft:redstone_ore"]),
+    (0, &["minecraft:redstone_ore[lit=true]"]),
+    (0, &["minecraft:redstone_torch[lit=false]"]),
+    (0, &["minecraft:redstone_torch"]),
+    (0, &["minecraft:stone_button"]),
+    (0, &["minecraft:snow"]),
+    (0, &["minecraft:ice"]),
+    (0, &["minecraft:snow_block"]),
+    (0, &["minecraft:cactus"]),
+    (0, &["minecraft:clay"]),
+    (0, &["minecraft:sugar_cane"]),
+    (0, &["minecraft:jukebox"]),
+    (0, &["minecraft:oak_fence"]),
+    (0, &["minecraft:carved_pumpkin"]),
+    (0, &["minecraft:netherrack"]),
+    (0, &["minecraft:soul_sand"]),
+    (0, &["minecraft:glowstone"]),
+    (0, &["minecraft:nether_portal"]),
+    (0, &["minecraft:jack_o_lantern"]),
+    (0, &["minecraft:cake"]),
+    (0, &["minecraft:repeater"]),
+    (0, &["minecraft:repeater[powered=true]"]),
+    (0, &["minecraft:chest"]), // the april fools locked chest
+    (0, &["minecraft:oak_trapdoor"]),
+    (3, &["minecraft:infested_stone", "minecraft:infested_cobblestone", "minecraft:infested_stone_bricks"]),
+    (3, &["minecraft:stone_bricks", "minecraft:mossy_stone_bricks", "minecraft:cracked_stone_bricks", "minecraft:chiseled_stone_bricks"]),
+    (0, &["minecraft:brown_mushroom_block"]),
+    (0, &["minecraft:red_mushroom_block"]),
+    (0, &["minecraft:iron_bars"]),
+    (0, &["minecraft:glass_pane"]),
+    (0, &["minecraft:melon"]),
+    (0, &["minecraft:pumpkin_stem"]),
+    (0, &["minecraft:melon_stem"]),
+    (0, &["minecraft:vine"]),
+    (0, &["minecraft:oak_fence_gate"]),
+    (0, &["minecraft:brick_stairs"]),
+    (0, &["minecraft:stone_brick_stairs"]),
+    (0, &["minecraft:mycelium"]),
+    (0, &["minecraft:lily_pad"]),
+    (0, &["minecraft:nether_bricks"]),
+    (0, &["minecraft:nether_brick_fence"]),
+    (0, &["minecraft:nether_brick_stairs"]),
+    (0, &["minecraft:nether_wart"]),
+    (0, &["minecraft:enchanting_table"]),
+    (0, &["minecraft:brewing_stand"]),
+    (0, &["minecraft:cauldron"]),
+    (0, &["minecraft:end_portal"]),
+    (0, &["minecraft:end_portal_frame"]),
+    (0, &["minecraft:end_stone"]),
+    (0, &["minecraft:dragon_egg"]),
+];
+
+// physical properties of blocks by their 1.13+ ids, as heightmaps need them.
+// ids may carry block state properties like `minecraft:oak_stairs[waterlogged=true]`
+
+// the id without its properties
+pub fn block_name(id: &str) -> &str {
+    match id.find('[') {
+        Some(i) => &id[..i],
+        None => id
+    }
+}
+
+fn block_properties(id: &str) -> Vec<(&str, &str)> {
+    let start = match id.find('[') {
+        Some(start) => start,
+        None => return Vec::new()
+    };
+    id[start + 1..].trim_end_matches(']').split(',')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            Some((parts.next()?, parts.next()?))
+        })
+        .collect()
+}
+
+fn block_property<'a>(id: &'a str, key: &str) -> Option<&'a str> {
+    block_properties(id).into_iter().find(|&(k, _)| k == key).map(|(_, v)| v)
+}
+
+pub fn is_air(id: &str) -> bool {
+    let name = block_name(id);
+    name == "minecraft:air" || name == "minecraft:cave_air" || name == "minecraft:void_air"
+}
+
+// water or lava, on their own or in a waterlogged block
+pub fn has_fluid(id: &str) -> bool {
+    match block_name(id) {
+        "minecraft:water" | "minecraft:lava" | "minecraft:bubble_column" | "minecraft:kelp" | "minecraft:kelp_plant"
+        | "minecraft:seagrass" | "minecraft:tall_seagrass" => true,
+        _ => block_property(id, "waterlogged") == Some("true")
+    }
+}
+
+pub fn is_leaves(id: &str) -> bool {
+    block_name(id).ends_with("_leaves")
+}
+
+// signs, banners and pressure plates are missing on purpose: the game takes
+// them as solid, and so counts them in heightmaps
+static NON_BLOCKING_NAMES: &[&str] = &[
+    "air", "cave_air", "void_air", "water", "lava", "bubble_column", "grass", "short_grass", "tall_grass", "fern", "large_fern",
+    "dead_bush", "seagrass", "tall_seagrass", "kelp", "kelp_plant", "sea_pickle", "vine", "ladder", "torch",
+    "wall_torch", "redstone_torch", "redstone_wall_torch", "soul_torch", "soul_wall_torch", "redstone_wire",
+    "tripwire", "tripwire_hook", "lever", "rail", "powered_rail", "detector_rail", "activator_rail", "snow",
+    "fire", "soul_fire", "nether_portal", "end_portal", "end_gateway", "cobweb", "sugar_cane", "wheat",
+    "carrots", "potatoes", "beetroots", "nether_wart", "sweet_berry_bush", "cocoa", "lily_pad", "structure_void",
+    "light", "moving_piston", "dandelion", "poppy", "blue_orchid", "allium", "azure_bluet", "oxeye_daisy",
+    "cornflower", "lily_of_the_valley", "wither_rose", "sunflower", "lilac", "rose_bush", "peony",
+    "brown_mushroom", "red_mushroom", "crimson_fungus", "warped_fungus", "nether_sprouts", "pumpkin_stem",
+    "melon_stem", "attached_pumpkin_stem", "attached_melon_stem", "twisting_vines", "twisting_vines_plant",
+    "weeping_vines", "weeping_vines_plant", "cave_vines", "cave_vines_plant", "glow_lichen", "hanging_roots",
+    "spore_blossom", "small_dripleaf", "big_dripleaf_stem", "scaffolding", "pink_petals", "frogspawn",
+    "torchflower", "torchflower_crop", "pitcher_plant", "pitcher_crop", "bamboo_sapling", "short_dry_grass",
+    "tall_dry_grass", "bush", "firefly_bush", "leaf_litter", "wildflowers",
+];
+
+static NON_BLOCKING_SUFFIXES: &[&str] = &[
+    "_sapling", "_tulip", "_button", "_carpet", "_roots", "_coral", "_coral_fan", "_coral_wall_fan", "candle",
+];
+
+// whether entities are stopped by the block, like stone but unlike flowers or water
+pub fn blocks_motion(id: &str) -> bool {
+    let name = block_name(id);
+    let name = name.trim_start_matches("minecraft:");
+    !NON_BLOCKING_NAMES.contains(&name) && !NON_BLOCKING_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
 // define our own cursors
 // match with System, following PC rules
 
@@ -420,7 +788,7 @@ mod tests {
     use crate::block::*;
     use std::io::{Error, Result};
     // every block is "minecraft:air" except (0, 60, 0) is "minecraft:sponge"
-    struct TestWorld(Box<IdSystem>);
+    struct TestWorld(Box<dyn IdSystem>);
 
     impl TestWorld {
         pub fn new() -> TestWorld {
@@ -474,6 +842,54 @@ mod tests {
         cur.set_block_id(Pos::from_xyz(1, 1, 1), Id::new("minecraft:stone")).unwrap();
     }
     
+    #[test]
+    fn legacy_id_system() {
+        let system = LegacyIdSystem::new();
+        let meta = LegacyIdSystem::meta_from_legacy(35, 14);
+        assert_eq!(system.block_meta_to_id(meta), "minecraft:red_wool");
+        assert_eq!(system.block_id_to_meta(Id::new("minecraft:red_wool")), meta);
+        // facing bits of a log are not part of the name
+        assert_eq!(system.block_meta_to_id(LegacyIdSystem::meta_from_legacy(17, 2 | 4)), "minecraft:birch_log");
+        assert_eq!(LegacyIdSystem::meta_to_legacy(system.block_id_to_meta(Id::new("minecraft:dragon_egg"))), (122, 0));
+        assert!(!system.has_block_meta(LegacyIdSystem::meta_from_legacy(123, 0)));
+        // states of known blocks take their plain variant
+        assert!(system.has_block_id(Id::new("minecraft:spruce_log[axis=x]")));
+        assert_eq!(system.block_id_to_meta(Id::new("minecraft:spruce_log[axis=x]")), LegacyIdSystem::meta_from_legacy(17, 1));
+        assert_eq!(system.block_id_to_meta(Id::new("minecraft:furnace[facing=north,lit=false]")), LegacyIdSystem::meta_from_legacy(61, 0));
+        assert_eq!(system.block_id_to_meta(Id::new("minecraft:furnace[facing=north,lit=true]")), LegacyIdSystem::meta_from_legacy(62, 0));
+        // double slabs and flowing fluids keep their own ids
+        assert_eq!(system.block_id_to_meta(Id::new("minecraft:brick_slab[type=bottom,waterlogged=false]")), LegacyIdSystem::meta_from_legacy(44, 4));
+        assert_eq!(system.block_id_to_meta(Id::new("minecraft:brick_slab[type=double,waterlogged=false]")), LegacyIdSystem::meta_from_legacy(43, 4));
+        assert_eq!(system.block_id_to_meta(Id::new("minecraft:water[level=3]")), LegacyIdSystem::meta_from_legacy(8, 3));
+        // every known legacy block goes back to itself, but for the dead bush
+        // and the locked chest that 1.13 merged into others
+        for id in (0..=122u8).filter(|&id| id != 32 && id != 95) {
+            for data in 0..16 {
+                let meta = LegacyIdSystem::meta_from_legacy(id, data);
+                let (mask, names) = LEGACY_BLOCK_NAMES[id as usize];
+                if (data & mask) as usize >= names.len() {
+                    continue;
+                }
+                let back = system.block_id_to_meta(system.block_meta_to_id(meta));
+                assert_eq!(back, LegacyIdSystem::meta_from_legacy(id, data & mask), "{} {}", id, data);
+            }
+        }
+        assert!(!system.has_block_id(Id::new("minecraft:deepslate")));
+    }
+
+    #[test]
+    fn block_properties() {
+        assert!(is_air("minecraft:cave_air") && !is_air("minecraft:stone"));
+        assert!(blocks_motion("minecraft:oak_stairs[facing=east,waterlogged=false]"));
+        assert!(!blocks_motion("minecraft:red_tulip") && !blocks_motion("minecraft:water[level=0]"));
+        assert!(!blocks_motion("minecraft:short_grass") && !blocks_motion("minecraft:pitcher_crop[age=0,half=lower]"));
+        assert!(blocks_motion("minecraft:oak_sign[rotation=0,waterlogged=false]"));
+        assert!(has_fluid("minecraft:water[level=0]") && has_fluid("minecraft:oak_stairs[facing=east,waterlogged=true]"));
+        assert!(!has_fluid("minecraft:oak_stairs[facing=east,waterlogged=false]"));
+        assert!(is_leaves("minecraft:oak_leaves[distance=1,persistent=true]"));
+        assert_eq!(block_name("minecraft:oak_log[axis=y]"), "minecraft:oak_log");
+    }
+
     #[test]
     fn seek() -> Result<()> {
         let mut cur = Cursor::new(TestWorld::new());
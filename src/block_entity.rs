@@ -0,0 +1,532 @@
+// block entities (tile entities) hold what a block state cannot, like the
+// items of a chest or the text of a sign. they are saved along with chunks.
+// https://minecraft.gamepedia.com/Chunk_format#Block_entity_format
+
+use std::io::{Error, ErrorKind, Result};
+use crate::block;
+use crate::nbt::{Compound, Tag, take_compound_list, take_field, take_field_opt};
+use crate::storage::ItemStack;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BlockEntity {
+    // like "minecraft:chest"
+    pub id: String,
+    // world position, not relative to the chunk
+    pub pos: block::Pos,
+    // every other field, as the typed views below read and write them
+    data: Compound,
+}
+
+impl BlockEntity {
+    pub fn new(id: impl Into<String>, pos: block::Pos) -> BlockEntity {
+        BlockEntity {
+            id: id.into(),
+            pos,
+            data: Compound::new()
+        }
+    }
+
+    pub fn from_nbt(mut map: Compound) -> Result<BlockEntity> {
+        let id = take_field(&mut map, "id", |t| t.as_str().map(String::from))?;
+        let x = take_field(&mut map, "x", Tag::as_int)?;
+        let y = take_field(&mut map, "y", Tag::as_int)?;
+        let z = take_field(&mut map, "z", Tag::as_int)?;
+        Ok(BlockEntity {
+            id,
+            pos: block::Pos::from_xyz(x, y, z),
+            data: map
+        })
+    }
+
+    pub fn to_nbt(&self) -> Compound {
+        let mut map = self.data.clone();
+        let (x, y, z) = self.pos.to_xyz();
+        map.insert("id".to_string(), Tag::String(self.id.clone()));
+        map.insert("x".to_string(), Tag::Int(x));
+        map.insert("y".to_string(), Tag::Int(y));
+        map.insert("z".to_string(), Tag::Int(z));
+        map
+    }
+
+    // fields besides the id and position
+    pub fn data(&self) -> &Compound {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut Compound {
+        &mut self.data
+    }
+
+    // whether this block entity belongs on the block, by their ids.
+    // `None` for block entities not known here
+    pub fn matches_block(&self, block_id: &str) -> Option<bool> {
+        let name = block::block_name(block_id);
+        let name = name.trim_start_matches("minecraft:");
+        let ans = match self.id.trim_start_matches("minecraft:") {
+            "sign" => name.ends_with("_sign") && !name.ends_with("_hanging_sign"),
+            "hanging_sign" => name.ends_with("_hanging_sign"),
+            "chest" | "trapped_chest" | "barrel" | "ender_chest" | "furnace" | "blast_furnace" | "smoker"
+            | "dispenser" | "dropper" | "hopper" | "beacon" | "enchanting_table" | "brewing_stand" | "jukebox"
+            | "end_gateway" | "end_portal" | "structure_block" | "jigsaw" | "daylight_detector" | "conduit"
+            | "lectern" | "bell" | "comparator" => name == self.id.trim_start_matches("minecraft:"),
+            "mob_spawner" => name == "spawner",
+            "shulker_box" => name.ends_with("shulker_box"),
+            "banner" => name.ends_with("_banner"),
+            "skull" => name.ends_with("_skull") || name.ends_with("_head"),
+            "bed" => name.ends_with("_bed"),
+            "command_block" => name.ends_with("command_block"),
+            "campfire" => name.ends_with("campfire"),
+            "beehive" => name == "beehive" || name == "bee_nest",
+            "piston" => name == "moving_piston",
+            _ => return None
+        };
+        Some(ans)
+    }
+}
+
+// typed views only read the block entities they are for
+fn check_id(entity: &BlockEntity, ids: &[&str]) -> Result<()> {
+    if !ids.contains(&entity.id.trim_start_matches("minecraft:")) {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Unexpected block entity: {}", entity.id)));
+    }
+    Ok(())
+}
+
+fn set_opt_string(map: &mut Compound, name: &str, value: &Option<String>) {
+    match value {
+        Some(value) => map.insert(name.to_string(), Tag::String(value.clone())),
+        None => map.remove(name)
+    };
+}
+
+fn set_opt_tag(map: &mut Compound, name: &str, value: &Option<Tag>) {
+    match value {
+        Some(value) => map.insert(name.to_string(), value.clone()),
+        None => map.remove(name)
+    };
+}
+
+// text components, like custom names and sign lines, are json strings before
+// 1.21.5 and NBT since. they are kept as stored
+fn take_text(map: &mut Compound, name: &str) -> Result<Option<Tag>> {
+    take_field_opt(map, name, |t| Some(t.clone()))
+}
+
+fn empty_lines() -> [Tag; 4] {
+    [Tag::String(String::new()), Tag::String(String::new()), Tag::String(String::new()), Tag::String(String::new())]
+}
+
+// first DataVersion with text on both sides of signs (23w12a, 1.20)
+crate const DATA_VERSION_SIGN_SIDES: i32 = 3442;
+
+// one side of a sign
+#[derive(Clone, PartialEq, Debug)]
+pub struct SignText {
+    // text components, like `{"text":"hello"}` as a json string
+    pub lines: [Tag; 4],
+    // dye color, like "black"
+    pub color: String,
+    pub glowing: bool,
+}
+
+impl Default for SignText {
+    fn default() -> SignText {
+        SignText {
+            lines: empty_lines(),
+            color: "black".to_string(),
+            glowing: false
+        }
+    }
+}
+
+impl SignText {
+    fn from_side(mut map: Compound) -> Result<SignText> {
+        let messages = take_field(&mut map, "messages", |t| t.as_list().cloned())?;
+        let mut lines = empty_lines();
+        if messages.len() != 4 {
+            return Err(Error::new(ErrorKind::InvalidData, "Sign side without 4 lines"));
+        }
+        lines.clone_from_slice(&messages);
+        Ok(SignText {
+            lines,
+            color: take_field_opt(&mut map, "color", |t| t.as_str().map(String::from))?.unwrap_or_else(|| "black".to_string()),
+            glowing: take_field_opt(&mut map, "has_glowing_text", Tag::as_bool)?.unwrap_or(false)
+        })
+    }
+
+    fn to_side(&self, mut map: Compound) -> Compound {
+        let messages = self.lines.to_vec();
+        map.insert("messages".to_string(), Tag::List(messages));
+        map.insert("color".to_string(), Tag::String(self.color.clone()));
+        map.insert("has_glowing_text".to_string(), Tag::Byte(self.glowing as i8));
+        map
+    }
+}
+
+// signs and hanging signs. before 1.20 a sign only has its front side
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Sign {
+    pub front: SignText,
+    pub back: SignText,
+    pub waxed: bool,
+}
+
+impl Sign {
+    pub fn from_block_entity(entity: &BlockEntity) -> Result<Sign> {
+        check_id(entity, &["sign", "hanging_sign"])?;
+        let mut map = entity.data.clone();
+        if let Some(front) = take_field_opt(&mut map, "front_text", |t| t.as_compound().cloned())? {
+            return Ok(Sign {
+                front: SignText::from_side(front)?,
+                back: match take_field_opt(&mut map, "back_text", |t| t.as_compound().cloned())? {
+                    Some(back) => SignText::from_side(back)?,
+                    None => SignText::default()
+                },
+                waxed: take_field_opt(&mut map, "is_waxed", Tag::as_bool)?.unwrap_or(false)
+            });
+        }
+        let mut lines = empty_lines();
+        for (i, line) in lines.iter_mut().enumerate() {
+            if let Some(text) = take_text(&mut map, &format!("Text{}", i + 1))? {
+                *line = text;
+            }
+        }
+        Ok(Sign {
+            front: SignText {
+                lines,
+                color: take_field_opt(&mut map, "Color", |t| t.as_str().map(String::from))?.unwrap_or_else(|| "black".to_string()),
+                glowing: take_field_opt(&mut map, "GlowingText", Tag::as_bool)?.unwrap_or(false)
+            },
+            back: SignText::default(),
+            waxed: false
+        })
+    }
+
+    // in the layout of the DataVersion, dropping the back side before 1.20
+    pub fn apply(&self, entity: &mut BlockEntity, data_version: i32) {
+        let map = &mut entity.data;
+        if data_version >= DATA_VERSION_SIGN_SIDES {
+            for i in 1..=4 {
+                map.remove(&format!("Text{}", i));
+            }
+            map.remove("Color");
+            map.remove("GlowingText");
+            let front = map.remove("front_text").and_then(|t| t.as_compound().cloned()).unwrap_or_default();
+            let back = map.remove("back_text").and_then(|t| t.as_compound().cloned()).unwrap_or_default();
+            map.insert("front_text".to_string(), Tag::Compound(self.front.to_side(front)));
+            map.insert("back_text".to_string(), Tag::Compound(self.back.to_side(back)));
+            map.insert("is_waxed".to_string(), Tag::Byte(self.waxed as i8));
+        } else {
+            map.remove("front_text");
+            map.remove("back_text");
+            map.remove("is_waxed");
+            for (i, line) in self.front.lines.iter().enumerate() {
+                map.insert(format!("Text{}", i + 1), line.clone());
+            }
+            map.insert("Color".to_string(), Tag::String(self.front.color.clone()));
+            map.insert("GlowingText".to_string(), Tag::Byte(self.front.glowing as i8));
+        }
+    }
+}
+
+// chests, barrels, shulker boxes, hoppers, dispensers and droppers
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Container {
+    // each with its slot
+    pub items: Vec<ItemStack>,
+    // a text component
+    pub custom_name: Option<Tag>,
+    // items are generated from it on first opening
+    pub loot_table: Option<String>,
+}
+
+impl Container {
+    pub fn from_block_entity(entity: &BlockEntity) -> Result<Container> {
+        check_id(entity, &["chest", "trapped_chest", "barrel", "shulker_box", "hopper", "dispenser", "dropper"])?;
+        let mut map = entity.data.clone();
+        Ok(Container {
+            items: take_compound_list(&mut map, "Items")?.into_iter().map(ItemStack::from_nbt).collect::<Result<_>>()?,
+            custom_name: take_text(&mut map, "CustomName")?,
+            loot_table: take_field_opt(&mut map, "LootTable", |t| t.as_str().map(String::from))?
+        })
+    }
+
+    // item counts in the layout of the DataVersion
+    pub fn apply(&self, entity: &mut BlockEntity, data_version: i32) {
+        let map = &mut entity.data;
+        let items = self.items.iter().map(|item| Tag::Compound(item.to_nbt(data_version))).collect();
+        map.insert("Items".to_string(), Tag::List(items));
+        set_opt_tag(map, "CustomName", &self.custom_name);
+        set_opt_string(map, "LootTable", &self.loot_table);
+    }
+}
+
+// furnaces, blast furnaces and smokers
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Furnace {
+    // slot 0 is the input, 1 the fuel and 2 the result
+    pub items: Vec<ItemStack>,
+    // ticks of fuel left
+    pub burn_time: i16,
+    // ticks the input has cooked, out of `cook_time_total`
+    pub cook_time: i16,
+    pub cook_time_total: i16,
+}
+
+impl Furnace {
+    pub fn from_block_entity(entity: &BlockEntity) -> Result<Furnace> {
+        check_id(entity, &["furnace", "blast_furnace", "smoker"])?;
+        let mut map = entity.data.clone();
+        Ok(Furnace {
+            items: take_compound_list(&mut map, "Items")?.into_iter().map(ItemStack::from_nbt).collect::<Result<_>>()?,
+            burn_time: take_field_opt(&mut map, "BurnTime", Tag::as_short)?.unwrap_or(0),
+            cook_time: take_field_opt(&mut map, "CookTime", Tag::as_short)?.unwrap_or(0),
+            cook_time_total: take_field_opt(&mut map, "CookTimeTotal", Tag::as_short)?.unwrap_or(0)
+        })
+    }
+
+    // item counts in the layout of the DataVersion
+    pub fn apply(&self, entity: &mut BlockEntity, data_version: i32) {
+        let map = &mut entity.data;
+        let items = self.items.iter().map(|item| Tag::Compound(item.to_nbt(data_version))).collect();
+        map.insert("Items".to_string(), Tag::List(items));
+        map.insert("BurnTime".to_string(), Tag::Short(self.burn_time));
+        map.insert("CookTime".to_string(), Tag::Short(self.cook_time));
+        map.insert("CookTimeTotal".to_string(), Tag::Short(self.cook_time_total));
+    }
+}
+
+// first DataVersion with the spawned entity under `SpawnData.entity` (21w37a, 1.18)
+crate const DATA_VERSION_SPAWN_DATA_ENTITY: i32 = 2834;
+
+// monster spawners, delays in ticks
+#[derive(Clone, PartialEq, Debug)]
+pub struct Spawner {
+    // the entity spawned next, like "minecraft:zombie"
+    pub entity_id: Option<String>,
+    pub delay: i16,
+    pub min_spawn_delay: i16,
+    pub max_spawn_delay: i16,
+    pub spawn_count: i16,
+    pub spawn_range: i16,
+    pub required_player_range: i16,
+    pub max_nearby_entities: i16,
+}
+
+impl Default for Spawner {
+    // as placed by the game
+    fn default() -> Spawner {
+        Spawner {
+            entity_id: None,
+            delay: 20,
+            min_spawn_delay: 200,
+            max_spawn_delay: 800,
+            spawn_count: 4,
+            spawn_range: 4,
+            required_player_range: 16,
+            max_nearby_entities: 6
+        }
+    }
+}
+
+impl Spawner {
+    pub fn from_block_entity(entity: &BlockEntity) -> Result<Spawner> {
+        check_id(entity, &["mob_spawner"])?;
+        let mut map = entity.data.clone();
+        let default = Spawner::default();
+        let spawn_data = take_field_opt(&mut map, "SpawnData", |t| t.as_compound().cloned())?.unwrap_or_default();
+        let entity_id = match spawn_data.get("entity") {
+            Some(Tag::Compound(entity)) => entity.get("id"),
+            _ => spawn_data.get("id")
+        };
+        let short = |map: &mut Compound, name: &str, default: i16| -> Result<i16> {
+            Ok(take_field_opt(map, name, Tag::as_short)?.unwrap_or(default))
+        };
+        Ok(Spawner {
+            entity_id: entity_id.and_then(Tag::as_str).map(String::from),
+            delay: short(&mut map, "Delay", default.delay)?,
+            min_spawn_delay: short(&mut map, "MinSpawnDelay", default.min_spawn_delay)?,
+            max_spawn_delay: short(&mut map, "MaxSpawnDelay", default.max_spawn_delay)?,
+            spawn_count: short(&mut map, "SpawnCount", default.spawn_count)?,
+            spawn_range: short(&mut map, "SpawnRange", default.spawn_range)?,
+            required_player_range: short(&mut map, "RequiredPlayerRange", default.required_player_range)?,
+            max_nearby_entities: short(&mut map, "MaxNearbyEntities", default.max_nearby_entities)?
+        })
+    }
+
+    // the spawned entity goes where the DataVersion keeps it, the rest of
+    // `SpawnData` and `SpawnPotentials` are left as they are
+    pub fn apply(&self, entity: &mut BlockEntity, data_version: i32) {
+        let map = &mut entity.data;
+        let mut spawn_data = map.remove("SpawnData").and_then(|t| t.as_compound().cloned()).unwrap_or_default();
+        if data_version >= DATA_VERSION_SPAWN_DATA_ENTITY {
+            spawn_data.remove("id");
+            let mut spawned = spawn_data.remove("entity").and_then(|t| t.as_compound().cloned()).unwrap_or_default();
+            set_opt_string(&mut spawned, "id", &self.entity_id);
+            spawn_data.insert("entity".to_string(), Tag::Compound(spawned));
+        } else {
+            spawn_data.remove("entity");
+            set_opt_string(&mut spawn_data, "id", &self.entity_id);
+        }
+        map.insert("SpawnData".to_string(), Tag::Compound(spawn_data));
+        for &(name, value) in [
+            ("Delay", self.delay), ("MinSpawnDelay", self.min_spawn_delay), ("MaxSpawnDelay", self.max_spawn_delay),
+            ("SpawnCount", self.spawn_count), ("SpawnRange", self.spawn_range),
+            ("RequiredPlayerRange", self.required_player_range), ("MaxNearbyEntities", self.max_nearby_entities),
+        ].iter() {
+            map.insert(name.to_string(), Tag::Short(value));
+        }
+    }
+}
+
+// first DataVersion with banner patterns by id and dye colors by name (24w09a, 1.20.5)
+crate const DATA_VERSION_BANNER_PATTERN_IDS: i32 = 3819;
+
+// dye colors by their number in `Color` of banner patterns before 1.20.5
+const DYE_COLORS: &[&str] = &[
+    "white", "orange", "magenta", "light_blue", "yellow", "lime", "pink", "gray",
+    "light_gray", "cyan", "purple", "blue", "brown", "green", "red", "black",
+];
+
+// (code before 1.20.5, id since) of banner patterns
+const BANNER_PATTERNS: &[(&str, &str)] = &[
+    ("b", "minecraft:base"), ("bl", "minecraft:square_bottom_left"), ("br", "minecraft:square_bottom_right"),
+    ("tl", "minecraft:square_top_left"), ("tr", "minecraft:square_top_right"), ("bs", "minecraft:stripe_bottom"),
+    ("ts", "minecraft:stripe_top"), ("ls", "minecraft:stripe_left"), ("rs", "minecraft:stripe_right"),
+    ("cs", "minecraft:stripe_center"), ("ms", "minecraft:stripe_middle"), ("drs", "minecraft:stripe_downright"),
+    ("dls", "minecraft:stripe_downleft"), ("ss", "minecraft:small_stripes"), ("cr", "minecraft:cross"),
+    ("sc", "minecraft:straight_cross"), ("bt", "minecraft:triangle_bottom"), ("tt", "minecraft:triangle_top"),
+    ("bts", "minecraft:triangles_bottom"), ("tts", "minecraft:triangles_top"), ("ld", "minecraft:diagonal_left"),
+    ("rd", "minecraft:diagonal_up_right"), ("lud", "minecraft:diagonal_up_left"), ("rud", "minecraft:diagonal_right"),
+    ("mc", "minecraft:circle"), ("mr", "minecraft:rhombus"), ("vh", "minecraft:half_vertical"),
+    ("hh", "minecraft:half_horizontal"), ("vhr", "minecraft:half_vertical_right"), ("hhb", "minecraft:half_horizontal_bottom"),
+    ("bo", "minecraft:border"), ("cbo", "minecraft:curly_border"), ("gra", "minecraft:gradient"),
+    ("gru", "minecraft:gradient_up"), ("bri", "minecraft:bricks"), ("glb", "minecraft:globe"),
+    ("cre", "minecraft:creeper"), ("sku", "minecraft:skull"), ("flo", "minecraft:flower"),
+    ("moj", "minecraft:mojang"), ("pig", "minecraft:piglin"),
+];
+
+// one layer of a banner, named as since 1.20.5
+#[derive(Clone, PartialEq, Debug)]
+pub struct BannerPattern {
+    // like "minecraft:stripe_bottom"
+    pub pattern: String,
+    // like "white"
+    pub color: String,
+}
+
+impl BannerPattern {
+    pub fn new(pattern: impl Into<String>, color: impl Into<String>) -> BannerPattern {
+        BannerPattern { pattern: pattern.into(), color: color.into() }
+    }
+}
+
+// standing and wall banners, their base color being part of the block.
+// patterns are read from either layout and written in the one of the DataVersion
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Banner {
+    // a text component
+    pub custom_name: Option<Tag>,
+    // from the bottom layer
+    pub patterns: Vec<BannerPattern>,
+}
+
+impl Banner {
+    pub fn from_block_entity(entity: &BlockEntity) -> Result<Banner> {
+        check_id(entity, &["banner"])?;
+        let mut map = entity.data.clone();
+        let mut patterns = Vec::new();
+        for mut pattern in take_compound_list(&mut map, "patterns")? {
+            patterns.push(BannerPattern {
+                pattern: take_field(&mut pattern, "pattern", |t| t.as_str().map(String::from))?,
+                color: take_field(&mut pattern, "color", |t| t.as_str().map(String::from))?
+            });
+        }
+        for mut pattern in take_compound_list(&mut map, "Patterns")? {
+            let code = take_field(&mut pattern, "Pattern", |t| t.as_str().map(String::from))?;
+            let color = take_field(&mut pattern, "Color", Tag::as_int)?;
+            let pattern = BANNER_PATTERNS.iter().find(|&&(c, _)| c == code).map(|&(_, id)| id)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Unknown banner pattern: {}", code)))?;
+            let color = DYE_COLORS.get(color as usize)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Invalid banner pattern color: {}", color)))?;
+            patterns.push(BannerPattern::new(pattern, *color));
+        }
+        Ok(Banner {
+            custom_name: take_text(&mut map, "CustomName")?,
+            patterns
+        })
+    }
+
+    // in the layout of the DataVersion. fails before 1.20.5 for patterns and
+    // colors that had no code or number yet
+    pub fn apply(&self, entity: &mut BlockEntity, data_version: i32) -> Result<()> {
+        let mut patterns = Vec::new();
+        for layer in self.patterns.iter() {
+            let mut map = Compound::new();
+            if data_version >= DATA_VERSION_BANNER_PATTERN_IDS {
+                map.insert("pattern".to_string(), Tag::String(layer.pattern.clone()));
+                map.insert("color".to_string(), Tag::String(layer.color.clone()));
+            } else {
+                let code = BANNER_PATTERNS.iter().find(|&&(_, id)| id == layer.pattern).map(|&(code, _)| code)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Banner pattern without a code: {}", layer.pattern)))?;
+                let color = DYE_COLORS.iter().position(|&c| c == layer.color)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Unknown dye color: {}", layer.color)))?;
+                map.insert("Pattern".to_string(), Tag::String(code.to_string()));
+                map.insert("Color".to_string(), Tag::Int(color as i32));
+            }
+            patterns.push(Tag::Compound(map));
+        }
+        let map = &mut entity.data;
+        set_opt_tag(map, "CustomName", &self.custom_name);
+        map.remove("Patterns");
+        map.remove("patterns");
+        let name = if data_version >= DATA_VERSION_BANNER_PATTERN_IDS { "patterns" } else { "Patterns" };
+        map.insert(name.to_string(), Tag::List(patterns));
+        Ok(())
+    }
+}
+
+// impulse, chain and repeating command blocks alike
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CommandBlock {
+    pub command: String,
+    // a text component
+    pub custom_name: Option<Tag>,
+    // runs without redstone
+    pub auto: bool,
+    pub powered: bool,
+    pub condition_met: bool,
+    pub track_output: bool,
+    pub success_count: i32,
+    // text component of the last output, if tracked
+    pub last_output: Option<Tag>,
+}
+
+impl CommandBlock {
+    pub fn from_block_entity(entity: &BlockEntity) -> Result<CommandBlock> {
+        check_id(entity, &["command_block"])?;
+        let mut map = entity.data.clone();
+        Ok(CommandBlock {
+            command: take_field_opt(&mut map, "Command", |t| t.as_str().map(String::from))?.unwrap_or_default(),
+            custom_name: take_text(&mut map, "CustomName")?,
+            auto: take_field_opt(&mut map, "auto", Tag::as_bool)?.unwrap_or(false),
+            powered: take_field_opt(&mut map, "powered", Tag::as_bool)?.unwrap_or(false),
+            condition_met: take_field_opt(&mut map, "conditionMet", Tag::as_bool)?.unwrap_or(false),
+            track_output: take_field_opt(&mut map, "TrackOutput", Tag::as_bool)?.unwrap_or(true),
+            success_count: take_field_opt(&mut map, "SuccessCount", Tag::as_int)?.unwrap_or(0),
+            last_output: take_text(&mut map, "LastOutput")?
+        })
+    }
+
+    pub fn apply(&self, entity: &mut BlockEntity) {
+        let map = &mut entity.data;
+        map.insert("Command".to_string(), Tag::String(self.command.clone()));
+        set_opt_tag(map, "CustomName", &self.custom_name);
+        map.insert("auto".to_string(), Tag::Byte(self.auto as i8));
+        map.insert("powered".to_string(), Tag::Byte(self.powered as i8));
+        map.insert("conditionMet".to_string(), Tag::Byte(self.condition_met as i8));
+        map.insert("TrackOutput".to_string(), Tag::Byte(self.track_output as i8));
+        map.insert("SuccessCount".to_string(), Tag::Int(self.success_count));
+        set_opt_tag(map, "LastOutput", &self.last_output);
+    }
+}
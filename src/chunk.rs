@@ -1,21 +1,821 @@
 // chunks are useful structures used in mc network protocol
 // as well as the world storage.
-// a chunk contains 16*128*16 blocks. by referring to a block system,
+// a chunk is a column of 16*16*16 sections, laid out like the `sections`
+// of anvil. it spans as many sections as its dimension does, from y 0 to 256
+// by default or -64 to 320 in the 1.18 overworld, see `Chunk::with_sections`.
+// blocks are stored as metas, to be named by referring to a block id system.
 
 pub use crate::pos::ChunkPos as Pos;
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result};
+use std::collections::HashMap;
+use crate::block;
+use crate::biome;
+use crate::block_entity::BlockEntity;
+use crate::entity::{BoundingBox, Entity};
+use crate::storage::Uuid;
 
-#[derive(Clone)] // does NOT derive Copy as it's expensive
+crate const SECTION_COUNT: usize = 16;
+crate const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
+
+// 16*16*16 blocks as indices into a palette, packed into longs without
+// spanning entries across longs. a section of only one block needs no indices
+#[derive(Clone, PartialEq, Debug)]
+pub struct Section {
+    palette: Vec<block::Meta>,
+    bits: u8,
+    data: Vec<u64>,
+}
+
+impl Section {
+    // a section with every block being `fill`
+    pub fn new(fill: block::Meta) -> Section {
+        Section {
+            palette: vec![fill],
+            bits: 0,
+            data: Vec::new()
+        }
+    }
+
+    pub fn palette(&self) -> &[block::Meta] {
+        &self.palette
+    }
+
+    // zero if the section holds a single block
+    pub fn bits_per_entry(&self) -> u8 {
+        self.bits
+    }
+
+    // x, y, z are relative to the section
+    // panic if out of range
+    pub fn get_block_meta(&self, x: usize, y: usize, z: usize) -> block::Meta {
+        self.palette[self.get_index(section_index(x, y, z))]
+    }
+
+    pub fn set_block_meta(&mut self, x: usize, y: usize, z: usize, meta: block::Meta) {
+        let index = section_index(x, y, z);
+        let entry = match self.palette.iter().position(|&m| m == meta) {
+            Some(entry) => entry,
+            None => {
+                self.palette.push(meta);
+                if self.palette.len() > 1 << self.bits {
+                    self.repack(bits_for(self.palette.len()));
+                }
+                self.palette.len() - 1
+            }
+        };
+        self.set_index(index, entry);
+    }
+
+    // true if every block has a zero meta
+    pub fn is_empty(&self) -> bool {
+        self.palette.iter().all(|m| m.to_inner() == 0)
+    }
+
+    // drop palette entries no longer used, and pack with as few bits as possible
+    pub fn compact(&mut self) {
+        let mut used = vec![false; self.palette.len()];
+        for index in 0..BLOCKS_PER_SECTION {
+            used[self.get_index(index)] = true;
+        }
+        if used.iter().all(|&u| u) {
+            return;
+        }
+        let mut remap = vec![0; self.palette.len()];
+        let mut palette = Vec::new();
+        for (entry, &meta) in self.palette.iter().enumerate() {
+            if used[entry] {
+                remap[entry] = palette.len();
+                palette.push(meta);
+            }
+        }
+        let indices: Vec<usize> = (0..BLOCKS_PER_SECTION).map(|i| remap[self.get_index(i)]).collect();
+        let bits = if palette.len() == 1 { 0 } else { bits_for(palette.len()) };
+        self.palette = palette;
+        self.fill_indices(bits, &indices);
+    }
+
+    // from the `Palette` and `BlockStates` of a section, entries already
+    // mapped into metas. the bits per entry follow from the palette length
+    pub fn from_packed(palette: Vec<block::Meta>, data: &[i64], data_version: i32) -> Result<Section> {
+        if palette.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "Empty section palette"));
+        }
+        // a single entry palette needs no data since 1.18, and any data before
+        if palette.len() == 1 {
+            return Ok(Section::new(palette[0]));
+        }
+        let bits = bits_for(palette.len());
+        let indices = unpack_longs(data, bits, BLOCKS_PER_SECTION, Packing::for_data_version(data_version))?;
+        if indices.iter().any(|&i| i as usize >= palette.len()) {
+            return Err(Error::new(ErrorKind::InvalidData, "Section palette index out of range"));
+        }
+        let indices: Vec<usize> = indices.into_iter().map(|i| i as usize).collect();
+        let mut ans = Section { palette, bits: 0, data: Vec::new() };
+        ans.fill_indices(bits, &indices);
+        Ok(ans)
+    }
+
+    // the packed indices into `palette()`, as stored for the DataVersion
+    pub fn to_packed(&self, data_version: i32) -> Vec<i64> {
+        if self.bits == 0 && data_version >= DATA_VERSION_SINGLE_VALUE_PALETTE {
+            return Vec::new();
+        }
+        let indices: Vec<u32> = (0..BLOCKS_PER_SECTION).map(|i| self.get_index(i) as u32).collect();
+        pack_longs(&indices, self.bits.max(4), Packing::for_data_version(data_version))
+    }
+
+    fn get_index(&self, index: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        ((self.data[index / per_long] >> shift) & ((1 << self.bits) - 1)) as usize
+    }
+
+    fn set_index(&mut self, index: usize, entry: usize) {
+        if self.bits == 0 {
+            return;
+        }
+        let per_long = 64 / self.bits as usize;
+        let shift = (index % per_long) * self.bits as usize;
+        let mask = ((1u64 << self.bits) - 1) << shift;
+        let long = &mut self.data[index / per_long];
+        *long = (*long & !mask) | ((entry as u64) << shift);
+    }
+
+    fn repack(&mut self, bits: u8) {
+        let indices: Vec<usize> = (0..BLOCKS_PER_SECTION).map(|i| self.get_index(i)).collect();
+        self.fill_indices(bits, &indices);
+    }
+
+    fn fill_indices(&mut self, bits: u8, indices: &[usize]) {
+        self.bits = bits;
+        self.data = if bits == 0 {
+            Vec::new()
+        } else {
+            let per_long = 64 / bits as usize;
+            vec![0; (BLOCKS_PER_SECTION - 1) / per_long + 1]
+        };
+        for (index, &entry) in indices.iter().enumerate() {
+            self.set_index(index, entry);
+        }
+    }
+}
+
+// as in anvil, x varies fastest, then z, then y
+fn section_index(x: usize, y: usize, z: usize) -> usize {
+    assert!(x < 16 && y < 16 && z < 16, "block out of section");
+    (y << 8) | (z << 4) | x
+}
+
+// at least 4 bits, as the game does
+fn bits_for(palette_len: usize) -> u8 {
+    let mut bits = 4;
+    while 1 << bits < palette_len {
+        bits += 1;
+    }
+    bits
+}
+
+// first DataVersion that packs entries without spanning longs (20w17a, 1.16)
+crate const DATA_VERSION_ALIGNED_PACKING: i32 = 2529;
+// first DataVersion that stores single entry palettes without data (21w37a, 1.18)
+crate const DATA_VERSION_SINGLE_VALUE_PALETTE: i32 = 2834;
+
+// how entries of a `LongArray` are laid out, lowest bits first
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Packing {
+    // before 1.16: entries follow each other, some spanning two longs
+    Spanning,
+    // since 1.16: as many entries as fit in a long, the rest of it left unused
+    Aligned,
+}
+
+impl Packing {
+    pub fn for_data_version(data_version: i32) -> Packing {
+        if data_version >= DATA_VERSION_ALIGNED_PACKING {
+            Packing::Aligned
+        } else {
+            Packing::Spanning
+        }
+    }
+}
+
+// longs needed for `count` entries of `bits` bits
+pub fn packed_len(bits: u8, count: usize, packing: Packing) -> usize {
+    if bits == 0 || count == 0 {
+        return 0;
+    }
+    match packing {
+        Packing::Spanning => (count * bits as usize - 1) / 64 + 1,
+        Packing::Aligned => (count - 1) / (64 / bits as usize) + 1
+    }
+}
+
+// `count` entries of `bits` bits, up to 32.
+// fail if the array is not exactly as long as they need
+pub fn unpack_longs(data: &[i64], bits: u8, count: usize, packing: Packing) -> Result<Vec<u32>> {
+    assert!(bits <= 32, "too many bits per entry");
+    let expected = packed_len(bits, count, packing);
+    if data.len() != expected {
+        return Err(Error::new(ErrorKind::InvalidData,
+            format!("Packed array of {} longs, expected {}", data.len(), expected)));
+    }
+    if bits == 0 {
+        return Ok(vec![0; count]);
+    }
+    let bits = bits as usize;
+    let mask = (1u64 << bits) - 1;
+    let mut ans = Vec::with_capacity(count);
+    for i in 0..count {
+        let value = match packing {
+            Packing::Spanning => {
+                let (long, offset) = (i * bits / 64, i * bits % 64);
+                let mut value = data[long] as u64 >> offset;
+                if offset + bits > 64 {
+                    value |= (data[long + 1] as u64) << (64 - offset);
+                }
+                value
+            },
+            Packing::Aligned => {
+                let per_long = 64 / bits;
+                data[i / per_long] as u64 >> (i % per_long * bits)
+            }
+        };
+        ans.push((value & mask) as u32);
+    }
+    Ok(ans)
+}
+
+// panic if a value does not fit in `bits` bits
+pub fn pack_longs(values: &[u32], bits: u8, packing: Packing) -> Vec<i64> {
+    assert!(bits <= 32, "too many bits per entry");
+    let mut ans = vec![0u64; packed_len(bits, values.len(), packing)];
+    if bits == 0 {
+        assert!(values.iter().all(|&v| v == 0), "value out of packed range");
+        return Vec::new();
+    }
+    let bits = bits as usize;
+    for (i, &value) in values.iter().enumerate() {
+        let value = value as u64;
+        assert!(value >> bits == 0, "value out of packed range");
+        match packing {
+            Packing::Spanning => {
+                let (long, offset) = (i * bits / 64, i * bits % 64);
+                ans[long] |= value << offset;
+                if offset + bits > 64 {
+                    ans[long + 1] |= value >> (64 - offset);
+                }
+            },
+            Packing::Aligned => {
+                let per_long = 64 / bits;
+                ans[i / per_long] |= value << (i % per_long * bits);
+            }
+        }
+    }
+    ans.into_iter().map(|v| v as i64).collect()
+}
+
+// sections are allocated on the first block written with a non-zero meta,
+// so that mostly empty chunks stay small.
+// a chunk spans the sections of its dimension, 0..256 unless told otherwise
+#[derive(Clone, PartialEq, Debug)] // does NOT derive Copy as it's expensive
 pub struct Chunk {
-    inner: [[[u16; 16]; 256]; 16]
-} 
+    min_section: i32,
+    sections: Vec<Option<Section>>,
+    biomes: Option<Biomes>,
+    // by x, y, z like blocks. `None` unless read from or given block entities,
+    // so that writing a chunk built from scratch keeps the saved ones
+    block_entities: Option<HashMap<(usize, i32, usize), BlockEntity>>,
+    // `None` unless read or given, the same way as block entities
+    entities: Option<Vec<Entity>>,
+    // by `TickKind`, `None` unless read or given the same way
+    ticks: Option<[Vec<ScheduledTick>; 2]>
+}
 
 impl Chunk {
     crate fn new_zeroed() -> Chunk {
+        Chunk::with_sections(0, SECTION_COUNT)
+    }
+
+    // a chunk with every block's meta being zero
+    pub fn new() -> Chunk {
+        Chunk::new_zeroed()
+    }
+
+    // a chunk of `count` sections from section y `min_section`,
+    // like -4 and 24 for the 1.18 overworld
+    pub fn with_sections(min_section: i32, count: usize) -> Chunk {
         Chunk {
-            inner: [[[0u16; 16]; 256]; 16]
+            min_section,
+            sections: vec![None; count],
+            biomes: None,
+            block_entities: None,
+            entities: None,
+            ticks: None
+        }
+    }
+
+    pub fn min_section(&self) -> i32 {
+        self.min_section
+    }
+
+    pub fn section_count(&self) -> usize {
+        self.sections.len()
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_section * 16
+    }
+
+    // exclusive
+    pub fn max_y(&self) -> i32 {
+        (self.min_section + self.sections.len() as i32) * 16
+    }
+
+    pub fn contains_y(&self, y: i32) -> bool {
+        y >= self.min_y() && y < self.max_y()
+    }
+
+    fn section_index(&self, section_y: i32) -> Option<usize> {
+        let index = section_y - self.min_section;
+        if index >= 0 && (index as usize) < self.sections.len() {
+            Some(index as usize)
+        } else {
+            None
         }
     }
+
+    fn section_index_of(&self, y: i32) -> usize {
+        match self.section_index(y >> 4) {
+            Some(index) => index,
+            None => panic!("block y out of chunk: {}", y)
+        }
+    }
+
+    // x, z are relative to the chunk, y is the world height
+    // panic if out of range
+    pub fn get_block_meta(&self, x: usize, y: i32, z: usize) -> block::Meta {
+        match &self.sections[self.section_index_of(y)] {
+            Some(section) => section.get_block_meta(x, (y & 15) as usize, z),
+            None => block::Meta::new(0)
+        }
+    }
+
+    pub fn set_block_meta(&mut self, x: usize, y: i32, z: usize, meta: block::Meta) {
+        let index = self.section_index_of(y);
+        let section = &mut self.sections[index];
+        if section.is_none() {
+            if meta.to_inner() == 0 {
+                return;
+            }
+            *section = Some(Section::new(block::Meta::new(0)));
+        }
+        section.as_mut().unwrap().set_block_meta(x, (y & 15) as usize, z, meta);
+    }
+
+    // by section y, like -4 for blocks -64..-48.
+    // `None` if out of the chunk or never written
+    pub fn section(&self, section_y: i32) -> Option<&Section> {
+        self.section_index(section_y).and_then(|index| self.sections[index].as_ref())
+    }
+
+    pub fn section_mut(&mut self, section_y: i32) -> Option<&mut Section> {
+        match self.section_index(section_y) {
+            Some(index) => self.sections[index].as_mut(),
+            None => None
+        }
+    }
+
+    // panic if out of the chunk
+    pub fn set_section(&mut self, section_y: i32, section: Option<Section>) {
+        match self.section_index(section_y) {
+            Some(index) => self.sections[index] = section,
+            None => panic!("section y out of chunk: {}", section_y)
+        }
+    }
+
+    // section y of every section written, from the bottom
+    pub fn section_ys(&self) -> impl Iterator<Item = i32> + '_ {
+        let min_section = self.min_section;
+        self.sections.iter().enumerate()
+            .filter(|(_, section)| section.is_some())
+            .map(move |(index, _)| min_section + index as i32)
+    }
+
+    // `None` if the chunk holds no biomes
+    pub fn biomes(&self) -> Option<&Biomes> {
+        self.biomes.as_ref()
+    }
+
+    // panic if cells do not cover the chunk's height
+    pub fn set_biomes(&mut self, biomes: Option<Biomes>) {
+        if let Some(Biomes::Cells(metas)) = &biomes {
+            assert_eq!(metas.len(), self.sections.len() * 64, "biome cells out of chunk height");
+        }
+        self.biomes = biomes;
+    }
+
+    // x, z are relative to the chunk, y is the world height.
+    // `None` if the chunk holds no biomes, panic if out of range
+    pub fn get_biome_meta(&self, x: usize, y: i32, z: usize) -> Option<biome::Meta> {
+        assert!(self.contains_y(y), "biome y out of chunk: {}", y);
+        let min_y = self.min_y();
+        self.biomes.as_ref().map(|biomes| biomes.get(x, (y - min_y) as usize, z))
+    }
+
+    // chunks without biomes get cells of zero metas first
+    pub fn set_biome_meta(&mut self, x: usize, y: i32, z: usize, meta: biome::Meta) {
+        assert!(self.contains_y(y), "biome y out of chunk: {}", y);
+        let (min_y, height) = (self.min_y(), self.sections.len() * 16);
+        self.biomes.get_or_insert_with(|| Biomes::new_cells(height, biome::Meta::new(0)))
+            .set(x, (y - min_y) as usize, z, meta);
+    }
+
+    // whether the chunk holds a list of block entities, even an empty one
+    pub fn has_block_entities(&self) -> bool {
+        self.block_entities.is_some()
+    }
+
+    // an empty list of block entities, dropping any held
+    pub fn clear_block_entities(&mut self) {
+        self.block_entities = Some(HashMap::new());
+    }
+
+    // from the bottom, then by z and x
+    pub fn block_entities(&self) -> Vec<&BlockEntity> {
+        let mut ans: Vec<_> = self.block_entities.iter().flat_map(|map| map.iter()).collect();
+        ans.sort_by_key(|&(&(x, y, z), _)| (y, z, x));
+        ans.into_iter().map(|(_, entity)| entity).collect()
+    }
+
+    // x, z are relative to the chunk, y is the world height
+    pub fn get_block_entity(&self, x: usize, y: i32, z: usize) -> Option<&BlockEntity> {
+        self.block_entities.as_ref().and_then(|map| map.get(&(x, y, z)))
+    }
+
+    pub fn get_block_entity_mut(&mut self, x: usize, y: i32, z: usize) -> Option<&mut BlockEntity> {
+        self.block_entities.as_mut().and_then(|map| map.get_mut(&(x, y, z)))
+    }
+
+    // at the block of its position, replacing the block entity there.
+    // the position is taken to be in this chunk, panic if its y is not
+    pub fn set_block_entity(&mut self, entity: BlockEntity) -> Option<BlockEntity> {
+        let (x, y, z) = entity.pos.to_xyz();
+        assert!(self.contains_y(y), "block entity y out of chunk: {}", y);
+        self.block_entities.get_or_insert_with(HashMap::new)
+            .insert(((x & 15) as usize, y, (z & 15) as usize), entity)
+    }
+
+    pub fn remove_block_entity(&mut self, x: usize, y: i32, z: usize) -> Option<BlockEntity> {
+        self.block_entities.as_mut().and_then(|map| map.remove(&(x, y, z)))
+    }
+
+    // positions of block entities not sitting on a block they belong on,
+    // like a chest entity left behind after its block was replaced by stone.
+    // block entities not known to `BlockEntity::matches_block` are skipped
+    pub fn check_block_entities(&self, ids: &dyn block::IdSystem) -> Vec<block::Pos> {
+        let mut ans = Vec::new();
+        for entity in self.block_entities() {
+            let (x, y, z) = entity.pos.to_xyz();
+            let meta = self.get_block_meta((x & 15) as usize, y, (z & 15) as usize);
+            let id = if ids.has_block_meta(meta) { ids.block_meta_to_id(meta).to_string() } else { String::new() };
+            if entity.matches_block(&id) == Some(false) {
+                ans.push(entity.pos);
+            }
+        }
+        ans
+    }
+
+    // whether the chunk holds a list of entities, even an empty one
+    pub fn has_entities(&self) -> bool {
+        self.entities.is_some()
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        match &self.entities {
+            Some(entities) => entities,
+            None => &[]
+        }
+    }
+
+    // the list of entities, an empty one if the chunk holds none
+    pub fn entities_mut(&mut self) -> &mut Vec<Entity> {
+        self.entities.get_or_insert_with(Vec::new)
+    }
+
+    pub fn set_entities(&mut self, entities: Option<Vec<Entity>>) {
+        self.entities = entities;
+    }
+
+    // entities with their position in the box
+    pub fn entities_in(&self, area: &BoundingBox) -> Vec<&Entity> {
+        self.entities().iter().filter(|entity| area.contains(entity.pos)).collect()
+    }
+
+    pub fn entity_by_uuid(&self, uuid: Uuid) -> Option<&Entity> {
+        self.entities().iter().find(|entity| entity.uuid == uuid)
+    }
+
+    // keep the entities `f` returns true for, returning how many were removed
+    pub fn retain_entities(&mut self, f: impl FnMut(&Entity) -> bool) -> usize {
+        match &mut self.entities {
+            Some(entities) => {
+                let len = entities.len();
+                entities.retain(f);
+                len - entities.len()
+            },
+            None => 0
+        }
+    }
+
+    // whether the chunk holds lists of scheduled ticks, even empty ones
+    pub fn has_ticks(&self) -> bool {
+        self.ticks.is_some()
+    }
+
+    pub fn ticks(&self, kind: TickKind) -> &[ScheduledTick] {
+        match &self.ticks {
+            Some(ticks) => &ticks[kind.index()],
+            None => &[]
+        }
+    }
+
+    // the list of ticks, an empty one if the chunk holds none
+    pub fn ticks_mut(&mut self, kind: TickKind) -> &mut Vec<ScheduledTick> {
+        &mut self.ticks.get_or_insert_with(Default::default)[kind.index()]
+    }
+
+    // drop every scheduled tick of both kinds
+    pub fn clear_ticks(&mut self) {
+        self.ticks = Some(Default::default());
+    }
+
+    // compact every section and free the empty ones
+    pub fn compact(&mut self) {
+        for section in self.sections.iter_mut() {
+            if let Some(s) = section {
+                s.compact();
+                if s.is_empty() {
+                    *section = None;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum TickKind {
+    // `TileTicks` before 1.18, `block_ticks` since
+    Block,
+    // `LiquidTicks` before 1.18, `fluid_ticks` since
+    Fluid,
+}
+
+impl TickKind {
+    fn index(self) -> usize {
+        match self {
+            TickKind::Block => 0,
+            TickKind::Fluid => 1
+        }
+    }
+}
+
+// a block or fluid update the game has scheduled, like a repeater turning
+// on or water about to flow
+#[derive(Clone, PartialEq, Debug)]
+pub struct ScheduledTick {
+    // the block or fluid to update, like "minecraft:water"
+    pub id: String,
+    // world position, not relative to the chunk
+    pub pos: block::Pos,
+    // ticks from now, negative if overdue
+    pub delay: i32,
+    // lower goes first among ticks due at once
+    pub priority: i32,
+}
+
+// the biomes of a chunk, one for each column before 1.15, or one for each
+// 4*4*4 cell since, from the bottom of the chunk
+#[derive(Clone, PartialEq, Debug)]
+pub enum Biomes {
+    // 16*16 columns, x varying fastest
+    Columns(Vec<biome::Meta>),
+    // 4*4 cells each layer, x varying fastest, then z, then y
+    Cells(Vec<biome::Meta>),
+}
+
+impl Biomes {
+    // one cell of `fill` for every 4*4*4 blocks of a chunk `height` blocks high
+    pub fn new_cells(height: usize, fill: biome::Meta) -> Biomes {
+        Biomes::Cells(vec![fill; height / 4 * 16])
+    }
+
+    pub fn new_columns(fill: biome::Meta) -> Biomes {
+        Biomes::Columns(vec![fill; 256])
+    }
+
+    // in the order of columns or cells
+    pub fn metas(&self) -> &[biome::Meta] {
+        match self {
+            Biomes::Columns(metas) | Biomes::Cells(metas) => metas
+        }
+    }
+
+    // x, z are relative to the chunk, y to its bottom
+    // panic if out of range
+    pub fn get(&self, x: usize, y: usize, z: usize) -> biome::Meta {
+        match self {
+            Biomes::Columns(metas) => metas[column_index(x, z)],
+            Biomes::Cells(metas) => metas[cell_index(x, y, z)]
+        }
+    }
+
+    // sets the whole column, or the whole cell holding the block
+    pub fn set(&mut self, x: usize, y: usize, z: usize, meta: biome::Meta) {
+        match self {
+            Biomes::Columns(metas) => metas[column_index(x, z)] = meta,
+            Biomes::Cells(metas) => metas[cell_index(x, y, z)] = meta
+        }
+    }
+
+    // cells for a chunk `height` blocks high, each taking the column at its corner
+    pub fn to_cells(&self, height: usize) -> Biomes {
+        match self {
+            Biomes::Columns(metas) => Biomes::Cells((0..height / 4 * 16)
+                .map(|i| metas[column_index(i % 4 * 4, i / 4 % 4 * 4)])
+                .collect()),
+            Biomes::Cells(_) => self.clone()
+        }
+    }
+
+    // columns taking their cells at y, relative to the bottom of the chunk
+    pub fn to_columns(&self, y: usize) -> Biomes {
+        match self {
+            Biomes::Columns(_) => self.clone(),
+            Biomes::Cells(_) => Biomes::Columns((0..256).map(|i| self.get(i % 16, y, i / 16)).collect())
+        }
+    }
+}
+
+fn cell_index(x: usize, y: usize, z: usize) -> usize {
+    assert!(x < 16 && z < 16, "biome out of chunk");
+    (y >> 2 << 4) | (z >> 2 << 2) | (x >> 2)
+}
+
+impl Default for Chunk {
+    fn default() -> Chunk {
+        Chunk::new_zeroed()
+    }
+}
+
+// kinds of heightmaps in the `Heightmaps` of chunk NBT, each the top of the
+// blocks matching its rule. the `_WG` ones exist only while generating,
+// `LIGHT_BLOCKING` only in 1.13
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum HeightmapKind {
+    WorldSurfaceWg,
+    WorldSurface,
+    OceanFloorWg,
+    OceanFloor,
+    MotionBlocking,
+    MotionBlockingNoLeaves,
+    LightBlocking,
+}
+
+impl HeightmapKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            HeightmapKind::WorldSurfaceWg => "WORLD_SURFACE_WG",
+            HeightmapKind::WorldSurface => "WORLD_SURFACE",
+            HeightmapKind::OceanFloorWg => "OCEAN_FLOOR_WG",
+            HeightmapKind::OceanFloor => "OCEAN_FLOOR",
+            HeightmapKind::MotionBlocking => "MOTION_BLOCKING",
+            HeightmapKind::MotionBlockingNoLeaves => "MOTION_BLOCKING_NO_LEAVES",
+            HeightmapKind::LightBlocking => "LIGHT_BLOCKING",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<HeightmapKind> {
+        let ans = match name {
+            "WORLD_SURFACE_WG" => HeightmapKind::WorldSurfaceWg,
+            "WORLD_SURFACE" => HeightmapKind::WorldSurface,
+            "OCEAN_FLOOR_WG" => HeightmapKind::OceanFloorWg,
+            "OCEAN_FLOOR" => HeightmapKind::OceanFloor,
+            "MOTION_BLOCKING" => HeightmapKind::MotionBlocking,
+            "MOTION_BLOCKING_NO_LEAVES" => HeightmapKind::MotionBlockingNoLeaves,
+            "LIGHT_BLOCKING" => HeightmapKind::LightBlocking,
+            _ => return None
+        };
+        Some(ans)
+    }
+
+    // whether a block of this id counts for the heightmap
+    pub fn matches(self, id: &str) -> bool {
+        match self {
+            HeightmapKind::WorldSurfaceWg | HeightmapKind::WorldSurface => !block::is_air(id),
+            HeightmapKind::OceanFloorWg | HeightmapKind::OceanFloor => block::blocks_motion(id),
+            HeightmapKind::MotionBlocking => block::blocks_motion(id) || block::has_fluid(id),
+            HeightmapKind::MotionBlockingNoLeaves =>
+                (block::blocks_motion(id) || block::has_fluid(id)) && !block::is_leaves(id),
+            // close to blocks with any opacity; glass lets light through
+            HeightmapKind::LightBlocking =>
+                (block::blocks_motion(id) && !block::block_name(id).contains("glass")) || block::has_fluid(id),
+        }
+    }
+}
+
+// for every column of a chunk, the y just above its top matching block,
+// or the bottom of the chunk if none matches
+#[derive(Clone, PartialEq, Debug)]
+pub struct Heightmap {
+    heights: Vec<i16>
+}
+
+impl Heightmap {
+    // every column at `y`, which is usually the bottom of the chunk
+    pub fn new(y: i32) -> Heightmap {
+        Heightmap { heights: vec![y as i16; 256] }
+    }
+
+    // x, z are relative to the chunk
+    // panic if out of range
+    pub fn get(&self, x: usize, z: usize) -> i32 {
+        self.heights[column_index(x, z)] as i32
+    }
+
+    pub fn set(&mut self, x: usize, z: usize, y: i32) {
+        self.heights[column_index(x, z)] = y as i16;
+    }
+
+    // from the long array in chunk NBT, holding heights above `min_y`
+    // in just enough bits for a chunk of `height` blocks
+    pub fn from_packed(data: &[i64], min_y: i32, height: usize, data_version: i32) -> Result<Heightmap> {
+        let values = unpack_longs(data, heightmap_bits(height), 256, Packing::for_data_version(data_version))?;
+        if values.iter().any(|&v| v as usize > height) {
+            return Err(Error::new(ErrorKind::InvalidData, "Heightmap value out of chunk"));
+        }
+        Ok(Heightmap { heights: values.into_iter().map(|v| (v as i32 + min_y) as i16).collect() })
+    }
+
+    // panic if a column is out of `min_y..=min_y + height`
+    pub fn to_packed(&self, min_y: i32, height: usize, data_version: i32) -> Vec<i64> {
+        let values: Vec<u32> = self.heights.iter().map(|&y| {
+            let value = y as i32 - min_y;
+            assert!(value >= 0 && value as usize <= height, "heightmap value out of chunk");
+            value as u32
+        }).collect();
+        pack_longs(&values, heightmap_bits(height), Packing::for_data_version(data_version))
+    }
+
+    // the heightmap of a chunk, naming blocks by `ids`.
+    // metas not in `ids` are taken as air
+    pub fn compute(chunk: &Chunk, kind: HeightmapKind, ids: &dyn block::IdSystem) -> Heightmap {
+        let mut ans = Heightmap::new(chunk.min_y());
+        let mut found = vec![false; 256];
+        let mut left = 256;
+        for section_y in chunk.section_ys().collect::<Vec<_>>().into_iter().rev() {
+            let section = chunk.section(section_y).unwrap();
+            let matching: Vec<bool> = section.palette().iter()
+                .map(|&meta| ids.has_block_meta(meta) && kind.matches(&ids.block_meta_to_id(meta).to_string()))
+                .collect();
+            if !matching.iter().any(|&m| m) {
+                continue;
+            }
+            for y in (0..16).rev() {
+                for z in 0..16 {
+                    for x in 0..16 {
+                        let column = column_index(x, z);
+                        if found[column] || !matching[section.get_index(section_index(x, y, z))] {
+                            continue;
+                        }
+                        found[column] = true;
+                        ans.heights[column] = (section_y * 16 + y as i32 + 1) as i16;
+                        left -= 1;
+                    }
+                }
+            }
+            if left == 0 {
+                break;
+            }
+        }
+        ans
+    }
+}
+
+fn column_index(x: usize, z: usize) -> usize {
+    assert!(x < 16 && z < 16, "column out of chunk");
+    (z << 4) | x
+}
+
+// enough bits for every value in 0..=height, 9 for 256 or 384 blocks high
+fn heightmap_bits(height: usize) -> u8 {
+    let mut bits = 1;
+    while 1 << bits <= height {
+        bits += 1;
+    }
+    bits
 }
 
 pub trait Read { // chunk::Read
@@ -80,5 +880,100 @@ impl<I: WriteExact> Write for Cursor<I> {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Meta;
+
+    #[test]
+    fn packed_longs() -> Result<()> {
+        let packings = [Packing::Spanning, Packing::Aligned];
+        for &packing in packings.iter() {
+            for bits in 0..=32u8 {
+                for &count in [0, 1, 63, 64, 256, 1024, 4096].iter() {
+                    let mask = if bits == 0 { 0 } else { !0u32 >> (32 - bits) };
+                    // every bit pattern shows up, the top bit included
+                    let values: Vec<u32> = (0..count as u32)
+                        .map(|i| i.wrapping_mul(2654435761).rotate_left(i % 32) & mask)
+                        .collect();
+                    let data = pack_longs(&values, bits, packing);
+                    assert_eq!(data.len(), packed_len(bits, count, packing));
+                    assert_eq!(unpack_longs(&data, bits, count, packing)?, values);
+                }
+            }
+        }
+        assert_eq!(packed_len(5, 4096, Packing::Spanning), 320);
+        assert_eq!(packed_len(5, 4096, Packing::Aligned), 342);
+        assert_eq!(packed_len(9, 256, Packing::Aligned), 37);
+        // the 13th entry of 5 bits spans two longs, unless aligned
+        let mut values = vec![0; 13];
+        values[12] = 31;
+        assert_eq!(pack_longs(&values, 5, Packing::Spanning), vec![0xF << 60, 1]);
+        assert_eq!(pack_longs(&values, 5, Packing::Aligned), vec![0, 31]);
+        assert!(unpack_longs(&[0; 3], 5, 13, Packing::Spanning).is_err());
+        Ok(())
+    }
 
+    #[test]
+    fn packed_sections() -> Result<()> {
+        let mut section = Section::new(Meta::new(0));
+        for i in 0..20u16 {
+            section.set_block_meta(i as usize % 16, i as usize / 16, 3, Meta::new(i * 7));
+        }
+        let palette = section.palette().to_vec();
+        for &data_version in [1519, 2566, 2860].iter() {
+            let data = section.to_packed(data_version);
+            assert_eq!(data.len(), if data_version < 2529 { 320 } else { 342 });
+            assert_eq!(Section::from_packed(palette.clone(), &data, data_version)?, section);
+        }
+        let single = Section::new(Meta::new(4));
+        assert_eq!(single.to_packed(1519), vec![0; 256]);
+        assert!(single.to_packed(2860).is_empty());
+        assert_eq!(Section::from_packed(vec![Meta::new(4)], &[], 2860)?, single);
+        assert_eq!(Section::from_packed(vec![Meta::new(4)], &[0; 256], 1519)?, single);
+        assert!(Section::from_packed(vec![Meta::new(1), Meta::new(2)], &[-1; 256], 2566).is_err());
+        Ok(())
+    }
 
+    #[test]
+    fn sections() {
+        let mut chunk = Chunk::new();
+        assert_eq!(chunk.get_block_meta(3, 200, 7), Meta::new(0));
+        chunk.set_block_meta(3, 200, 7, Meta::new(0));
+        assert!(chunk.section(12).is_none());
+        for i in 0..40u16 {
+            chunk.set_block_meta(i as usize % 16, 64 + i as i32 / 16, 5, Meta::new(i + 1));
+        }
+        let section = chunk.section(4).unwrap();
+        assert_eq!(section.palette().len(), 41);
+        assert_eq!(section.bits_per_entry(), 6);
+        assert_eq!(chunk.get_block_meta(7, 66, 5), Meta::new(40));
+        assert_eq!(chunk.get_block_meta(7, 66, 6), Meta::new(0));
+        assert!(chunk.section(3).is_none() && chunk.section(5).is_none());
+        assert_eq!(chunk.section_ys().collect::<Vec<_>>(), vec![4]);
+        // overwrite all but one block, then compact
+        for i in 1..40u16 {
+            chunk.set_block_meta(i as usize % 16, 64 + i as i32 / 16, 5, Meta::new(1));
+        }
+        chunk.set_block_meta(0, 0, 0, Meta::new(9));
+        chunk.set_block_meta(0, 0, 0, Meta::new(0));
+        chunk.compact();
+        assert!(chunk.section(0).is_none());
+        let section = chunk.section(4).unwrap();
+        assert_eq!(section.palette(), &[Meta::new(0), Meta::new(1)]);
+        assert_eq!(section.bits_per_entry(), 4);
+        assert_eq!(chunk.get_block_meta(7, 66, 5), Meta::new(1));
+        let mut tall = Chunk::with_sections(-4, 24);
+        assert_eq!((tall.min_y(), tall.max_y()), (-64, 320));
+        tall.set_block_meta(15, -64, 15, Meta::new(3));
+        tall.set_block_meta(0, 319, 0, Meta::new(4));
+        assert_eq!(tall.get_block_meta(15, -64, 15), Meta::new(3));
+        assert_eq!(tall.get_block_meta(0, 319, 0), Meta::new(4));
+        assert!(tall.section(-4).is_some() && tall.section(19).is_some() && tall.section(20).is_none());
+        assert!(!tall.contains_y(320) && tall.contains_y(-1));
+        let mut full = Section::new(Meta::new(5));
+        full.compact();
+        assert_eq!(full.bits_per_entry(), 0);
+        assert_eq!(full.get_block_meta(15, 15, 15), Meta::new(5));
+    }
+}
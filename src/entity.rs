@@ -0,0 +1,164 @@
+// entities, like mobs, items on the ground and minecarts. saved in the
+// `Entities` of chunk NBT before 1.17, and in entity region files since.
+// https://minecraft.gamepedia.com/Entity_format
+
+use std::io::{Error, ErrorKind, Result};
+use std::ops::RangeInclusive;
+use crate::chunk;
+use crate::nbt::{Compound, Tag, take_field, take_field_opt};
+use crate::storage::Uuid;
+
+// first DataVersion with uuids as 4 ints in `UUID` (20w12a, 1.16)
+crate const DATA_VERSION_INT_ARRAY_UUID: i32 = 2515;
+// first DataVersion with entities in their own region files (20w45a, 1.17)
+crate const DATA_VERSION_ENTITY_CHUNKS: i32 = 2681;
+// no block is further away from the origin in x or z
+const WORLD_BORDER: f64 = 30_000_000.0;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entity {
+    pub uuid: Uuid,
+    // like "minecraft:zombie"
+    pub id: String,
+    // x, y, z of the feet
+    pub pos: [f64; 3],
+    // blocks per tick
+    pub motion: [f64; 3],
+    // yaw and pitch in degrees
+    pub rotation: [f32; 2],
+    // health, items, passengers and the rest
+    data: Compound,
+}
+
+impl Entity {
+    pub fn new(id: impl Into<String>, uuid: Uuid, pos: [f64; 3]) -> Entity {
+        Entity {
+            uuid,
+            id: id.into(),
+            pos,
+            motion: [0.0; 3],
+            rotation: [0.0; 2],
+            data: Compound::new()
+        }
+    }
+
+    pub fn from_nbt(mut map: Compound) -> Result<Entity> {
+        let uuid = match take_field_opt(&mut map, "UUID", |t| t.as_int_array().cloned())? {
+            Some(ints) if ints.len() == 4 => Uuid::from_u128(ints.iter().fold(0, |ans, &i| ans << 32 | i as u32 as u128)),
+            Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid entity UUID")),
+            None => Uuid::from_most_least(
+                take_field(&mut map, "UUIDMost", Tag::as_long)?,
+                take_field(&mut map, "UUIDLeast", Tag::as_long)?
+            )
+        };
+        Ok(Entity {
+            uuid,
+            id: take_field(&mut map, "id", |t| t.as_str().map(String::from))?,
+            pos: take_doubles(&mut map, "Pos")?.unwrap_or([0.0; 3]),
+            motion: take_doubles(&mut map, "Motion")?.unwrap_or([0.0; 3]),
+            rotation: match take_field_opt(&mut map, "Rotation", |t| t.as_list().cloned())? {
+                Some(list) => match list.as_slice() {
+                    [Tag::Float(yaw), Tag::Float(pitch)] => [*yaw, *pitch],
+                    _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid entity Rotation"))
+                },
+                None => [0.0; 2]
+            },
+            data: map
+        })
+    }
+
+    // the uuid is written as the DataVersion stores it
+    pub fn to_nbt(&self, data_version: i32) -> Compound {
+        let mut map = self.data.clone();
+        map.insert("id".to_string(), Tag::String(self.id.clone()));
+        if data_version >= DATA_VERSION_INT_ARRAY_UUID {
+            let value = self.uuid.to_u128();
+            let ints = (0..4).map(|i| (value >> (96 - 32 * i)) as u32 as i32).collect();
+            map.insert("UUID".to_string(), Tag::IntArray(ints));
+        } else {
+            let (most, least) = self.uuid.to_most_least();
+            map.insert("UUIDMost".to_string(), Tag::Long(most));
+            map.insert("UUIDLeast".to_string(), Tag::Long(least));
+        }
+        map.insert("Pos".to_string(), Tag::List(self.pos.iter().map(|&v| Tag::Double(v)).collect()));
+        map.insert("Motion".to_string(), Tag::List(self.motion.iter().map(|&v| Tag::Double(v)).collect()));
+        map.insert("Rotation".to_string(), Tag::List(self.rotation.iter().map(|&v| Tag::Float(v)).collect()));
+        map
+    }
+
+    // fields besides the ones above
+    pub fn data(&self) -> &Compound {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut Compound {
+        &mut self.data
+    }
+
+    // the chunk the entity is saved in
+    pub fn chunk_pos(&self) -> chunk::Pos {
+        chunk::Pos::from_xz((self.pos[0].floor() as i32) >> 4, (self.pos[2].floor() as i32) >> 4)
+    }
+}
+
+fn take_doubles(map: &mut Compound, name: &str) -> Result<Option<[f64; 3]>> {
+    match take_field_opt(map, name, |t| t.as_list().cloned())? {
+        Some(list) => match list.as_slice() {
+            [Tag::Double(x), Tag::Double(y), Tag::Double(z)] => Ok(Some([*x, *y, *z])),
+            _ => Err(Error::new(ErrorKind::InvalidData, format!("Invalid entity {}", name)))
+        },
+        None => Ok(None)
+    }
+}
+
+// an axis aligned box of world coordinates, holding positions from
+// `min` up to but not including `max`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl BoundingBox {
+    // between two corners in any order
+    pub fn new(a: [f64; 3], b: [f64; 3]) -> BoundingBox {
+        BoundingBox {
+            min: [a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2])],
+            max: [a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])]
+        }
+    }
+
+    // every block of the chunks between two corner chunks
+    pub fn from_chunks(a: chunk::Pos, b: chunk::Pos) -> BoundingBox {
+        let ((ax, az), (bx, bz)) = (a.to_xz(), b.to_xz());
+        // entities may be far above or below the world
+        let min = [(ax.min(bx) * 16) as f64, -1.0e300, (az.min(bz) * 16) as f64];
+        let max = [(ax.max(bx) * 16 + 16) as f64, 1.0e300, (az.max(bz) * 16 + 16) as f64];
+        BoundingBox { min, max }
+    }
+
+    pub fn contains(&self, pos: [f64; 3]) -> bool {
+        (0..3).all(|i| pos[i] >= self.min[i] && pos[i] < self.max[i])
+    }
+
+    // in the area, or anywhere without one
+    crate fn contains_opt(area: Option<&BoundingBox>, pos: [f64; 3]) -> bool {
+        match area {
+            Some(area) => area.contains(pos),
+            None => true
+        }
+    }
+
+    // the chunks the box reaches into as x and z ranges, cut at the world border
+    // so that a box read from a corrupt entity stays bounded
+    crate fn chunk_ranges(&self) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
+        let to_chunk = |v: f64| (v.clamp(-WORLD_BORDER, WORLD_BORDER).floor() as i32) >> 4;
+        (to_chunk(self.min[0])..=to_chunk(self.max[0]), to_chunk(self.min[2])..=to_chunk(self.max[2]))
+    }
+
+    // the chunks the box reaches into, one at a time
+    pub fn chunk_positions(&self) -> impl Iterator<Item = chunk::Pos> {
+        let (xs, zs) = self.chunk_ranges();
+        xs.flat_map(move |x| zs.clone().map(move |z| chunk::Pos::from_xz(x, z)))
+    }
+}
@@ -1,6 +1,7 @@
 // an actual string id for blocks
 // available for everything except 'transparent' block
 use alloc::borrow::Cow;
+use std::fmt;
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub struct Id<'a> {
@@ -21,9 +22,9 @@ impl<'a, I: AsRef<str>> PartialEq<I> for Id<'a> {
     }
 }  
 
-impl<'a> ToString for Id<'a> {
-    fn to_string(&self) -> String {
-        self.inner.clone().into_owned()
+impl<'a> fmt::Display for Id<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.inner)
     }
 }
 
@@ -1,4 +1,7 @@
 pub mod block;
+pub mod biome;
+pub mod block_entity;
+pub mod entity;
 pub mod pos;
 pub mod id;
 pub mod light;
@@ -10,7 +10,7 @@ NbtWrite
 NbtValue
 
  */
-use byteorder::{BigEndian, ReadBytesExt};//, WriteBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::collections::HashMap;
 use std::io::{Error, ErrorKind, Result};
 
@@ -28,13 +28,40 @@ const TYPE_ID_COMPOUND: u8 = 10;
 const TYPE_ID_INT_ARRAY: u8 = 11;
 const TYPE_ID_LONG_ARRAY: u8 = 12;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Data {
     root_name: String,
     root_tag: Tag
 }
 
-#[derive(Debug, PartialEq)]
+impl Data {
+    pub fn new(root_name: impl Into<String>, root_tag: Tag) -> Data {
+        Data {
+            root_name: root_name.into(),
+            root_tag
+        }
+    }
+
+    pub fn root_name(&self) -> &str {
+        &self.root_name
+    }
+
+    pub fn root_tag(&self) -> &Tag {
+        &self.root_tag
+    }
+
+    pub fn root_tag_mut(&mut self) -> &mut Tag {
+        &mut self.root_tag
+    }
+
+    pub fn into_root_tag(self) -> Tag {
+        self.root_tag
+    }
+}
+
+pub type Compound = HashMap<String, Tag>;
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Tag {
     Byte(i8),
     Short(i16),
@@ -52,6 +79,121 @@ pub enum Tag {
 
 pub type Meta = (u8, String);
 
+macro_rules! tag_as {
+    ($($func_name: ident, $variant: ident, $ty: ty;)+) => {
+$(
+    pub fn $func_name(&self) -> Option<$ty> {
+        match self {
+            Tag::$variant(v) => Some(*v),
+            _ => None
+        }
+    }
+)+
+    };
+}
+
+macro_rules! tag_as_ref {
+    ($($func_name: ident, $func_name_mut: ident, $variant: ident, $ty: ty;)+) => {
+$(
+    pub fn $func_name(&self) -> Option<&$ty> {
+        match self {
+            Tag::$variant(v) => Some(v),
+            _ => None
+        }
+    }
+
+    pub fn $func_name_mut(&mut self) -> Option<&mut $ty> {
+        match self {
+            Tag::$variant(v) => Some(v),
+            _ => None
+        }
+    }
+)+
+    };
+}
+
+impl Tag {
+    tag_as!(
+        as_byte, Byte, i8;
+        as_short, Short, i16;
+        as_int, Int, i32;
+        as_long, Long, i64;
+        as_float, Float, f32;
+        as_double, Double, f64;
+    );
+
+    tag_as_ref!(
+        as_byte_array, as_byte_array_mut, ByteArray, Vec<i8>;
+        as_list, as_list_mut, List, Vec<Tag>;
+        as_compound, as_compound_mut, Compound, Compound;
+        as_int_array, as_int_array_mut, IntArray, Vec<i32>;
+        as_long_array, as_long_array_mut, LongArray, Vec<i64>;
+    );
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(v) => Some(v),
+            _ => None
+        }
+    }
+
+    // bytes are often used as booleans
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_byte().map(|v| v != 0)
+    }
+
+    // use for nbt write
+    fn type_id(&self) -> u8 {
+        use self::Tag::*;
+        match self {
+            Byte(_) => TYPE_ID_BYTE,
+            Short(_) => TYPE_ID_SHORT,
+            Int(_) => TYPE_ID_INT,
+            Long(_) => TYPE_ID_LONG,
+            Float(_) => TYPE_ID_FLOAT,
+            Double(_) => TYPE_ID_DOUBLE,
+            ByteArray(_) => TYPE_ID_BYTE_ARRAY,
+            String(_) => TYPE_ID_STRING,
+            List(_) => TYPE_ID_LIST,
+            Compound(_) => TYPE_ID_COMPOUND,
+            IntArray(_) => TYPE_ID_INT_ARRAY,
+            LongArray(_) => TYPE_ID_LONG_ARRAY,
+        }
+    }
+}
+
+// helpers for typed models built upon compounds.
+// fields are removed from the compound so that whatever remains
+// can be kept as unknown keys and written back untouched.
+crate fn take_field<T>(map: &mut Compound, name: &str, f: impl FnOnce(&Tag) -> Option<T>) -> Result<T> {
+    match take_field_opt(map, name, f)? {
+        Some(value) => Ok(value),
+        None => Err(Error::new(ErrorKind::InvalidData, format!("Missing NBT field: {}", name)))
+    }
+}
+
+crate fn take_field_opt<T>(map: &mut Compound, name: &str, f: impl FnOnce(&Tag) -> Option<T>) -> Result<Option<T>> {
+    match map.remove(name) {
+        Some(tag) => match f(&tag) {
+            Some(value) => Ok(Some(value)),
+            None => Err(Error::new(ErrorKind::InvalidData, format!("Invalid type of NBT field: {}", name)))
+        },
+        None => Ok(None)
+    }
+}
+
+// a list of compounds, empty if absent
+crate fn take_compound_list(map: &mut Compound, name: &str) -> Result<Vec<Compound>> {
+    let mut ans = Vec::new();
+    for tag in take_field_opt(map, name, |t| t.as_list().cloned())?.unwrap_or_default() {
+        match tag {
+            Tag::Compound(compound) => ans.push(compound),
+            _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid element in NBT list: {}", name)))
+        }
+    }
+    Ok(ans)
+}
+
 pub trait Read {
 
     // fn read_nbt_meta() 
@@ -114,28 +256,6 @@ static NBT_SPLIT_TERMINATOR: char = '.';
 /* ------- Implmentations (Full-Read Mode) -------- */
 /* ------- For tests only -------- */
 
-// use for nbt write
-// impl Tag {
-//     fn type_id(&self) -> u8 {
-//         use self::Tag::*;
-// 	    match self {
-//             End => TYPE_ID_END,
-// 	        Byte(_) => TYPE_ID_BYTE,
-// 	        Short(_) => TYPE_ID_SHORT,
-// 	        Int(_) => TYPE_ID_INT,
-// 	        Long(_) => TYPE_ID_LONG,
-// 	        Float(_) => TYPE_ID_FLOAT,
-// 	        Double(_) => TYPE_ID_DOUBLE,
-// 	        ByteArray(_) => TYPE_ID_BYTE_ARRAY,
-// 	        String(_) => TYPE_ID_STRING,
-// 	        List(_) => TYPE_ID_LIST,
-// 	        Compound(_) => TYPE_ID_COMPOUND,
-// 	        IntArray(_) => TYPE_ID_INT_ARRAY,
-// 	        LongArray(_) => TYPE_ID_LONG_ARRAY,
-// 	    }
-//     }
-// }
-
 impl<T> Read for T where T: std::io::Read {
 
     fn read_nbt_data(&mut self) -> Result<Data> {
@@ -150,10 +270,23 @@ impl<T> Read for T where T: std::io::Read {
     }
 }
 
-//pub trait NbtWrite {
-//
-//    fn write_nbt_data(&mut self, data: NbtData) -> Result<()>;
-//}
+pub trait Write {
+
+    fn write_nbt_data(&mut self, data: &Data) -> Result<()>;
+}
+
+impl<T> Write for T where T: std::io::Write {
+
+    fn write_nbt_data(&mut self, data: &Data) -> Result<()> {
+        if data.root_tag.type_id() != TYPE_ID_COMPOUND {
+            return Err(
+                Error::new(ErrorKind::InvalidInput, "NBT root tag must be a compound")
+            );
+        }
+        write_meta(self, TYPE_ID_COMPOUND, &data.root_name)?;
+        write_content(self, &data.root_tag)
+    }
+}
 
 
 #[inline]
@@ -163,9 +296,58 @@ fn read_string<R: std::io::Read>(read: &mut R) -> Result<String> {
         return Ok("".to_string());
     }
     let mut buf = vec![0; len];
-    read.read(&mut buf)?;
-    String::from_utf8(buf).map_err(|_| 
-        Error::new(ErrorKind::InvalidData, "Invalid UTF-8 string in NBT parsing"))
+    read.read_exact(&mut buf)?;
+    decode_modified_utf8(buf)
+}
+
+// strings are in java's modified UTF-8: NUL takes two bytes, and characters
+// beyond the BMP are surrogate pairs of three bytes each
+fn decode_modified_utf8(buf: Vec<u8>) -> Result<String> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "Invalid modified UTF-8 string in NBT parsing");
+    if buf.iter().all(|&b| b != 0 && b < 0x80) {
+        return String::from_utf8(buf).map_err(|_| invalid());
+    }
+    let mut units = Vec::with_capacity(buf.len());
+    let mut i = 0;
+    while i < buf.len() {
+        let b = buf[i] as u16;
+        let cont = |j: usize| match buf.get(j) {
+            Some(&c) if c & 0xc0 == 0x80 => Ok((c & 0x3f) as u16),
+            _ => Err(invalid())
+        };
+        if b < 0x80 {
+            units.push(b);
+            i += 1;
+        } else if b & 0xe0 == 0xc0 {
+            units.push((b & 0x1f) << 6 | cont(i + 1)?);
+            i += 2;
+        } else if b & 0xf0 == 0xe0 {
+            units.push((b & 0x0f) << 12 | cont(i + 1)? << 6 | cont(i + 2)?);
+            i += 3;
+        } else {
+            return Err(invalid());
+        }
+    }
+    String::from_utf16(&units).map_err(|_| invalid())
+}
+
+fn encode_modified_utf8(string: &str) -> Vec<u8> {
+    let mut ans = Vec::with_capacity(string.len());
+    for unit in string.encode_utf16() {
+        match unit {
+            0x01..=0x7f => ans.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                ans.push(0xc0 | (unit >> 6) as u8);
+                ans.push(0x80 | (unit & 0x3f) as u8);
+            },
+            _ => {
+                ans.push(0xe0 | (unit >> 12) as u8);
+                ans.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                ans.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    ans
 }
 
 #[inline]
@@ -244,6 +426,72 @@ fn read_content<R: std::io::Read>(read: &mut R, type_id: u8) -> Result<Tag> {
     }
 }
 
+#[inline]
+fn write_string<W: std::io::Write>(write: &mut W, string: &str) -> Result<()> {
+    let buf = encode_modified_utf8(string);
+    if buf.len() > u16::MAX as usize {
+        return Err(Error::new(ErrorKind::InvalidInput, "String too long for NBT"));
+    }
+    write.write_u16::<BigEndian>(buf.len() as u16)?;
+    write.write_all(&buf)
+}
+
+#[inline]
+fn write_meta<W: std::io::Write>(write: &mut W, type_id: u8, name: &str) -> Result<()> {
+    write.write_u8(type_id)?;
+    write_string(write, name)
+}
+
+macro_rules! write_array {
+    ($write: ident, $buf: ident, $write_expr: ident) => {{
+        $write.write_i32::<BigEndian>($buf.len() as i32)?;
+        for v in $buf.iter() {
+            $write.$write_expr::<BigEndian>(*v)?;
+        }
+    }};
+}
+
+#[inline]
+fn write_content<W: std::io::Write>(write: &mut W, tag: &Tag) -> Result<()> {
+    match tag {
+        Tag::Byte(v) => write.write_i8(*v)?,
+        Tag::Short(v) => write.write_i16::<BigEndian>(*v)?,
+        Tag::Int(v) => write.write_i32::<BigEndian>(*v)?,
+        Tag::Long(v) => write.write_i64::<BigEndian>(*v)?,
+        Tag::Float(v) => write.write_f32::<BigEndian>(*v)?,
+        Tag::Double(v) => write.write_f64::<BigEndian>(*v)?,
+        Tag::ByteArray(buf) => {
+            write.write_i32::<BigEndian>(buf.len() as i32)?;
+            for v in buf.iter() {
+                write.write_i8(*v)?;
+            }
+        },
+        Tag::String(v) => write_string(write, v)?,
+        Tag::List(buf) => {
+            // empty lists are written with element type `End`
+            let type_id_elem = buf.first().map(Tag::type_id).unwrap_or(TYPE_ID_END);
+            if buf.iter().any(|elem| elem.type_id() != type_id_elem) {
+                return Err(Error::new(ErrorKind::InvalidInput, "NBT list elements must have the same type"));
+            }
+            write.write_u8(type_id_elem)?;
+            write.write_i32::<BigEndian>(buf.len() as i32)?;
+            for elem in buf.iter() {
+                write_content(write, elem)?;
+            }
+        },
+        Tag::Compound(map) => {
+            for (name, elem) in map.iter() {
+                write_meta(write, elem.type_id(), name)?;
+                write_content(write, elem)?;
+            }
+            write.write_u8(TYPE_ID_END)?;
+        },
+        Tag::IntArray(buf) => write_array!(write, buf, write_i32),
+        Tag::LongArray(buf) => write_array!(write, buf, write_i64),
+    };
+    Ok(())
+}
+
 // for test and bench only
 #[doc(hidden)]
 pub static TEST_BIG_UNCOMPRESSED: &[u8] = &[
@@ -280,7 +528,7 @@ pub static TEST_BIG_UNCOMPRESSED: &[u8] = &[
             0x7f, 0xff, // i16::max_value()
         0x3, // int
         0x0, 0x4, 0x33, 0x69, 0x6e, 0x74, // "3int"
-            0x7f, 0xff, 0xff, 0xff, // i16::max_value()
+            0x7f, 0xff, 0xff, 0xff, // i32::max_value()
         0xa, // compund #2
         0x0, 0x9, 0x31, 0x63, 0x6f, 0x6d, 0x70, 0x6f, 0x75, 0x6e, 0x64, // "1compund"
             0x5, // float
@@ -342,6 +590,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn modified_utf8_strings() -> Result<()> {
+        let cond = [
+            ("sign", vec![0, 4, 0x73, 0x69, 0x67, 0x6e]),
+            ("a\0b", vec![0, 4, 0x61, 0xc0, 0x80, 0x62]),
+            ("\u{e9}\u{20ac}", vec![0, 5, 0xc3, 0xa9, 0xe2, 0x82, 0xac]),
+            // a surrogate pair, not the 4 bytes of UTF-8
+            ("\u{1f600}", vec![0, 6, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]),
+        ];
+        for (input, output) in cond.iter() {
+            let mut buf = Vec::new();
+            write_string(&mut buf, input)?;
+            assert_eq!(&buf, output);
+            assert_eq!(read_string(&mut Cursor::new(output))?, *input);
+        }
+        assert!(read_string(&mut Cursor::new(vec![0, 4, 0xf0, 0x9f, 0x98, 0x80])).is_err());
+        // the limit is on encoded bytes
+        assert!(write_string(&mut Vec::new(), &"\u{e9}".repeat(40000)).is_err());
+        Ok(())
+    }
+
     #[test]
     fn read_nbt_big() -> Result<()> {
         let mut inner_map = HashMap::new();
@@ -374,4 +643,15 @@ mod tests {
         assert_eq!(data, correct);
         Ok(())
     }
+
+    #[test]
+    fn write_nbt_big() -> Result<()> {
+        let data = Cursor::new(TEST_BIG_UNCOMPRESSED).read_nbt_data()?;
+        let mut buf = Vec::new();
+        buf.write_nbt_data(&data)?;
+        // compound order is not preserved, so compare by reading back
+        assert_eq!(buf.len(), TEST_BIG_UNCOMPRESSED.len());
+        assert_eq!(Cursor::new(buf).read_nbt_data()?, data);
+        Ok(())
+    }
 }
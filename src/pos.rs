@@ -24,9 +24,10 @@ impl BlockPos {
         if z >= 33554432 { z - 67108864 } else { z })
     }
 
+    // rounds down, so that x -1 is in chunk -1
     pub fn to_chunk_pos(&self) -> ChunkPos {
         let (x, _, z) = self.to_xyz();
-        ChunkPos::from_xz(x / 16, z / 16)
+        ChunkPos::from_xz(x >> 4, z >> 4)
     }
 }
 
@@ -63,6 +64,12 @@ mod tests {
         assert_eq!(p1.to_u64_repr(), 2750121246750);
         let p4 = BlockPos::from_u64_repr(2750121246750);
         assert_eq!(p2, p4);
+        // y spans the -2032..2032 datapack dimensions may use
+        for &y in [-2032, -64, -1, 319, 2031].iter() {
+            assert_eq!(BlockPos::from_xyz(-30000000, y, 29999999).to_xyz(), (-30000000, y, 29999999));
+        }
+        assert_eq!(BlockPos::from_xyz(-1, 0, 17).to_chunk_pos(), ChunkPos::from_xz(-1, 1));
+        assert_eq!(BlockPos::from_xyz(-16, 0, -17).to_chunk_pos(), ChunkPos::from_xz(-1, -2));
     }
 
     #[test]
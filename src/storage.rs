@@ -3,26 +3,133 @@
 //full-functional java-edition level
 //https://minecraft.gamepedia.com/Level_format
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
 use std::fs;
+use std::sync::RwLock;
 use std::io::{self, Result};
-use byteorder::{BigEndian, ReadBytesExt};
-use crate::{block, chunk};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use crate::{biome, block, block_entity, chunk, entity, nbt};
+use crate::nbt::{Read as NbtRead, Write as NbtWrite};
+
+mod level;
+mod session;
+mod dimension;
+mod region;
+mod entities;
+mod poi;
+mod legacy;
+mod convert;
+mod buffered;
+mod iter;
+mod mapped;
+mod trim;
+mod backup;
+mod player;
+mod stats;
+mod saved;
+mod anvil;
+
+pub use self::level::{LevelData, GameType, Difficulty, Generator, VersionInfo, WorldBorder, Weather};
+pub use self::session::{SessionLock, LockFormat, LockPolicy};
+pub use self::dimension::{Dimension, HeightRange};
+pub use self::region::{Region, RegionKind, region_xz, now_timestamp};
+pub use self::entities::EntityChunk;
+pub use self::poi::{PoiChunk, PoiSection, PoiRecord};
+pub use self::legacy::{McRegionWorld, AlphaWorld};
+pub use self::convert::{convert_legacy_chunk, convert_mcregion_world, convert_mcregion_world_with};
+pub use self::buffered::{BufferedWorld, DEFAULT_CHUNK_CAPACITY};
+pub use self::iter::{ChunkIter, ParChunkIter};
+pub use self::mapped::MappedRegion;
+pub use self::trim::{ChunkArea, TrimOptions, TrimReport, trim_world};
+pub use self::backup::{export_changed_chunks, restore_changed_chunks};
+pub use self::player::{PlayerData, RespawnPoint, ItemStack, StatusEffect, Uuid};
+pub use self::stats::{PlayerStats, PlayerAdvancements, AdvancementProgress, stats_leaderboard, stats_total, advancements_leaderboard};
+pub use self::saved::{SavedData, SavedDataKind, Villages, Village, Raids, Raid, Scoreboard, Objective, Score, Team, IdCounts, MapData, CommandStorage, RandomSequences};
 
 // reference to a world path
 // unbuffered!
 pub struct McJavaWorld<P: AsRef<Path>> {
-    path: P
+    path: P,
+    // `None` if opened read-only
+    lock: Option<SessionLock>,
+    // block states by their ids, see `anvil::block_state_to_id`
+    ids: block::SharedHashSystem,
+    biome_ids: biome::SharedHashSystem,
+    // level.dat once read, for the height of dimensions and the DataVersion of
+    // new chunks. `None` until first needed, `Some(None)` if there is no level.dat
+    level: RwLock<Option<Option<LevelData>>>,
 }
 
 impl<P: AsRef<Path>> McJavaWorld<P> {
+    // opens a world read-only, any write would fail
     pub fn new(path: P) -> McJavaWorld<P> {
         McJavaWorld {
-            path
+            path,
+            lock: None,
+            ids: block::SharedHashSystem::new(),
+            biome_ids: biome::SharedHashSystem::new(),
+            level: RwLock::new(None)
+        }
+    }
+
+    // opens a world for writing by acquiring its session lock.
+    // the lock format follows the DataVersion in level.dat.
+    // if another process holds the world, either fail or fall back to read-only
+    pub fn open_for_write(path: P, on_locked: LockPolicy) -> io::Result<McJavaWorld<P>> {
+        let mut world = McJavaWorld::new(path);
+        let level = match world.read_level_data() {
+            Ok(level) => Some(level),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e)
+        };
+        let format = LockFormat::for_data_version(level.as_ref().and_then(|level| level.data_version));
+        *world.level.get_mut().unwrap() = Some(level);
+        match SessionLock::acquire(&world.path, format) {
+            Ok(lock) => world.lock = Some(lock),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock && on_locked == LockPolicy::ReadOnly => {},
+            Err(e) => return Err(e)
+        }
+        Ok(world)
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_ref()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.lock.is_none()
+    }
+
+    // must be called before every write into the world
+    crate fn check_writable(&mut self) -> io::Result<()> {
+        match &mut self.lock {
+            Some(lock) => lock.check(),
+            None => Err(io::Error::new(io::ErrorKind::PermissionDenied, "World is opened read-only"))
         }
     }
 
-    //create
+    // create a new world, with its folders, session lock and `level.dat`.
+    // fails if the folder already holds a level.dat
+    pub fn create(path: P, level: &LevelData) -> io::Result<McJavaWorld<P>> {
+        let dir = path.as_ref();
+        if dir.join("level.dat").exists() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("World already exists: {}", dir.display())));
+        }
+        for folder in ["region", "data", "playerdata"].iter() {
+            fs::create_dir_all(dir.join(folder))?;
+        }
+        let lock = SessionLock::acquire(dir, LockFormat::for_data_version(level.data_version))?;
+        let mut world = McJavaWorld {
+            path,
+            lock: Some(lock),
+            ids: block::SharedHashSystem::new(),
+            biome_ids: biome::SharedHashSystem::new(),
+            level: RwLock::new(None)
+        };
+        world.write_level_data(level)?;
+        Ok(world)
+    }
 
     //sync_data
 
@@ -30,48 +137,721 @@ impl<P: AsRef<Path>> McJavaWorld<P> {
 
     //world_metadata
 
+    pub fn read_level_data(&self) -> io::Result<LevelData> {
+        let data = read_gzip_nbt(self.path.as_ref().join("level.dat"))?;
+        LevelData::from_nbt(data)
+    }
+
+    // writes `level.dat_new` first, then keeps the previous file as `level.dat_old`
+    // so that a crash in between never leaves the world without a valid level.dat
+    pub fn write_level_data(&mut self, level: &LevelData) -> io::Result<()> {
+        self.write_level_nbt(&level.to_nbt())?;
+        *self.level.get_mut().unwrap() = Some(Some(level.clone()));
+        Ok(())
+    }
+
+    crate fn write_level_nbt(&mut self, data: &nbt::Data) -> io::Result<()> {
+        self.check_writable()?;
+        // read again when next needed
+        *self.level.get_mut().unwrap() = None;
+        replace_gzip_nbt(&self.path.as_ref().join("level.dat"), data)
+    }
+
+    // looks into level.dat as first read, or as last written through this world
+    fn with_level_data<T>(&self, f: impl FnOnce(Option<&LevelData>) -> T) -> io::Result<T> {
+        if let Some(level) = &*self.level.read().unwrap() {
+            return Ok(f(level.as_ref()));
+        }
+        let level = match self.read_level_data() {
+            Ok(level) => Some(level),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e)
+        };
+        let ans = f(level.as_ref());
+        *self.level.write().unwrap() = Some(level);
+        Ok(ans)
+    }
+
     // no set_permissions here as it might change in the future
 
-    // Is this process id? I think it's not time here.
-    //todo
-    #[allow(unused)]
-    fn read_session_lock(&self) -> io::Result<i64> {
-        let session_lock_path = self.path.as_ref().join("session.lock");
-        let mut session_lock_file = fs::File::open(session_lock_path)?;
-        session_lock_file.read_i64::<BigEndian>()
-        // files are automatically closed when they go out of scope
+    // block states met in this world by their ids, like `minecraft:oak_log[axis=y]`.
+    // register a state here to get its meta before writing it
+    pub fn block_ids(&self) -> &block::SharedHashSystem {
+        &self.ids
+    }
+
+    // biomes met in this world by their ids, like `minecraft:plains`.
+    // biomes stored as numbers are named as in 1.13 to 1.17
+    pub fn biome_ids(&self) -> &biome::SharedHashSystem {
+        &self.biome_ids
+    }
+
+    // the biome at a block of the overworld, by loading its whole chunk.
+    // `None` if the chunk holds no biomes
+    pub fn read_biome(&self, pos: block::Pos) -> io::Result<Option<biome::Meta>> {
+        let (x, y, z) = pos.to_xyz();
+        check_height(self.height_range(&Dimension::Overworld)?, y)?;
+        let mut buf = chunk::Chunk::new();
+        chunk::ReadExact::read_chunk_exact(self, chunk::Pos::from_xz(x >> 4, z >> 4), &mut buf)?;
+        Ok(buf.get_biome_meta((x & 15) as usize, y, (z & 15) as usize))
+    }
+
+    // sets the biome of the whole column before 1.15, or of the 4*4*4 cell since
+    pub fn write_biome(&mut self, pos: block::Pos, meta: biome::Meta) -> io::Result<()> {
+        let (x, y, z) = pos.to_xyz();
+        check_height(self.height_range(&Dimension::Overworld)?, y)?;
+        let chunk_pos = chunk::Pos::from_xz(x >> 4, z >> 4);
+        let mut buf = chunk::Chunk::new();
+        chunk::ReadExact::read_chunk_exact(self, chunk_pos, &mut buf)?;
+        buf.set_biome_meta((x & 15) as usize, y, (z & 15) as usize, meta);
+        chunk::WriteExact::write_chunk_exact(self, chunk_pos, &buf)
+    }
+
+    // the block entity at a block of the overworld, by loading its whole chunk
+    pub fn read_block_entity(&self, pos: block::Pos) -> io::Result<Option<block_entity::BlockEntity>> {
+        let (x, y, z) = pos.to_xyz();
+        check_height(self.height_range(&Dimension::Overworld)?, y)?;
+        let mut buf = chunk::Chunk::new();
+        chunk::ReadExact::read_chunk_exact(self, pos.to_chunk_pos(), &mut buf)?;
+        Ok(buf.get_block_entity((x & 15) as usize, y, (z & 15) as usize).cloned())
+    }
+
+    // adds or replaces the block entity at its position, leaving the block as is
+    pub fn write_block_entity(&mut self, entity: block_entity::BlockEntity) -> io::Result<()> {
+        check_height(self.height_range(&Dimension::Overworld)?, entity.pos.to_xyz().1)?;
+        let chunk_pos = entity.pos.to_chunk_pos();
+        let mut buf = chunk::Chunk::new();
+        chunk::ReadExact::read_chunk_exact(self, chunk_pos, &mut buf)?;
+        buf.set_block_entity(entity);
+        chunk::WriteExact::write_chunk_exact(self, chunk_pos, &buf)
+    }
+
+    // the format of session.lock, and the timestamp inside for the old format
+    pub fn read_session_lock(&self) -> io::Result<(LockFormat, Option<i64>)> {
+        session::read_session_lock(&self.path)
+    }
+
+    // the overworld, plus every other dimension that exists on disk
+    pub fn dimensions(&self) -> io::Result<Vec<Dimension>> {
+        dimension::list_dimensions(self.path.as_ref())
+    }
+
+    pub fn dimension_path(&self, dim: &Dimension) -> PathBuf {
+        self.path.as_ref().join(dim.folder())
+    }
+
+    // the blocks a dimension spans, as level.dat tells.
+    // level.dat is read once, not on every block
+    pub fn height_range(&self, dim: &Dimension) -> io::Result<HeightRange> {
+        self.with_level_data(|level| match level {
+            Some(level) => level.height_range(dim),
+            None => HeightRange::vanilla(dim, None)
+        })
+    }
+
+    // reads and writes blocks and chunks of another dimension than the overworld
+    pub fn dimension_view(&mut self, dim: Dimension) -> DimensionView<'_, P> {
+        DimensionView { world: self, dim }
     }
 
-    fn read_region(&self, region_x: i32, region_z: i32) -> io::Result<()> {
-        // todo return value type
+    pub fn region_folder(&self, dim: &Dimension, kind: RegionKind) -> PathBuf {
+        self.dimension_path(dim).join(kind.folder_name())
+    }
+
+    pub fn region_path(&self, dim: &Dimension, kind: RegionKind, region_x: i32, region_z: i32) -> PathBuf {
         let file_name = format!("r.{}.{}.mca", region_x, region_z);
-        let file_path = self.path.as_ref().join("region").join(file_name);
-        let region_file = fs::File::open(file_path)?;
-        
-        //Ok(())
-        unimplemented!();
+        self.region_folder(dim, kind).join(file_name)
+    }
+
+    // coordinates of all region files of a kind in a dimension
+    pub fn region_positions(&self, dim: &Dimension, kind: RegionKind) -> io::Result<Vec<(i32, i32)>> {
+        let dir = self.region_folder(dim, kind);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut ans = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file_name = entry?.file_name();
+            if let Some(pos) = file_name.to_str().and_then(|name| region::parse_region_file_name(name, "mca")) {
+                ans.push(pos);
+            }
+        }
+        ans.sort();
+        Ok(ans)
+    }
+
+    pub fn open_region(&self, dim: &Dimension, kind: RegionKind, region_x: i32, region_z: i32) -> io::Result<Region> {
+        Region::open(self.region_path(dim, kind, region_x, region_z))
+    }
+
+    // see `MappedRegion` for when this is safe to use
+    pub fn open_region_mapped(&self, dim: &Dimension, kind: RegionKind, region_x: i32, region_z: i32) -> io::Result<MappedRegion> {
+        MappedRegion::open(self.region_path(dim, kind, region_x, region_z))
+    }
+
+    // creates the region file and its folder if missing
+    pub fn open_region_writable(&mut self, dim: &Dimension, kind: RegionKind, region_x: i32, region_z: i32) -> io::Result<Region> {
+        self.check_writable()?;
+        let path = self.region_path(dim, kind, region_x, region_z);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Region::open_writable(path)
+    }
+
+    // `None` if the chunk or its region file does not exist
+    pub fn read_chunk_nbt(&self, dim: &Dimension, kind: RegionKind, pos: chunk::Pos) -> io::Result<Option<nbt::Data>> {
+        let (region_x, region_z) = region_xz(pos);
+        match self.open_region(dim, kind, region_x, region_z) {
+            Ok(mut region) => region.read_chunk_nbt(pos),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    // the heightmaps saved with a chunk, which are as fresh as its last save.
+    // `None` if the chunk does not exist
+    pub fn read_heightmaps(&self, dim: &Dimension, pos: chunk::Pos) -> io::Result<Option<Vec<(chunk::HeightmapKind, chunk::Heightmap)>>> {
+        match self.read_chunk_nbt(dim, RegionKind::Terrain, pos)? {
+            Some(data) => anvil::anvil_heightmaps(&data, self.height_range(dim)?).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    pub fn write_chunk_nbt(&mut self, dim: &Dimension, kind: RegionKind, pos: chunk::Pos, data: &nbt::Data) -> io::Result<()> {
+        let (region_x, region_z) = region_xz(pos);
+        self.open_region_writable(dim, kind, region_x, region_z)?.write_chunk_nbt(pos, data)
+    }
+
+    fn region_paths(&self, dim: &Dimension, kind: RegionKind) -> io::Result<Vec<(PathBuf, (i32, i32))>> {
+        Ok(self.region_positions(dim, kind)?.into_iter()
+            .map(|(x, z)| (self.region_path(dim, kind, x, z), (x, z)))
+            .collect())
+    }
+
+    // every chunk saved in a dimension, region by region.
+    // a region file that fails to open is reported at its first chunk position
+    pub fn chunks(&self, dim: &Dimension, kind: RegionKind) -> io::Result<ChunkIter> {
+        Ok(ChunkIter::new(self.region_paths(dim, kind)?, false))
+    }
+
+    // like `chunks`, with region files memory mapped, which is faster for
+    // scanning whole worlds. a mapped file must not be written to meanwhile,
+    // by a game or by this process, see `MappedRegion`. no session lock is
+    // taken or needed, so scan worlds no game is running on
+    pub fn chunks_mapped(&self, dim: &Dimension, kind: RegionKind) -> io::Result<ChunkIter> {
+        Ok(ChunkIter::new(self.region_paths(dim, kind)?, true))
+    }
+
+    // like `chunks`, with region files read on `threads` worker threads
+    pub fn par_chunks(&self, dim: &Dimension, kind: RegionKind, threads: usize) -> io::Result<ParChunkIter<(chunk::Pos, io::Result<nbt::Data>)>> {
+        self.par_map_chunks(dim, kind, threads, |pos, data| (pos, data))
+    }
+
+    // run `f` on every chunk on the worker threads, and iterate over its results.
+    // this keeps the work of analysing chunks on the workers too
+    pub fn par_map_chunks<T, F>(&self, dim: &Dimension, kind: RegionKind, threads: usize, f: F) -> io::Result<ParChunkIter<T>>
+    where T: Send + 'static, F: Fn(chunk::Pos, io::Result<nbt::Data>) -> T + Send + Sync + 'static {
+        Ok(ParChunkIter::new(self.region_paths(dim, kind)?, false, threads, f))
+    }
+
+    // like `par_map_chunks`, with region files memory mapped as `chunks_mapped` does
+    pub fn par_map_chunks_mapped<T, F>(&self, dim: &Dimension, kind: RegionKind, threads: usize, f: F) -> io::Result<ParChunkIter<T>>
+    where T: Send + 'static, F: Fn(chunk::Pos, io::Result<nbt::Data>) -> T + Send + Sync + 'static {
+        Ok(ParChunkIter::new(self.region_paths(dim, kind)?, true, threads, f))
+    }
+
+    pub fn player_data_path(&self, uuid: Uuid) -> PathBuf {
+        self.path.as_ref().join("playerdata").join(format!("{}.dat", uuid))
+    }
+
+    // every player that has joined the world
+    pub fn player_uuids(&self) -> io::Result<Vec<Uuid>> {
+        uuid_files(&self.path.as_ref().join("playerdata"), ".dat")
+    }
+
+    // `None` if the player never joined
+    pub fn read_player_data(&self, uuid: Uuid) -> io::Result<Option<PlayerData>> {
+        match read_gzip_nbt(self.player_data_path(uuid)) {
+            Ok(data) => PlayerData::from_nbt(data).map(Some),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    // a running server overwrites this when the player is online
+    pub fn write_player_data(&mut self, uuid: Uuid, player: &PlayerData) -> io::Result<()> {
+        self.check_writable()?;
+        let path = self.player_data_path(uuid);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        replace_gzip_nbt(&path, &player.to_nbt()?)
+    }
+
+    pub fn player_stats_path(&self, uuid: Uuid) -> PathBuf {
+        self.path.as_ref().join("stats").join(format!("{}.json", uuid))
+    }
+
+    pub fn read_player_stats(&self, uuid: Uuid) -> io::Result<Option<PlayerStats>> {
+        match fs::read_to_string(self.player_stats_path(uuid)) {
+            Ok(json) => PlayerStats::from_json(&json).map(Some),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    pub fn write_player_stats(&mut self, uuid: Uuid, stats: &PlayerStats) -> io::Result<()> {
+        self.check_writable()?;
+        fs::create_dir_all(self.path.as_ref().join("stats"))?;
+        replace_json(&self.player_stats_path(uuid), &stats.to_json())
     }
 
+    // statistics of every player, see `stats_leaderboard` and `stats_total`
+    pub fn read_all_player_stats(&self) -> io::Result<Vec<(Uuid, PlayerStats)>> {
+        let mut ans = Vec::new();
+        for uuid in uuid_files(&self.path.as_ref().join("stats"), ".json")? {
+            if let Some(stats) = self.read_player_stats(uuid)? {
+                ans.push((uuid, stats));
+            }
+        }
+        Ok(ans)
+    }
+
+    pub fn player_advancements_path(&self, uuid: Uuid) -> PathBuf {
+        self.path.as_ref().join("advancements").join(format!("{}.json", uuid))
+    }
+
+    pub fn read_player_advancements(&self, uuid: Uuid) -> io::Result<Option<PlayerAdvancements>> {
+        match fs::read_to_string(self.player_advancements_path(uuid)) {
+            Ok(json) => PlayerAdvancements::from_json(&json).map(Some),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    pub fn write_player_advancements(&mut self, uuid: Uuid, advancements: &PlayerAdvancements) -> io::Result<()> {
+        self.check_writable()?;
+        fs::create_dir_all(self.path.as_ref().join("advancements"))?;
+        replace_json(&self.player_advancements_path(uuid), &advancements.to_json())
+    }
+
+    // see `advancements_leaderboard`
+    pub fn read_all_player_advancements(&self) -> io::Result<Vec<(Uuid, PlayerAdvancements)>> {
+        let mut ans = Vec::new();
+        for uuid in uuid_files(&self.path.as_ref().join("advancements"), ".json")? {
+            if let Some(advancements) = self.read_player_advancements(uuid)? {
+                ans.push((uuid, advancements));
+            }
+        }
+        Ok(ans)
+    }
+
+    // `data/<name>.dat`, like `raids` or `map_0`. before 1.16 other dimensions
+    // keep some of theirs in their own folder, like `DIM-1/data/raids.dat`
+    pub fn saved_data_path(&self, dim: &Dimension, name: &str) -> PathBuf {
+        self.dimension_path(dim).join("data").join(format!("{}.dat", name))
+    }
+
+    // names of all saved data of a dimension, sorted
+    pub fn saved_data_names(&self, dim: &Dimension) -> io::Result<Vec<String>> {
+        let dir = self.dimension_path(dim).join("data");
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut ans = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file_name = entry?.file_name();
+            if let Some(name) = file_name.to_str().filter(|name| name.ends_with(".dat")) {
+                ans.push(name[..name.len() - 4].to_string());
+            }
+        }
+        ans.sort();
+        Ok(ans)
+    }
+
+    // `None` if the game never saved it; read as `nbt::Compound` for any other kind
+    pub fn read_saved_data<T: SavedDataKind>(&self, dim: &Dimension, name: &str) -> io::Result<Option<SavedData<T>>> {
+        match read_gzip_nbt(self.saved_data_path(dim, name)) {
+            Ok(data) => SavedData::from_nbt(data).map(Some),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+
+    // written to `<name>.dat_new` first like level.dat, keeping the last
+    // version as `<name>.dat_old`
+    pub fn write_saved_data<T: SavedDataKind>(&mut self, dim: &Dimension, name: &str, data: &SavedData<T>) -> io::Result<()> {
+        self.check_writable()?;
+        let path = self.saved_data_path(dim, name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        replace_gzip_nbt(&path, &data.to_nbt())
+    }
+
+    // entities of a chunk, stored apart from terrain since 1.17
+    pub fn read_entity_chunk(&self, dim: &Dimension, pos: chunk::Pos) -> io::Result<Option<EntityChunk>> {
+        match self.read_chunk_nbt(dim, RegionKind::Entities, pos)? {
+            Some(data) => EntityChunk::from_nbt(data).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    pub fn write_entity_chunk(&mut self, dim: &Dimension, entities: &EntityChunk) -> io::Result<()> {
+        self.write_chunk_nbt(dim, RegionKind::Entities, entities.pos, &entities.to_nbt())
+    }
+
+    // the entities of a chunk, from the chunk itself before 1.17 or its entity chunk
+    // since. `None` if neither exists
+    pub fn read_entities(&self, dim: &Dimension, pos: chunk::Pos) -> io::Result<Option<Vec<entity::Entity>>> {
+        let terrain = self.read_chunk_nbt(dim, RegionKind::Terrain, pos)?;
+        if let Some(data) = &terrain {
+            if let Some(entities) = anvil::anvil_entities(data)? {
+                return Ok(Some(entities));
+            }
+        }
+        match self.read_entity_chunk(dim, pos)? {
+            Some(entities) => Ok(Some(entities.entities)),
+            None => Ok(terrain.map(|_| Vec::new()))
+        }
+    }
+
+    // replace the entities of a chunk wherever its DataVersion keeps them.
+    // fail if an entity is out of the chunk
+    pub fn write_entities(&mut self, dim: &Dimension, pos: chunk::Pos, entities: &[entity::Entity]) -> io::Result<()> {
+        let terrain = self.read_chunk_nbt(dim, RegionKind::Terrain, pos)?;
+        let data_version = self.chunk_data_version(terrain.as_ref())?;
+        if data_version < entity::DATA_VERSION_ENTITY_CHUNKS {
+            let mut data = terrain.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("Chunk not found: {:?}", pos)))?;
+            anvil::set_anvil_entities(&mut data, pos, entities)?;
+            return self.write_chunk_nbt(dim, RegionKind::Terrain, pos, &data);
+        }
+        let mut entity_chunk = self.read_entity_chunk(dim, pos)?.unwrap_or_else(|| EntityChunk::new(pos, data_version));
+        if let Some(entity) = entities.iter().find(|entity| entity.chunk_pos() != pos) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Entity out of chunk: {:?}", entity.pos)));
+        }
+        entity_chunk.entities = entities.to_vec();
+        self.write_entity_chunk(dim, &entity_chunk)
+    }
+
+    // chunks with terrain or entities saved, of the whole dimension or those the area reaches into.
+    // only saved chunks are listed, however large the area is
+    fn entity_chunk_positions(&self, dim: &Dimension, area: Option<&entity::BoundingBox>) -> io::Result<Vec<chunk::Pos>> {
+        let ranges = area.map(entity::BoundingBox::chunk_ranges);
+        // whether the 32 chunks from `min` overlap the range
+        let overlaps = |range: &std::ops::RangeInclusive<i32>, min: i32| *range.start() < min + 32 && *range.end() >= min;
+        let mut ans = std::collections::HashSet::new();
+        for &kind in [RegionKind::Terrain, RegionKind::Entities].iter() {
+            for (region_x, region_z) in self.region_positions(dim, kind)? {
+                if let Some((xs, zs)) = &ranges {
+                    if !overlaps(xs, region_x * 32) || !overlaps(zs, region_z * 32) {
+                        continue;
+                    }
+                }
+                let positions = self.open_region(dim, kind, region_x, region_z)?.chunk_positions();
+                ans.extend(positions.into_iter().filter(|pos| match &ranges {
+                    Some((xs, zs)) => xs.contains(&pos.to_xz().0) && zs.contains(&pos.to_xz().1),
+                    None => true
+                }));
+            }
+        }
+        let mut ans: Vec<_> = ans.into_iter().collect();
+        ans.sort_by_key(|pos| pos.to_xz());
+        Ok(ans)
+    }
+
+    // how many entities of each id there are, in the whole dimension or in the area.
+    // passengers are counted with what they ride on only
+    pub fn count_entities(&self, dim: &Dimension, area: Option<&entity::BoundingBox>) -> io::Result<HashMap<String, usize>> {
+        let mut ans = HashMap::new();
+        for pos in self.entity_chunk_positions(dim, area)? {
+            for entity in self.read_entities(dim, pos)?.unwrap_or_default() {
+                if entity::BoundingBox::contains_opt(area, entity.pos) {
+                    *ans.entry(entity.id).or_insert(0) += 1;
+                }
+            }
+        }
+        Ok(ans)
+    }
+
+    // remove the entities `f` returns true for, in the whole dimension or in the area,
+    // returning how many were removed. chunks without any are left untouched
+    pub fn remove_entities(&mut self, dim: &Dimension, area: Option<&entity::BoundingBox>,
+        mut f: impl FnMut(&entity::Entity) -> bool) -> io::Result<usize> {
+        let mut ans = 0;
+        for pos in self.entity_chunk_positions(dim, area)? {
+            let mut entities = match self.read_entities(dim, pos)? {
+                Some(entities) => entities,
+                None => continue
+            };
+            let len = entities.len();
+            entities.retain(|entity| !(entity::BoundingBox::contains_opt(area, entity.pos) && f(entity)));
+            if entities.len() < len {
+                ans += len - entities.len();
+                self.write_entities(dim, pos, &entities)?;
+            }
+        }
+        Ok(ans)
+    }
+
+    // the DataVersion of a chunk, or of level.dat for chunks not saved yet
+    fn chunk_data_version(&self, terrain: Option<&nbt::Data>) -> io::Result<i32> {
+        match terrain {
+            Some(data) => data.root_tag().as_compound()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid chunk root tag"))
+                .and_then(anvil::chunk_data_version),
+            None => self.with_level_data(|level| level.and_then(|level| level.data_version))?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "World without DataVersion is not supported"))
+        }
+    }
+
+    pub fn read_poi_chunk(&self, dim: &Dimension, pos: chunk::Pos) -> io::Result<Option<PoiChunk>> {
+        match self.read_chunk_nbt(dim, RegionKind::Poi, pos)? {
+            Some(data) => PoiChunk::from_nbt(data).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    pub fn write_poi_chunk(&mut self, dim: &Dimension, pos: chunk::Pos, poi: &PoiChunk) -> io::Result<()> {
+        self.write_chunk_nbt(dim, RegionKind::Poi, pos, &poi.to_nbt())
+    }
+}
+
+
+crate fn read_gzip_nbt(path: impl AsRef<Path>) -> io::Result<nbt::Data> {
+    let file = io::BufReader::new(fs::File::open(path)?);
+    GzDecoder::new(file).read_nbt_data()
+}
+
+// players with a `<uuid><extension>` file in the folder, sorted
+fn uuid_files(dir: &Path, extension: &str) -> io::Result<Vec<Uuid>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut ans = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let file_name = entry?.file_name();
+        let uuid = file_name.to_str()
+            .filter(|name| name.ends_with(extension))
+            .and_then(|name| Uuid::parse(&name[..name.len() - extension.len()]));
+        if let Some(uuid) = uuid {
+            ans.push(uuid);
+        }
+    }
+    ans.sort();
+    Ok(ans)
+}
+
+// write `<file>_new` first, then keep the previous file as `<file>_old`,
+// so that a crash in between never leaves no valid file behind
+crate fn replace_file(path: &Path, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let with_suffix = |suffix: &str| {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        path.with_file_name(name)
+    };
+    let new_path = with_suffix("_new");
+    write(&new_path)?;
+    if path.exists() {
+        fs::copy(path, with_suffix("_old"))?;
+    }
+    fs::rename(new_path, path)
+}
+
+crate fn replace_gzip_nbt(path: &Path, data: &nbt::Data) -> io::Result<()> {
+    replace_file(path, |new_path| write_gzip_nbt(new_path, data))
+}
+
+fn replace_json(path: &Path, json: &str) -> io::Result<()> {
+    replace_file(path, |new_path| {
+        let mut file = fs::File::create(new_path)?;
+        io::Write::write_all(&mut file, json.as_bytes())?;
+        file.sync_all()
+    })
+}
+
+crate fn write_gzip_nbt(path: impl AsRef<Path>, data: &nbt::Data) -> io::Result<()> {
+    let file = io::BufWriter::new(fs::File::create(path)?);
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder.write_nbt_data(data)?;
+    let mut file = encoder.finish()?;
+    io::Write::flush(&mut file)?;
+    file.get_ref().sync_all()
 }
 
+fn check_height(range: HeightRange, y: i32) -> io::Result<()> {
+    if !range.contains(y) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+            format!("Block y out of range: {}, dimension spans {}..{}", y, range.min_y(), range.max_y())));
+    }
+    Ok(())
+}
+
+// blocks are read by loading their whole chunk, and written by loading, changing
+// and saving back their whole chunk. prefer a `BufferedWorld` to write many
+impl<P: AsRef<Path>> McJavaWorld<P> {
+    fn read_block_in(&self, dim: &Dimension, pos: block::Pos) -> io::Result<block::Meta> {
+        let (x, y, z) = pos.to_xyz();
+        check_height(self.height_range(dim)?, y)?;
+        let mut buf = chunk::Chunk::new();
+        self.read_chunk_in(dim, chunk::Pos::from_xz(x >> 4, z >> 4), &mut buf)?;
+        Ok(buf.get_block_meta((x & 15) as usize, y, (z & 15) as usize))
+    }
+
+    fn contains_block_in(&self, dim: &Dimension, pos: block::Pos) -> io::Result<bool> {
+        let (x, y, z) = pos.to_xyz();
+        if !self.height_range(dim)?.contains(y) {
+            return Ok(false);
+        }
+        self.contains_chunk_in(dim, chunk::Pos::from_xz(x >> 4, z >> 4))
+    }
+
+    fn write_block_in(&mut self, dim: &Dimension, pos: block::Pos, meta: block::Meta) -> io::Result<()> {
+        let (x, y, z) = pos.to_xyz();
+        check_height(self.height_range(dim)?, y)?;
+        let chunk_pos = chunk::Pos::from_xz(x >> 4, z >> 4);
+        let mut buf = chunk::Chunk::new();
+        self.read_chunk_in(dim, chunk_pos, &mut buf)?;
+        buf.set_block_meta((x & 15) as usize, y, (z & 15) as usize, meta);
+        self.write_chunk_in(dim, chunk_pos, &buf)
+    }
+
+    fn read_chunk_in(&self, dim: &Dimension, pos: chunk::Pos, buf: &mut chunk::Chunk) -> io::Result<()> {
+        match self.read_chunk_nbt(dim, RegionKind::Terrain, pos)? {
+            Some(data) => anvil::anvil_to_chunk(&data, &self.ids, &self.biome_ids, self.height_range(dim)?, buf)?,
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("Chunk not found: {:?}", pos)))
+        }
+        // since 1.17 entities are in their own region files
+        if !buf.has_entities() {
+            buf.set_entities(self.read_entities(dim, pos)?);
+        }
+        Ok(())
+    }
+
+    fn contains_chunk_in(&self, dim: &Dimension, pos: chunk::Pos) -> io::Result<bool> {
+        let (region_x, region_z) = region_xz(pos);
+        match self.open_region(dim, RegionKind::Terrain, region_x, region_z) {
+            Ok(region) => Ok(region.contains_chunk(pos)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e)
+        }
+    }
+
+    // keeps whatever the chunk does not hold of the chunk saved there before,
+    // see `anvil::chunk_to_anvil`. new chunks take the DataVersion of level.dat
+    fn write_chunk_in(&mut self, dim: &Dimension, pos: chunk::Pos, chunk: &chunk::Chunk) -> io::Result<()> {
+        let original = self.read_chunk_nbt(dim, RegionKind::Terrain, pos)?;
+        let data_version = self.chunk_data_version(original.as_ref())?;
+        let data = anvil::chunk_to_anvil(chunk, pos, &self.ids, &self.biome_ids, original.as_ref(), data_version)?;
+        self.write_chunk_nbt(dim, RegionKind::Terrain, pos, &data)?;
+        if chunk.has_entities() && data_version >= entity::DATA_VERSION_ENTITY_CHUNKS {
+            self.write_entities(dim, pos, chunk.entities())?;
+        }
+        Ok(())
+    }
+}
 
+// the world itself reads and writes blocks and chunks of the overworld
 impl<P: AsRef<Path>> block::ReadExact for McJavaWorld<P> {
-    fn read_block_exact(&self, _pos: block::Pos) -> Result<block::Meta> {
-        unimplemented!()
+    fn read_block_exact(&self, pos: block::Pos) -> Result<block::Meta> {
+        self.read_block_in(&Dimension::Overworld, pos)
     }
 
-    fn contain_block_exact(&self, _pos: block::Pos) -> Result<bool> {
-        unimplemented!()
+    fn contain_block_exact(&self, pos: block::Pos) -> Result<bool> {
+        self.contains_block_in(&Dimension::Overworld, pos)
     }
 }
 
 impl<P: AsRef<Path>> chunk::ReadExact for McJavaWorld<P> {
-    fn read_chunk_exact(&self, _pos: chunk::Pos, _buf: &mut chunk::Chunk) -> Result<()>{
-        unimplemented!()
-    } 
+    fn read_chunk_exact(&self, pos: chunk::Pos, buf: &mut chunk::Chunk) -> Result<()> {
+        self.read_chunk_in(&Dimension::Overworld, pos, buf)
+    }
+
+    fn contains_chunk_exact(&self, pos: chunk::Pos) -> Result<bool> {
+        self.contains_chunk_in(&Dimension::Overworld, pos)
+    }
+}
+
+impl<P: AsRef<Path>> block::WriteExact for McJavaWorld<P> {
+    fn write_block_exact(&mut self, pos: block::Pos, meta: block::Meta) -> Result<()> {
+        self.write_block_in(&Dimension::Overworld, pos, meta)
+    }
+
+    // every write is already on disk
+    fn flush_block(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<P: AsRef<Path>> chunk::WriteExact for McJavaWorld<P> {
+    fn write_chunk_exact(&mut self, pos: chunk::Pos, chunk: &chunk::Chunk) -> Result<()> {
+        self.write_chunk_in(&Dimension::Overworld, pos, chunk)
+    }
+}
+
+impl<P: AsRef<Path>> block::IdOperate for McJavaWorld<P> {
+    fn block_id_system(&self) -> &dyn block::IdSystem {
+        &self.ids
+    }
+}
+
+// one dimension of a world, read and written like the world does the overworld.
+// wrap it into a `Cursor` or a `BufferedWorld` the same way
+pub struct DimensionView<'a, P: AsRef<Path>> {
+    world: &'a mut McJavaWorld<P>,
+    dim: Dimension,
+}
 
-    fn contains_chunk_exact(&self, _pos: chunk::Pos) -> Result<bool> {
-        unimplemented!()
+impl<'a, P: AsRef<Path>> DimensionView<'a, P> {
+    pub fn dimension(&self) -> &Dimension {
+        &self.dim
+    }
+
+    pub fn world(&self) -> &McJavaWorld<P> {
+        self.world
+    }
+}
+
+impl<'a, P: AsRef<Path>> block::ReadExact for DimensionView<'a, P> {
+    fn read_block_exact(&self, pos: block::Pos) -> Result<block::Meta> {
+        self.world.read_block_in(&self.dim, pos)
+    }
+
+    fn contain_block_exact(&self, pos: block::Pos) -> Result<bool> {
+        self.world.contains_block_in(&self.dim, pos)
+    }
+}
+
+impl<'a, P: AsRef<Path>> chunk::ReadExact for DimensionView<'a, P> {
+    fn read_chunk_exact(&self, pos: chunk::Pos, buf: &mut chunk::Chunk) -> Result<()> {
+        self.world.read_chunk_in(&self.dim, pos, buf)
+    }
+
+    fn contains_chunk_exact(&self, pos: chunk::Pos) -> Result<bool> {
+        self.world.contains_chunk_in(&self.dim, pos)
+    }
+}
+
+impl<'a, P: AsRef<Path>> block::WriteExact for DimensionView<'a, P> {
+    fn write_block_exact(&mut self, pos: block::Pos, meta: block::Meta) -> Result<()> {
+        self.world.write_block_in(&self.dim, pos, meta)
+    }
+
+    fn flush_block(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a, P: AsRef<Path>> chunk::WriteExact for DimensionView<'a, P> {
+    fn write_chunk_exact(&mut self, pos: chunk::Pos, chunk: &chunk::Chunk) -> Result<()> {
+        self.world.write_chunk_in(&self.dim, pos, chunk)
+    }
+}
+
+impl<'a, P: AsRef<Path>> block::IdOperate for DimensionView<'a, P> {
+    fn block_id_system(&self) -> &dyn block::IdSystem {
+        &self.world.ids
     }
 }
 
@@ -82,7 +862,1312 @@ mod tests {
     #[test]
     fn session_lock() -> io::Result<()> {
         let world = McJavaWorld::new("./test_worlds/water_only");
-        println!("{}", world.read_session_lock()?);
+        assert_eq!(world.read_session_lock()?, (LockFormat::Timestamp, Some(0xc70c)));
+        assert!(world.is_read_only());
+        Ok(())
+    }
+
+    #[test]
+    fn session_lock_held() -> io::Result<()> {
+        let path = copy_test_world("session_lock_held")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        assert!(!world.is_read_only());
+        let (format, timestamp) = world.read_session_lock()?;
+        assert_eq!(format, LockFormat::Timestamp);
+        assert_ne!(timestamp, Some(0xc70c));
+        let level = world.read_level_data()?;
+        world.write_level_data(&level)?;
+        // an older process taking over by rewriting the timestamp
+        fs::write(path.join("session.lock"), [0u8; 8])?;
+        assert!(world.write_level_data(&level).is_err());
+        drop(world);
+        let world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        assert!(!world.is_read_only());
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    // a second open from this process stands for another process, which
+    // only holds where the lock belongs to the handle, see `session::LOCK_COMMAND`
+    #[cfg(any(target_os = "linux", target_os = "android", windows))]
+    #[test]
+    fn session_lock_other_handle() -> io::Result<()> {
+        let path = copy_test_world("session_lock_other_handle")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let err = McJavaWorld::open_for_write(&path, LockPolicy::Refuse).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        let mut other = McJavaWorld::open_for_write(&path, LockPolicy::ReadOnly)?;
+        assert!(other.is_read_only());
+        let level = other.read_level_data()?;
+        assert!(other.write_level_data(&level).is_err());
+        // reading the lock through another handle keeps it held
+        other.read_session_lock()?;
+        world.write_level_data(&level)?;
+        drop(other);
+        assert!(McJavaWorld::open_for_write(&path, LockPolicy::Refuse).is_err());
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn session_lock_java() -> io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+        let path = copy_test_world("session_lock_java")?;
+        let before = fs::read(path.join("session.lock"))?;
+        // a running game holds an fcntl record lock, as `FileChannel.tryLock` takes it
+        let file = fs::OpenOptions::new().read(true).write(true).open(path.join("session.lock"))?;
+        let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+        lock.l_type = libc::F_WRLCK as _;
+        lock.l_whence = libc::SEEK_SET as _;
+        assert_eq!(unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock) }, 0);
+        let err = McJavaWorld::open_for_write(&path, LockPolicy::Refuse).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        // the stamp of the game is left alone
+        assert_eq!(fs::read(path.join("session.lock"))?, before);
+        drop(file);
+        fs::remove_dir_all(path)
+    }
+
+    // copies a test world so that tests may write into it
+    fn copy_test_world(name: &str) -> io::Result<std::path::PathBuf> {
+        fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+            fs::create_dir_all(to)?;
+            for entry in fs::read_dir(from)? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+                } else {
+                    fs::copy(entry.path(), to.join(entry.file_name()))?;
+                }
+            }
+            Ok(())
+        }
+        let to = std::env::temp_dir().join(format!("mc-types-test-{}", name));
+        if to.exists() {
+            fs::remove_dir_all(&to)?;
+        }
+        copy_dir(Path::new("./test_worlds/water_only"), &to)?;
+        Ok(to)
+    }
+
+    #[test]
+    fn level_data() -> io::Result<()> {
+        let world = McJavaWorld::new("./test_worlds/water_only");
+        let level = world.read_level_data()?;
+        assert_eq!(level.seed, -3363745190469074761);
+        assert_eq!(level.spawn.to_xyz(), (8, 55, 8));
+        assert_eq!(level.game_type, GameType::Creative);
+        assert_eq!(level.difficulty, Difficulty::Peaceful);
+        assert!(!level.hardcore);
+        assert_eq!(level.data_version, Some(1519));
+        assert_eq!(level.version.as_ref().map(|v| v.name.as_str()), Some("1.13"));
+        assert_eq!(level.time, 60300);
+        assert_eq!(level.day_time, 44027);
+        assert_eq!(level.world_border.size, 60_000_000.0);
+        assert_eq!(level.weather.rain_time, 5129);
+        assert_eq!(level.generator.as_ref().map(|g| g.name.as_str()), Some("flat"));
+        Ok(())
+    }
+
+    #[test]
+    fn create_world() -> io::Result<()> {
+        let path = std::env::temp_dir().join("mc-types-test-create_world");
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        let mut level = LevelData::new("created", 42);
+        level.game_type = GameType::Creative;
+        level.data_version = Some(1519);
+        let mut generator = Generator::new("flat");
+        generator.options = Some(nbt::Tag::String("3;minecraft:bedrock,2*minecraft:dirt,minecraft:grass;1".to_string()));
+        level.generator = Some(generator);
+        let mut world = McJavaWorld::create(&path, &level)?;
+        assert!(!world.is_read_only());
+        assert_eq!(world.read_level_data()?, level);
+        assert_eq!(world.read_session_lock()?.0, LockFormat::Timestamp);
+        for folder in ["region", "data", "playerdata"].iter() {
+            assert!(path.join(folder).is_dir());
+        }
+        let pos = chunk::Pos::from_xz(3, -4);
+        world.write_chunk_nbt(&Dimension::Overworld, RegionKind::Terrain, pos, &legacy_chunk_nbt(pos))?;
+        drop(world);
+        let err = McJavaWorld::create(&path, &level).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn write_level_data() -> io::Result<()> {
+        let path = copy_test_world("write_level_data")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let original = read_gzip_nbt(path.join("level.dat"))?;
+        let mut level = world.read_level_data()?;
+        assert_eq!(level.to_nbt(), original); // nothing is lost
+        level.level_name = "renamed".to_string();
+        level.game_rules.insert("doDaylightCycle".to_string(), "false".to_string());
+        world.write_level_data(&level)?;
+        assert_eq!(world.read_level_data()?, level);
+        assert_eq!(read_gzip_nbt(path.join("level.dat_old"))?, original);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn level_data_minimal() -> io::Result<()> {
+        let mut data = nbt::Compound::new();
+        data.insert("LevelName".to_string(), Tag::String("minimal".to_string()));
+        data.insert("RandomSeed".to_string(), Tag::Long(7));
+        for key in ["SpawnX", "SpawnY", "SpawnZ", "GameType"].iter() {
+            data.insert(key.to_string(), Tag::Int(0));
+        }
+        data.insert("Time".to_string(), Tag::Long(100));
+        data.insert("raining".to_string(), Tag::Byte(0));
+        let mut root = nbt::Compound::new();
+        root.insert("Data".to_string(), Tag::Compound(data));
+        let original = nbt::Data::new("", Tag::Compound(root));
+        let mut level = LevelData::from_nbt(original.clone())?;
+        assert_eq!(level.day_time, 100);
+        // no defaults are added for keys that were not there
+        assert_eq!(level.to_nbt(), original);
+        level.hardcore = true;
+        level.weather.rain_time = 20;
+        let data = level.to_nbt().into_root_tag();
+        let data = data.as_compound().and_then(|root| root.get("Data")).and_then(Tag::as_compound).unwrap();
+        assert_eq!(data.get("hardcore"), Some(&Tag::Byte(1)));
+        assert_eq!(data.get("rainTime"), Some(&Tag::Int(20)));
+        assert_eq!(data.get("raining"), Some(&Tag::Byte(0)));
+        for key in ["GameRules", "BorderSize", "thundering", "DayTime", "LastPlayed", "Difficulty"].iter() {
+            assert!(!data.contains_key(*key), "{}", key);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn dimensions() -> io::Result<()> {
+        let path = copy_test_world("dimensions")?;
+        let world = McJavaWorld::new(&path);
+        assert_eq!(world.dimensions()?, vec![Dimension::Overworld]);
+        assert_eq!(world.region_positions(&Dimension::Overworld, RegionKind::Terrain)?.len(), 12);
+        fs::create_dir_all(path.join("DIM-1/region"))?;
+        fs::create_dir_all(path.join("dimensions/mypack/sky/islands/region"))?;
+        fs::create_dir_all(path.join("dimensions/mypack/sky/data"))?;
+        let custom = Dimension::from_id("mypack:sky/islands").unwrap();
+        assert_eq!(world.dimensions()?, vec![Dimension::Overworld, Dimension::Nether, custom.clone()]);
+        assert_eq!(world.dimension_path(&custom), path.join("dimensions/mypack/sky/islands"));
+        assert_eq!(Dimension::from_id("the_end"), Some(Dimension::End));
+        assert!(world.read_chunk_nbt(&custom, RegionKind::Terrain, chunk::Pos::from_xz(0, 0))?.is_none());
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn region() -> io::Result<()> {
+        let path = copy_test_world("region")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let dim = Dimension::Overworld;
+        let mut region = world.open_region(&dim, RegionKind::Terrain, 1, 1)?;
+        let positions = region.chunk_positions();
+        assert!(!positions.is_empty());
+        assert!(positions.iter().all(|&pos| region_xz(pos) == (1, 1)));
+        let data = region.read_chunk_nbt(positions[0])?.unwrap();
+        // move the chunk into a brand new region
+        let pos = chunk::Pos::from_xz(-100, 100);
+        assert!(world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.is_none());
+        world.write_chunk_nbt(&dim, RegionKind::Terrain, pos, &data)?;
+        assert_eq!(world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?, Some(data.clone()));
+        let mut region = world.open_region_writable(&dim, RegionKind::Terrain, -4, 3)?;
+        assert_eq!(region.chunk_positions(), vec![pos]);
+        // a bigger chunk no longer fits into its old sectors
+        let mut big = data.clone();
+        big.root_tag_mut().as_compound_mut().unwrap()
+            .insert("Padding".to_string(), nbt::Tag::LongArray((0..2048).map(|i| i * 7919).collect()));
+        region.write_chunk_nbt(chunk::Pos::from_xz(-99, 100), &data)?;
+        region.write_chunk_nbt(pos, &big)?;
+        assert_eq!(region.read_chunk_nbt(pos)?, Some(big));
+        assert_eq!(region.read_chunk_nbt(chunk::Pos::from_xz(-99, 100))?, Some(data));
+        region.remove_chunk(pos)?;
+        assert!(!region.contains_chunk(pos));
+        assert!(world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.is_none());
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn mapped_region() -> io::Result<()> {
+        let world = McJavaWorld::new("./test_worlds/water_only");
+        let dim = Dimension::Overworld;
+        let mut region = world.open_region(&dim, RegionKind::Terrain, 1, 1)?;
+        let mapped = world.open_region_mapped(&dim, RegionKind::Terrain, 1, 1)?;
+        assert_eq!(mapped.position(), (1, 1));
+        assert_eq!(mapped.chunk_positions(), region.chunk_positions());
+        for pos in region.chunk_positions() {
+            let (compression, payload) = mapped.chunk_payload(pos)?.unwrap();
+            assert_eq!(region.read_chunk_raw(pos)?, Some((compression, payload.to_vec())));
+            assert_eq!(mapped.read_chunk_nbt(pos)?, region.read_chunk_nbt(pos)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn iterate_chunks() -> io::Result<()> {
+        let world = McJavaWorld::new("./test_worlds/water_only");
+        let dim = Dimension::Overworld;
+        let mut expected = Vec::new();
+        for (pos, data) in world.chunks(&dim, RegionKind::Terrain)? {
+            data?;
+            expected.push(pos.to_xz());
+        }
+        assert!(!expected.is_empty());
+        let mut positions = world.par_map_chunks(&dim, RegionKind::Terrain, 4, |pos, data| data.map(|_| pos.to_xz()))?
+            .collect::<io::Result<Vec<_>>>()?;
+        positions.sort();
+        expected.sort();
+        assert_eq!(positions, expected);
+        // stopping early leaves no worker behind
+        assert_eq!(world.par_chunks(&dim, RegionKind::Terrain, 2)?.take(3).count(), 3);
+        assert_eq!(world.chunks(&dim, RegionKind::Poi)?.count(), 0);
+        // mapping needs no session lock, leaving session.lock as it is
+        let lock = fs::read(world.path().join("session.lock"))?;
+        let mut mapped = world.chunks_mapped(&dim, RegionKind::Terrain)?
+            .map(|(pos, data)| data.map(|_| pos.to_xz()))
+            .collect::<io::Result<Vec<_>>>()?;
+        mapped.sort();
+        assert_eq!(mapped, expected);
+        let mut mapped = world.par_map_chunks_mapped(&dim, RegionKind::Terrain, 4, |pos, data| data.map(|_| pos.to_xz()))?
+            .collect::<io::Result<Vec<_>>>()?;
+        mapped.sort();
+        assert_eq!(mapped, expected);
+        assert_eq!(fs::read(world.path().join("session.lock"))?, lock);
+        Ok(())
+    }
+
+    #[test]
+    fn trim() -> io::Result<()> {
+        let path = copy_test_world("trim")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let dim = Dimension::Overworld;
+        let total = world.chunks(&dim, RegionKind::Terrain)?.count();
+        let options = TrimOptions {
+            inhabited_below: Some(1_000_000_000),
+            dry_run: true,
+            ..TrimOptions::default()
+        };
+        let report = trim_world(&mut world, &dim, &options)?;
+        assert_eq!(report.chunks_removed, total);
+        assert_eq!(report.regions_removed, 12);
+        assert_eq!(world.chunks(&dim, RegionKind::Terrain)?.count(), total);
+        // keep a single chunk of region 1, 1
+        let center = world.open_region(&dim, RegionKind::Terrain, 1, 1)?.chunk_positions()[0];
+        let region_path = world.region_path(&dim, RegionKind::Terrain, 1, 1);
+        let size = fs::metadata(&region_path)?.len();
+        let keep = ChunkArea::new(center, center);
+        let options = TrimOptions {
+            keep_areas: vec![keep],
+            outside_keep_areas: true,
+            ..TrimOptions::default()
+        };
+        let report = trim_world(&mut world, &dim, &options)?;
+        assert_eq!(report.chunks_kept + report.chunks_removed, total);
+        let mut kept = Vec::new();
+        for (pos, data) in world.chunks(&dim, RegionKind::Terrain)? {
+            data?;
+            assert!(keep.contains(pos));
+            kept.push(pos);
+        }
+        assert!(kept.contains(&center));
+        assert_eq!(kept.len(), report.chunks_kept);
+        assert_eq!(world.region_positions(&dim, RegionKind::Terrain)?, vec![(1, 1)]);
+        assert_eq!(report.regions_removed, 11);
+        // the region that lost chunks holds just the ones kept
+        let mut region = world.open_region(&dim, RegionKind::Terrain, 1, 1)?;
+        let mut sectors = 0;
+        for &pos in kept.iter() {
+            let (_, data) = region.read_chunk_raw(pos)?.unwrap();
+            sectors += (data.len() as u64 + 5 + 4095) / 4096;
+        }
+        let trimmed = fs::metadata(&region_path)?.len();
+        assert!(trimmed < size);
+        assert_eq!(trimmed, (2 + sectors) * 4096);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn incremental_backup() -> io::Result<()> {
+        let path = copy_test_world("incremental_backup")?;
+        let delta = std::env::temp_dir().join("mc-types-test-incremental_backup-delta");
+        if delta.exists() {
+            fs::remove_dir_all(&delta)?;
+        }
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let dim = Dimension::Overworld;
+        let region = world.open_region_mapped(&dim, RegionKind::Terrain, 1, 1)?;
+        let pos = region.chunk_positions()[0];
+        assert!(region.chunk_timestamp(pos).unwrap() > 0);
+        drop(region);
+        assert_eq!(export_changed_chunks(&world, now_timestamp(), &delta)?, 0);
+        assert!(!delta.exists());
+        // only the chunk written afterwards makes it into the delta
+        let data = world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.unwrap();
+        let mut changed = data.clone();
+        changed.root_tag_mut().as_compound_mut().unwrap()
+            .insert("Changed".to_string(), nbt::Tag::Byte(1));
+        let since = now_timestamp() - 1;
+        world.write_chunk_nbt(&dim, RegionKind::Terrain, pos, &changed)?;
+        let timestamp = world.open_region(&dim, RegionKind::Terrain, 1, 1)?.chunk_timestamp(pos);
+        assert!(timestamp.unwrap() > since);
+        assert_eq!(export_changed_chunks(&world, since, &delta)?, 1);
+        // the cutoff is inclusive, for chunks saved in the second an export starts
+        let same_second = std::env::temp_dir().join("mc-types-test-incremental_backup-same-second");
+        assert_eq!(export_changed_chunks(&world, timestamp.unwrap(), &same_second)?, 1);
+        fs::remove_dir_all(same_second)?;
+        let delta_region = Region::open(delta.join("region").join("r.1.1.mca"))?;
+        assert_eq!(delta_region.chunk_positions(), vec![pos]);
+        assert_eq!(delta_region.chunk_timestamp(pos), timestamp);
+        // bring the change back after reverting it
+        world.write_chunk_nbt(&dim, RegionKind::Terrain, pos, &data)?;
+        assert_eq!(restore_changed_chunks(&mut world, &delta)?, 1);
+        assert_eq!(world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?, Some(changed.clone()));
+        assert_eq!(world.open_region(&dim, RegionKind::Terrain, 1, 1)?.chunk_timestamp(pos), timestamp);
+        // dimensions with only poi or entities in the delta are restored too
+        let custom = Dimension::from_id("mypack:sky").unwrap();
+        let poi_folder = delta.join(custom.folder()).join("poi");
+        fs::create_dir_all(&poi_folder)?;
+        fs::copy(delta.join("region").join("r.1.1.mca"), poi_folder.join("r.1.1.mca"))?;
+        assert_eq!(restore_changed_chunks(&mut world, &delta)?, 2);
+        assert_eq!(world.read_chunk_nbt(&custom, RegionKind::Poi, pos)?, Some(changed));
+        drop(world);
+        fs::remove_dir_all(delta)?;
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn player_data() -> io::Result<()> {
+        let path = copy_test_world("player_data")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let uuid = Uuid::parse("f3afa935-6d27-3248-8aab-796f9daf1797").unwrap();
+        assert_eq!(uuid.to_string(), "f3afa935-6d27-3248-8aab-796f9daf1797");
+        assert_eq!(Uuid::from_most_least(-887304554639052216, -8454530355164145769), uuid);
+        assert_eq!(world.player_uuids()?, vec![uuid]);
+        let original = read_gzip_nbt(world.player_data_path(uuid))?;
+        let mut player = world.read_player_data(uuid)?.unwrap();
+        assert_eq!(player.to_nbt()?, original); // nothing is lost
+        assert_eq!(player.dimension, Dimension::Overworld);
+        assert_eq!(player.game_type, GameType::Creative);
+        assert_eq!(player.health, 20.0);
+        assert_eq!(player.spawn.as_ref().map(|s| (s.pos.to_xyz(), s.dimension.clone())), Some(((19, 20, -11), Dimension::Overworld)));
+        assert_eq!(player.inventory.len(), 15);
+        assert_eq!(player.inventory[2].id, "minecraft:piston");
+        assert!(player.ender_items.is_empty());
+        // take the pickaxe away and give some speed
+        player.inventory.retain(|item| item.id != "minecraft:diamond_pickaxe");
+        player.active_effects.push(StatusEffect::new("minecraft:speed", 1, 600));
+        player.dimension = Dimension::Nether;
+        world.write_player_data(uuid, &player)?;
+        assert_eq!(world.read_player_data(uuid)?, Some(player));
+        assert_eq!(read_gzip_nbt(path.join("playerdata/f3afa935-6d27-3248-8aab-796f9daf1797.dat_old"))?, original);
+        assert!(world.read_player_data(Uuid::from_u128(1))?.is_none());
+        // effects are saved by id since 1.20.2
+        let mut player = world.read_player_data(uuid)?.unwrap();
+        player.data_version = Some(3955);
+        let data = player.to_nbt()?;
+        let effects = data.root_tag().as_compound().unwrap()["active_effects"].as_list().unwrap();
+        assert_eq!(effects[0].as_compound().unwrap()["id"], Tag::String("minecraft:speed".to_string()));
+        player.data_version = None;
+        player.active_effects.push(StatusEffect::new("mypack:frenzy", 0, 20));
+        assert_eq!(player.to_nbt().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn player_data_1_21() -> io::Result<()> {
+        let item = |id: &str, count: i32, slot: i8| {
+            let mut item = Compound::new();
+            item.insert("id".to_string(), Tag::String(id.to_string()));
+            item.insert("count".to_string(), Tag::Int(count));
+            item.insert("Slot".to_string(), Tag::Byte(slot));
+            item
+        };
+        let mut pickaxe = item("minecraft:diamond_pickaxe", 1, 0);
+        let mut components = Compound::new();
+        components.insert("minecraft:damage".to_string(), Tag::Int(12));
+        pickaxe.insert("components".to_string(), Tag::Compound(components));
+        let mut effect = Compound::new();
+        effect.insert("id".to_string(), Tag::String("minecraft:speed".to_string()));
+        effect.insert("amplifier".to_string(), Tag::Byte(0));
+        effect.insert("duration".to_string(), Tag::Int(600));
+        effect.insert("ambient".to_string(), Tag::Byte(0));
+        effect.insert("show_particles".to_string(), Tag::Byte(1));
+        let mut root = Compound::new();
+        root.insert("DataVersion".to_string(), Tag::Int(3955));
+        root.insert("Pos".to_string(), Tag::List(vec![Tag::Double(0.5), Tag::Double(70.0), Tag::Double(-3.5)]));
+        root.insert("Rotation".to_string(), Tag::List(vec![Tag::Float(90.0), Tag::Float(0.0)]));
+        root.insert("Dimension".to_string(), Tag::String("minecraft:the_nether".to_string()));
+        root.insert("Health".to_string(), Tag::Float(20.0));
+        root.insert("foodLevel".to_string(), Tag::Int(20));
+        root.insert("foodSaturationLevel".to_string(), Tag::Float(5.0));
+        root.insert("XpLevel".to_string(), Tag::Int(3));
+        root.insert("XpP".to_string(), Tag::Float(0.25));
+        root.insert("XpTotal".to_string(), Tag::Int(30));
+        root.insert("playerGameType".to_string(), Tag::Int(0));
+        root.insert("Inventory".to_string(), Tag::List(vec![
+            Tag::Compound(pickaxe), Tag::Compound(item("minecraft:cobblestone", 64, 1))]));
+        root.insert("EnderItems".to_string(), Tag::List(Vec::new()));
+        root.insert("active_effects".to_string(), Tag::List(vec![Tag::Compound(effect)]));
+        // a respawn anchor
+        root.insert("SpawnX".to_string(), Tag::Int(12));
+        root.insert("SpawnY".to_string(), Tag::Int(40));
+        root.insert("SpawnZ".to_string(), Tag::Int(-7));
+        root.insert("SpawnForced".to_string(), Tag::Byte(0));
+        root.insert("SpawnDimension".to_string(), Tag::String("minecraft:the_nether".to_string()));
+        root.insert("SpawnAngle".to_string(), Tag::Float(45.0));
+        let original = nbt::Data::new("", Tag::Compound(root));
+        let mut player = PlayerData::from_nbt(original.clone())?;
+        assert_eq!(player.to_nbt()?, original);
+        assert_eq!(player.dimension, Dimension::Nether);
+        assert_eq!((player.inventory[1].id.as_str(), player.inventory[1].count), ("minecraft:cobblestone", 64));
+        let components = player.inventory[0].components.as_ref().unwrap();
+        assert_eq!(components["minecraft:damage"], Tag::Int(12));
+        assert_eq!(player.active_effects[0].id, "minecraft:speed");
+        assert_eq!(player.active_effects[0].duration, 600);
+        let mut spawn = RespawnPoint::new(crate::pos::BlockPos::from_xyz(12, 40, -7), Dimension::Nether);
+        spawn.angle = 45.0;
+        assert_eq!(player.spawn, Some(spawn.clone()));
+        // in a `respawn` compound since 1.21.5
+        player.data_version = Some(4325);
+        spawn.forced = true;
+        player.spawn = Some(spawn.clone());
+        let data = player.to_nbt()?;
+        let root = data.root_tag().as_compound().unwrap();
+        assert!(!root.contains_key("SpawnX") && !root.contains_key("SpawnForced"));
+        let respawn = root["respawn"].as_compound().unwrap();
+        assert_eq!(respawn["pos"], Tag::IntArray(vec![12, 40, -7]));
+        assert_eq!(respawn["dimension"], Tag::String("minecraft:the_nether".to_string()));
+        assert_eq!(respawn["forced"], Tag::Byte(1));
+        assert_eq!(PlayerData::from_nbt(data)?.spawn, Some(spawn));
+        // and nothing at all without a spawn
+        for &data_version in [1519, 3955, 4325].iter() {
+            player.data_version = Some(data_version);
+            player.spawn = None;
+            let data = player.to_nbt()?;
+            let root = data.root_tag().as_compound().unwrap();
+            assert!(root.keys().all(|key| !key.starts_with("Spawn") && key != "respawn"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn stats_and_advancements() -> io::Result<()> {
+        let path = copy_test_world("stats_and_advancements")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let uuid = Uuid::parse("f3afa935-6d27-3248-8aab-796f9daf1797").unwrap();
+        let mut stats = world.read_player_stats(uuid)?.unwrap();
+        assert_eq!(stats.data_version, Some(1519));
+        assert_eq!(stats.get("minecraft:used", "minecraft:sea_pickle"), 153);
+        assert_eq!(stats.get("minecraft:mined", "minecraft:bedrock"), 0);
+        let original = fs::read_to_string(world.player_stats_path(uuid))?;
+        assert_eq!(PlayerStats::from_json(&stats.to_json())?, PlayerStats::from_json(&original)?);
+        // a second player who jumped more
+        let other = Uuid::from_u128(7);
+        let mut other_stats = PlayerStats::default();
+        other_stats.set("minecraft:custom", "minecraft:jump", 1000);
+        world.write_player_stats(other, &other_stats)?;
+        let all = world.read_all_player_stats()?;
+        assert_eq!(all.len(), 2);
+        assert_eq!(stats_leaderboard(&all, "minecraft:custom", "minecraft:jump"), vec![(other, 1000), (uuid, 115)]);
+        assert_eq!(stats_total(&all).get("minecraft:custom", "minecraft:jump"), 1115);
+        stats.set("minecraft:custom", "minecraft:jump", 0);
+        world.write_player_stats(uuid, &stats)?;
+        assert_eq!(world.read_player_stats(uuid)?, Some(stats));
+        // replaced like level.dat, the previous file kept aside
+        let mut old = world.player_stats_path(uuid).into_os_string();
+        old.push("_old");
+        assert_eq!(fs::read_to_string(old)?, original);
+        assert_eq!(world.read_all_player_stats()?.len(), 2);
+
+        let mut advancements = world.read_player_advancements(uuid)?.unwrap();
+        let bed = &advancements.advancements["minecraft:adventure/sleep_in_bed"];
+        assert!(bed.done);
+        assert_eq!(bed.criteria["slept_in_bed"], "2018-08-15 15:22:40 +0800");
+        let done = advancements.done_count(true);
+        assert!(advancements.done_count(false) < done);
+        advancements.advancements.insert("minecraft:story/root".to_string(), AdvancementProgress::default());
+        world.write_player_advancements(uuid, &advancements)?;
+        assert_eq!(world.read_player_advancements(uuid)?, Some(advancements));
+        let all = world.read_all_player_advancements()?;
+        assert_eq!(advancements_leaderboard(&all, true), vec![(uuid, done)]);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn saved_data() -> io::Result<()> {
+        let path = copy_test_world("saved_data")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let dim = Dimension::Overworld;
+        assert_eq!(world.saved_data_names(&dim)?, vec!["villages", "villages_end", "villages_nether"]);
+        let villages = world.read_saved_data::<Villages>(&dim, "villages")?.unwrap();
+        assert_eq!(villages.data_version, Some(1519));
+        assert_eq!(villages.data.tick, 60300);
+        assert!(villages.data.villages.is_empty());
+        let original = read_gzip_nbt(world.saved_data_path(&dim, "villages"))?;
+        assert_eq!(villages.to_nbt(), original);
+        let untyped = world.read_saved_data::<Compound>(&dim, "villages_end")?.unwrap();
+        assert_eq!(untyped.data.get("Tick"), Some(&Tag::Int(60300)));
+        assert!(world.read_saved_data::<Scoreboard>(&dim, "scoreboard")?.is_none());
+
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.scores.push(Score::new("agent", "deaths", 3));
+        scoreboard.display_slots.insert("slot_1".to_string(), "deaths".to_string());
+        let scoreboard = SavedData::new(Some(1519), scoreboard);
+        world.write_saved_data(&dim, "scoreboard", &scoreboard)?;
+        let read = world.read_saved_data::<Scoreboard>(&dim, "scoreboard")?.unwrap();
+        assert_eq!(read.data.score("agent", "deaths"), Some(3));
+        assert_eq!(read, scoreboard);
+        // text components are NBT since 1.21.5, and teams may have no display name
+        let mut text = Compound::new();
+        text.insert("text".to_string(), Tag::String("Deaths".to_string()));
+        let mut objective = Compound::new();
+        objective.insert("Name".to_string(), Tag::String("deaths".to_string()));
+        objective.insert("CriteriaName".to_string(), Tag::String("deathCount".to_string()));
+        objective.insert("DisplayName".to_string(), Tag::Compound(text.clone()));
+        objective.insert("RenderType".to_string(), Tag::String("integer".to_string()));
+        let mut team = Compound::new();
+        team.insert("Name".to_string(), Tag::String("red".to_string()));
+        team.insert("Players".to_string(), Tag::List(vec![Tag::String("agent".to_string())]));
+        let mut data = scoreboard.to_nbt();
+        let root = data.root_tag_mut().as_compound_mut().unwrap();
+        root.insert("DataVersion".to_string(), Tag::Int(4325));
+        let map = root.get_mut("data").and_then(Tag::as_compound_mut).unwrap();
+        map.insert("Objectives".to_string(), Tag::List(vec![Tag::Compound(objective)]));
+        map.insert("Teams".to_string(), Tag::List(vec![Tag::Compound(team)]));
+        let current = SavedData::<Scoreboard>::from_nbt(data.clone())?;
+        assert_eq!(current.data.objectives[0].display_name, Tag::Compound(text));
+        assert_eq!(current.data.teams[0].display_name, None);
+        assert_eq!(current.to_nbt(), data);
+        world.write_saved_data(&dim, "villages", &villages)?;
+        assert_eq!(read_gzip_nbt(path.join("data/villages.dat_old"))?, original);
+        assert!(!path.join("data/villages.dat_new").exists());
+        assert_eq!(world.saved_data_names(&dim)?.len(), 4);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn entity_and_poi_chunks() -> io::Result<()> {
+        let path = copy_test_world("entity_and_poi_chunks")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let dim = Dimension::Overworld;
+        let pos = chunk::Pos::from_xz(-3, 40);
+        assert!(world.read_entity_chunk(&dim, pos)?.is_none());
+        let mut entities = EntityChunk::new(pos, 2730);
+        let mut pig = entity::Entity::new("minecraft:pig", Uuid::from_u128(7), [-40.5, 70.0, 645.5]);
+        pig.data_mut().insert("Saddle".to_string(), nbt::Tag::Byte(1));
+        entities.entities.push(pig);
+        world.write_entity_chunk(&dim, &entities)?;
+        assert!(path.join("entities/r.-1.1.mca").exists());
+        let read = world.read_entity_chunk(&dim, pos)?.unwrap();
+        assert_eq!(read.entities[0].data()["Saddle"], nbt::Tag::Byte(1));
+        assert_eq!(read, entities);
+        let mut poi = PoiChunk::new(2730);
+        let home = PoiRecord::new(crate::pos::BlockPos::from_xyz(-40, 70, 645), "minecraft:home", 1);
+        poi.sections.insert(4, PoiSection::new(true, vec![home]));
+        world.write_poi_chunk(&dim, pos, &poi)?;
+        assert_eq!(world.read_poi_chunk(&dim, pos)?, Some(poi.clone()));
+        // keys unknown to sections and records are kept
+        let mut data = poi.to_nbt();
+        let root = data.root_tag_mut().as_compound_mut().unwrap();
+        let section = root.get_mut("Sections").and_then(nbt::Tag::as_compound_mut).unwrap()
+            .get_mut("4").and_then(nbt::Tag::as_compound_mut).unwrap();
+        section.insert("Future".to_string(), nbt::Tag::Int(1));
+        section.get_mut("Records").and_then(nbt::Tag::as_list_mut).unwrap()[0].as_compound_mut().unwrap()
+            .insert("claimed_by".to_string(), nbt::Tag::String("villager".to_string()));
+        world.write_chunk_nbt(&dim, RegionKind::Poi, pos, &data)?;
+        let read = world.read_poi_chunk(&dim, pos)?.unwrap();
+        assert_ne!(read, poi);
+        assert_eq!(read.to_nbt(), data);
+        // terrain is not touched
+        assert!(world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.is_none());
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    // a 128 high legacy chunk of stone below y = 64, with red wool at the chunk's (1, 64, 2)
+    fn legacy_chunk_nbt(pos: chunk::Pos) -> nbt::Data {
+        use crate::nbt::{Compound, Tag};
+        let mut blocks = vec![0i8; 16 * 128 * 16];
+        let mut data = vec![0i8; 16 * 128 * 16 / 2];
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..64 {
+                    blocks[y + z * 128 + x * 128 * 16] = 1;
+                }
+            }
+        }
+        let index = 64 + 2 * 128 + 128 * 16;
+        blocks[index] = 35;
+        data[index >> 1] = 14; // even indexes take the low nibble
+        let (x, z) = pos.to_xz();
+        let mut level = Compound::new();
+        level.insert("xPos".to_string(), Tag::Int(x));
+        level.insert("zPos".to_string(), Tag::Int(z));
+        level.insert("Blocks".to_string(), Tag::ByteArray(blocks));
+        level.insert("Data".to_string(), Tag::ByteArray(data));
+        level.insert("SkyLight".to_string(), Tag::ByteArray(vec![-1; 16 * 128 * 16 / 2]));
+        level.insert("BlockLight".to_string(), Tag::ByteArray(vec![0; 16 * 128 * 16 / 2]));
+        level.insert("HeightMap".to_string(), Tag::ByteArray(vec![64; 256]));
+        level.insert("Entities".to_string(), Tag::List(vec![]));
+        level.insert("TileEntities".to_string(), Tag::List(vec![]));
+        level.insert("LastUpdate".to_string(), Tag::Long(1000));
+        level.insert("TerrainPopulated".to_string(), Tag::Byte(1));
+        let mut root = Compound::new();
+        root.insert("Level".to_string(), Tag::Compound(level));
+        nbt::Data::new("", Tag::Compound(root))
+    }
+
+    #[test]
+    fn legacy_worlds() -> io::Result<()> {
+        use crate::block::{Cursor, Pos, ReadExact as _};
+        use crate::chunk::ReadExact as _;
+        let path = std::env::temp_dir().join("mc-types-test-legacy_worlds");
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        let pos = chunk::Pos::from_xz(-1, 40);
+        fs::create_dir_all(path.join("mcr/region"))?;
+        Region::open_writable(path.join("mcr/region/r.-1.1.mcr"))?.write_chunk_nbt(pos, &legacy_chunk_nbt(pos))?;
+        fs::create_dir_all(path.join("alpha/1r/14"))?;
+        write_gzip_nbt(path.join("alpha/1r/14/c.-1.14.dat"), &legacy_chunk_nbt(pos))?;
+        let mcr = McRegionWorld::new(path.join("mcr"));
+        let alpha = AlphaWorld::new(path.join("alpha"));
+        assert_eq!(mcr.region_positions()?, vec![(-1, 1)]);
+        assert_eq!(alpha.chunk_positions()?, vec![pos]);
+        assert_eq!(alpha.chunk_path(pos), path.join("alpha/1r/14/c.-1.14.dat"));
+        assert!(mcr.contains_chunk_exact(pos)? && alpha.contains_chunk_exact(pos)?);
+        assert!(!mcr.contains_chunk_exact(chunk::Pos::from_xz(0, 0))?);
+        let mut buf = chunk::Chunk::new();
+        mcr.read_chunk_exact(pos, &mut buf)?;
+        let mut alpha_buf = chunk::Chunk::new();
+        alpha.read_chunk_exact(pos, &mut alpha_buf)?;
+        assert!(buf == alpha_buf);
+        let mut cur = Cursor::new(mcr);
+        assert_eq!(cur.get_block_id(Pos::from_xyz(-15, 64, 642))?, "minecraft:red_wool");
+        assert_eq!(cur.get_block_id(Pos::from_xyz(-15, 63, 642))?, "minecraft:stone");
+        assert_eq!(cur.get_block_id(Pos::from_xyz(-15, 65, 642))?, "minecraft:air");
+        let mcr = cur.into_inner();
+        assert!(!mcr.contain_block_exact(Pos::from_xyz(-15, 128, 642))?);
+        assert!(mcr.read_block_exact(Pos::from_xyz(-15, 128, 642)).is_err());
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn convert_mcregion() -> io::Result<()> {
+        use crate::block::{IdSystem as _, ReadExact as _};
+        use crate::nbt::Tag;
+        let path = std::env::temp_dir().join("mc-types-test-convert_mcregion");
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        let pos = chunk::Pos::from_xz(-1, 40);
+        fs::create_dir_all(path.join("region"))?;
+        fs::create_dir_all(path.join("DIM-1/region"))?;
+        Region::open_writable(path.join("region/r.-1.1.mcr"))?.write_chunk_nbt(pos, &legacy_chunk_nbt(pos))?;
+        Region::open_writable(path.join("DIM-1/region/r.0.0.mcr"))?.write_chunk_nbt(chunk::Pos::from_xz(0, 0), &legacy_chunk_nbt(chunk::Pos::from_xz(0, 0)))?;
+        fs::copy("./test_worlds/water_only/level.dat", path.join("level.dat"))?;
+        let legacy = McRegionWorld::new(&path);
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        // every red wool becomes stone
+        let count = convert_mcregion_world_with(&legacy, &mut world, &|id, data| match (id, data) {
+            (35, 14) => (1, 0),
+            _ => (id, data)
+        })?;
+        assert_eq!(count, 2);
+        assert!(path.join("region/r.-1.1.mcr").exists());
+        let data = world.read_chunk_nbt(&Dimension::Overworld, RegionKind::Terrain, pos)?.unwrap();
+        let root = data.root_tag().as_compound().unwrap();
+        assert_eq!(root["DataVersion"], Tag::Int(1519));
+        let level = root["Level"].as_compound().unwrap();
+        let sections = level["Sections"].as_list().unwrap();
+        assert_eq!(sections.len(), 5); // stone up to y = 64, then the former wool
+        let top = sections.iter().map(|s| s.as_compound().unwrap())
+            .find(|s| s["Y"] == Tag::Byte(4)).unwrap();
+        assert!(top.contains_key("Palette") && !top.contains_key("Blocks"));
+        assert_eq!(top["SkyLight"].as_byte_array().unwrap()[0], -1);
+        assert!(!level.contains_key("HeightMap") && level.contains_key("Heightmaps"));
+        // read back by the Anvil reader like any 1.13 world
+        drop(world);
+        let world = McJavaWorld::new(&path);
+        assert_eq!(world.read_level_data()?.data_version, Some(1519));
+        let id_at = |x, y, z| world.read_block_exact(block::Pos::from_xyz(x, y, z))
+            .map(|meta| world.block_ids().block_meta_to_id(meta).to_string());
+        assert_eq!(id_at(-15, 64, 642)?, "minecraft:stone");
+        assert_eq!(id_at(-14, 64, 642)?, "minecraft:air");
+        assert_eq!(id_at(-3, 10, 655)?, "minecraft:stone");
+        let heightmaps = world.read_heightmaps(&Dimension::Overworld, pos)?.unwrap();
+        assert!(heightmaps.iter().all(|(_, heightmap)| heightmap.get(1, 2) == 65 && heightmap.get(2, 2) == 64));
+        let mut nether = chunk::Chunk::new();
+        world.read_chunk_in(&Dimension::Nether, chunk::Pos::from_xz(0, 0), &mut nether)?;
+        assert_eq!(world.block_ids().block_meta_to_id(nether.get_block_meta(1, 64, 2)).to_string(), "minecraft:stone");
+        let level = read_gzip_nbt(path.join("level.dat"))?;
+        let version = &level.root_tag().as_compound().unwrap()["Data"].as_compound().unwrap()["version"];
+        assert_eq!(version, &Tag::Int(19133));
+        fs::remove_dir_all(path)
+    }
+
+    // converted chunks keep every block, named by `LegacyIdSystem`, and their light
+    #[test]
+    fn convert_legacy_round_trip() -> io::Result<()> {
+        use crate::block::IdSystem as _;
+        let pos = chunk::Pos::from_xz(-1, 40);
+        let legacy = legacy_chunk_nbt(pos);
+        let data = convert_legacy_chunk(&legacy, &|id, data| (id, data))?;
+        let legacy = legacy.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap();
+        let root = data.root_tag().as_compound().unwrap();
+        let level = root["Level"].as_compound().unwrap();
+        assert_eq!((level["xPos"].as_int(), level["zPos"].as_int()), (Some(-1), Some(40)));
+        assert_eq!(level["LastUpdate"], legacy["LastUpdate"]);
+        let ids = block::SharedHashSystem::new();
+        let mut chunk = chunk::Chunk::new();
+        anvil::anvil_to_chunk(&data, &ids, &biome::SharedHashSystem::new(), HeightRange::new(0, 256).unwrap(), &mut chunk)?;
+        let sections: HashMap<_, _> = level["Sections"].as_list().unwrap().iter()
+            .map(|s| s.as_compound().unwrap())
+            .map(|s| (s["Y"].as_byte().unwrap() as usize, s))
+            .collect();
+        let nibble = |array: &[i8], index: usize| (array[index >> 1] as u8 >> ((index & 1) * 4)) & 0xF;
+        let legacy_ids = block::LegacyIdSystem::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..128 {
+                    let from = y + z * 128 + x * 128 * 16;
+                    let meta = block::LegacyIdSystem::meta_from_legacy(legacy["Blocks"].as_byte_array().unwrap()[from] as u8,
+                        nibble(legacy["Data"].as_byte_array().unwrap(), from));
+                    let id = ids.block_meta_to_id(chunk.get_block_meta(x, y as i32, z)).to_string();
+                    assert_eq!(id, legacy_ids.block_meta_to_id(meta).to_string());
+                    if let Some(section) = sections.get(&(y >> 4)) {
+                        let to = ((y & 15) << 8) | (z << 4) | x;
+                        let sky = nibble(section["SkyLight"].as_byte_array().unwrap(), to);
+                        assert_eq!(sky, nibble(legacy["SkyLight"].as_byte_array().unwrap(), from));
+                    }
+                }
+            }
+        }
+        // blocks no name is known for are refused
+        let unknown = convert_legacy_chunk(&legacy_chunk_nbt(pos), &|id, data| if id == 35 { (200, data) } else { (id, data) });
+        assert_eq!(unknown.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        Ok(())
+    }
+
+    #[test]
+    fn read_anvil_blocks() -> io::Result<()> {
+        let world = McJavaWorld::new("./test_worlds/water_only");
+        let mut buf = chunk::Chunk::new();
+        chunk::ReadExact::read_chunk_exact(&world, chunk::Pos::from_xz(32, 32), &mut buf)?;
+        assert!(buf.section(3).is_some() && buf.section(4).is_none());
+        let mut cur = block::Cursor::new(world);
+        assert_eq!(cur.get_block_id(block::Pos::from_xyz(512, 0, 512))?, "minecraft:bedrock");
+        assert_eq!(cur.get_block_id(block::Pos::from_xyz(519, 3, 520))?, "minecraft:stone");
+        assert_eq!(cur.get_block_id(block::Pos::from_xyz(527, 55, 527))?, "minecraft:sandstone");
+        assert_eq!(cur.get_block_id(block::Pos::from_xyz(512, 56, 512))?, "minecraft:air");
+        assert_eq!(cur.get_block_id(block::Pos::from_xyz(520, 200, 515))?, "minecraft:air");
+        assert_eq!(cur.get_block_meta(block::Pos::from_xyz(0, 0, 0)).unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert!(cur.get_block_meta(block::Pos::from_xyz(512, 256, 512)).is_err());
+        let world = cur.into_inner();
+        assert!(chunk::ReadExact::contains_chunk_exact(&world, chunk::Pos::from_xz(32, 32))?);
+        assert!(!chunk::ReadExact::contains_chunk_exact(&world, chunk::Pos::from_xz(0, 0))?);
+        // properties are part of the id
+        let mut state = Compound::new();
+        state.insert("Name".to_string(), Tag::String("minecraft:oak_log".to_string()));
+        let mut properties = Compound::new();
+        properties.insert("axis".to_string(), Tag::String("y".to_string()));
+        properties.insert("waterlogged".to_string(), Tag::String("false".to_string()));
+        state.insert("Properties".to_string(), Tag::Compound(properties));
+        assert_eq!(anvil::block_state_to_id(&state)?, "minecraft:oak_log[axis=y,waterlogged=false]");
+        Ok(())
+    }
+
+    #[test]
+    fn write_anvil_blocks() -> io::Result<()> {
+        use crate::chunk::{ReadExact as _, WriteExact as _};
+        let path = copy_test_world("write_anvil_blocks")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let pos = chunk::Pos::from_xz(32, 32);
+        let mut chunk = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut chunk)?;
+        let log = world.block_ids().register_block(block::Id::new("minecraft:oak_log[axis=x]"));
+        chunk.set_block_meta(3, 60, 4, log);
+        chunk.set_block_meta(1, 0, 1, block::Meta::new(0));
+        for x in 0..16 {
+            for z in 0..16 {
+                chunk.set_block_meta(x, 55, z, block::Meta::new(0));
+            }
+        }
+        world.write_chunk_exact(pos, &chunk)?;
+        let mut read = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut read)?;
+        for y in 0..256 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    assert_eq!(read.get_block_meta(x, y, z), chunk.get_block_meta(x, y, z));
+                }
+            }
+        }
+        // other data is left untouched
+        let data = world.read_chunk_nbt(&Dimension::Overworld, RegionKind::Terrain, pos)?.unwrap();
+        let level = data.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap();
+        assert!(level.contains_key("TileTicks") && level.contains_key("Biomes"));
+        let sections = level["Sections"].as_list().unwrap();
+        let section = sections[3].as_compound().unwrap();
+        assert_eq!(section["Y"], Tag::Byte(3));
+        assert!(section.contains_key("SkyLight"));
+        let palette = section["Palette"].as_list().unwrap();
+        assert_eq!(palette.len(), 3);
+        let state = palette[2].as_compound().unwrap();
+        assert_eq!(state["Properties"].as_compound().unwrap()["axis"], Tag::String("x".to_string()));
+
+        let mut cur = block::Cursor::new(world);
+        let stone = block::Pos::from_xyz(520, 100, 519);
+        cur.set_block_id(stone, block::Id::new("minecraft:stone"))?;
+        assert_eq!(cur.get_block_id(stone)?, "minecraft:stone");
+        assert!(cur.set_block_id(block::Pos::from_xyz(0, 0, 0), block::Id::new("minecraft:stone")).is_err());
+        let world = cur.into_inner();
+
+        // fresh chunks in the 1.18 layout, every section holding biomes
+        let data = anvil::chunk_to_anvil(&chunk, pos, world.block_ids(), world.biome_ids(), None, 2860)?;
+        let root = data.root_tag().as_compound().unwrap();
+        assert!(!root.contains_key("Level"));
+        assert_eq!(root["sections"].as_list().unwrap().len(), 16);
+        let mut read = chunk::Chunk::new();
+        anvil::anvil_to_chunk(&data, world.block_ids(), world.biome_ids(), HeightRange::new(-64, 384).unwrap(), &mut read)?;
+        assert_eq!(read.get_block_meta(3, 60, 4), log);
+        assert_eq!(read.get_block_meta(15, 54, 15), chunk.get_block_meta(15, 54, 15));
+        assert_eq!(anvil::id_to_block_state("minecraft:oak_log[axis=x]")?, state.clone());
+        assert!(anvil::id_to_block_state("minecraft:oak_log[axis]").is_err());
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn heightmaps() -> io::Result<()> {
+        use crate::chunk::{Heightmap, HeightmapKind, ReadExact as _, WriteExact as _};
+        let path = copy_test_world("heightmaps")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let pos = chunk::Pos::from_xz(32, 32);
+        let mut chunk = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut chunk)?;
+        // the game's heightmaps match ours, sandstone topping at y 55
+        let stored = world.read_heightmaps(&Dimension::Overworld, pos)?.unwrap();
+        assert_eq!(stored.len(), 5);
+        for (kind, heightmap) in stored.iter() {
+            assert_eq!(heightmap.get(7, 9), 56);
+            assert_eq!(&Heightmap::compute(&chunk, *kind, world.block_ids()), heightmap);
+        }
+        let ids = world.block_ids();
+        chunk.set_block_meta(3, 60, 4, ids.register_block(block::Id::new("minecraft:oak_log[axis=y]")));
+        chunk.set_block_meta(5, 70, 5, ids.register_block(block::Id::new("minecraft:water[level=0]")));
+        chunk.set_block_meta(6, 80, 6, ids.register_block(block::Id::new("minecraft:oak_leaves[distance=1,persistent=true]")));
+        chunk.set_block_meta(7, 90, 7, ids.register_block(block::Id::new("minecraft:poppy")));
+        for y in 0..56 {
+            chunk.set_block_meta(8, y, 8, block::Meta::new(0));
+        }
+        // columns left alone keep what the game saved, even where we would differ
+        let mut data = world.read_chunk_nbt(&Dimension::Overworld, RegionKind::Terrain, pos)?.unwrap();
+        let level = data.root_tag_mut().as_compound_mut().unwrap().get_mut("Level").and_then(Tag::as_compound_mut).unwrap();
+        let saved = level.get_mut("Heightmaps").and_then(Tag::as_compound_mut).unwrap();
+        let mut surface = Heightmap::from_packed(saved["WORLD_SURFACE"].as_long_array().unwrap(), 0, 256, 1519)?;
+        surface.set(0, 15, 100);
+        surface.set(3, 4, 100);
+        saved.insert("WORLD_SURFACE".to_string(), Tag::LongArray(surface.to_packed(0, 256, 1519)));
+        world.write_chunk_nbt(&Dimension::Overworld, RegionKind::Terrain, pos, &data)?;
+        world.write_chunk_exact(pos, &chunk)?;
+        let stored = world.read_heightmaps(&Dimension::Overworld, pos)?.unwrap();
+        let get = |kind| stored.iter().find(|(k, _)| *k == kind).map(|(_, h)| h).unwrap();
+        let columns = [(3, 4), (5, 5), (6, 6), (7, 7), (8, 8), (9, 9)];
+        let expected = [
+            (HeightmapKind::WorldSurface, [61, 71, 81, 91, 0, 56]),
+            (HeightmapKind::OceanFloor, [61, 56, 81, 56, 0, 56]),
+            (HeightmapKind::MotionBlocking, [61, 71, 81, 56, 0, 56]),
+            (HeightmapKind::MotionBlockingNoLeaves, [61, 71, 56, 56, 0, 56]),
+        ];
+        for (kind, heights) in expected.iter() {
+            for (&(x, z), &y) in columns.iter().zip(heights.iter()) {
+                assert_eq!(get(*kind).get(x, z), y, "{:?} at {}, {}", kind, x, z);
+            }
+        }
+        assert_eq!(get(HeightmapKind::WorldSurface).get(0, 15), 100);
+        // fresh chunks get the usual kinds, packed for 1.18 heights
+        let data = anvil::chunk_to_anvil(&chunk, pos, world.block_ids(), world.biome_ids(), None, 2860)?;
+        let heightmaps = data.root_tag().as_compound().unwrap()["Heightmaps"].as_compound().unwrap();
+        assert_eq!(heightmaps.len(), 4);
+        assert_eq!(heightmaps["WORLD_SURFACE"].as_long_array().unwrap().len(), 37);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn biomes() -> io::Result<()> {
+        use crate::biome::IdSystem as _;
+        use crate::chunk::ReadExact as _;
+        let path = copy_test_world("biomes")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let desert = world.biome_ids().biome_id_to_meta(block::Id::new("minecraft:desert"));
+        let plains = world.biome_ids().register_biome(block::Id::new("minecraft:plains"));
+        let pos = block::Pos::from_xyz(520, 70, 516);
+        assert_eq!(world.read_biome(pos)?, Some(desert));
+        // columns before 1.15
+        world.write_biome(pos, plains)?;
+        assert_eq!(world.read_biome(block::Pos::from_xyz(520, 0, 516))?, Some(plains));
+        assert_eq!(world.read_biome(block::Pos::from_xyz(521, 70, 516))?, Some(desert));
+        let chunk_pos = chunk::Pos::from_xz(32, 32);
+        let data = world.read_chunk_nbt(&Dimension::Overworld, RegionKind::Terrain, chunk_pos)?.unwrap();
+        let numbers = data.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap()["Biomes"].as_int_array().unwrap();
+        assert_eq!((numbers.len(), numbers[4 * 16 + 8], numbers[0]), (256, 1, 2));
+        // cells over the chunk in 1.15, each taking the column at its corner
+        let mut chunk = chunk::Chunk::new();
+        world.read_chunk_exact(chunk_pos, &mut chunk)?;
+        let data = anvil::chunk_to_anvil(&chunk, chunk_pos, world.block_ids(), world.biome_ids(), None, 2230)?;
+        let numbers = data.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap()["Biomes"].as_int_array().unwrap();
+        assert_eq!(numbers.len(), 1024);
+        let mut read = chunk::Chunk::new();
+        anvil::anvil_to_chunk(&data, world.block_ids(), world.biome_ids(), HeightRange::new(0, 256).unwrap(), &mut read)?;
+        assert_eq!(read.biomes().map(|biomes| biomes.metas().len()), Some(1024));
+        assert_eq!(read.get_biome_meta(11, 255, 7), Some(plains));
+        assert_eq!(read.get_biome_meta(12, 255, 7), Some(desert));
+        // palettes in each section since 1.18
+        let mut tall = chunk::Chunk::with_sections(-4, 24);
+        tall.set_biomes(Some(chunk.biomes().unwrap().to_cells(384)));
+        let grove = world.biome_ids().register_biome(block::Id::new("minecraft:grove"));
+        tall.set_biome_meta(0, -64, 0, grove);
+        let data = anvil::chunk_to_anvil(&tall, chunk_pos, world.block_ids(), world.biome_ids(), None, 2860)?;
+        let sections = data.root_tag().as_compound().unwrap()["sections"].as_list().unwrap();
+        assert_eq!(sections.len(), 24);
+        let biomes = sections[0].as_compound().unwrap()["biomes"].as_compound().unwrap();
+        assert_eq!(biomes["palette"].as_list().unwrap().len(), 3);
+        let mut read = chunk::Chunk::new();
+        anvil::anvil_to_chunk(&data, world.block_ids(), world.biome_ids(), HeightRange::new(-64, 384).unwrap(), &mut read)?;
+        assert_eq!(read.biomes(), tall.biomes());
+        assert_eq!(read.get_biome_meta(0, -61, 3), Some(grove));
+        assert_eq!(read.get_biome_meta(8, 319, 4), Some(plains));
+        // a section without biomes among others with them is not read as ocean
+        let mut partial = data.clone();
+        let root = partial.root_tag_mut().as_compound_mut().unwrap();
+        root.get_mut("sections").unwrap().as_list_mut().unwrap()[5].as_compound_mut().unwrap().remove("biomes");
+        let err = anvil::anvil_to_chunk(&partial, world.block_ids(), world.biome_ids(), HeightRange::new(-64, 384).unwrap(), &mut read);
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn block_entities() -> io::Result<()> {
+        use crate::block_entity::{Banner, BannerPattern, BlockEntity, Container, Sign, Spawner};
+        use crate::chunk::{ReadExact as _, WriteExact as _};
+        let path = copy_test_world("block_entities")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let pos = chunk::Pos::from_xz(32, 32);
+        let mut chunk = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut chunk)?;
+        assert!(chunk.has_block_entities() && chunk.block_entities().is_empty());
+        let chest_pos = block::Pos::from_xyz(520, 56, 516);
+        chunk.set_block_meta(8, 56, 4, world.block_ids().register_block(block::Id::new("minecraft:chest[facing=north,type=single,waterlogged=false]")));
+        let mut chest = BlockEntity::new("minecraft:chest", chest_pos);
+        let mut container = Container::default();
+        let mut item = ItemStack::new("minecraft:diamond", 3);
+        item.slot = Some(13);
+        container.items.push(item);
+        container.apply(&mut chest, 1519);
+        chunk.set_block_entity(chest);
+        // a sign left on sandstone
+        let sign_pos = block::Pos::from_xyz(521, 40, 516);
+        let mut sign = BlockEntity::new("minecraft:sign", sign_pos);
+        let mut text = Sign::default();
+        text.front.lines[0] = Tag::String(r#"{"text":"hello"}"#.to_string());
+        text.apply(&mut sign, 1519);
+        chunk.set_block_entity(sign);
+        assert_eq!(chunk.check_block_entities(world.block_ids()), vec![sign_pos]);
+        world.write_chunk_exact(pos, &chunk)?;
+
+        let read = world.read_block_entity(chest_pos)?.unwrap();
+        assert_eq!(Container::from_block_entity(&read)?, container);
+        assert!(Sign::from_block_entity(&read).is_err());
+        let mut sign = world.read_block_entity(sign_pos)?.unwrap();
+        assert_eq!(sign.data()["Text1"], Tag::String(r#"{"text":"hello"}"#.to_string()));
+        assert_eq!(Sign::from_block_entity(&sign)?, text);
+        // both sides since 1.20
+        text.back.lines[3] = Tag::String(r#"{"text":"bye"}"#.to_string());
+        text.apply(&mut sign, 3465);
+        assert!(!sign.data().contains_key("Text1") && sign.data().contains_key("front_text"));
+        assert_eq!(Sign::from_block_entity(&sign)?, text);
+        world.write_block_entity(sign.clone())?;
+        assert_eq!(Sign::from_block_entity(&world.read_block_entity(sign_pos)?.unwrap())?, text);
+        assert!(world.read_block_entity(block::Pos::from_xyz(520, 57, 516))?.is_none());
+        // text components are NBT since 1.21.5
+        let mut hello = Compound::new();
+        hello.insert("text".to_string(), Tag::String("hello".to_string()));
+        text.front.lines[0] = Tag::Compound(hello.clone());
+        text.apply(&mut sign, 4325);
+        assert_eq!(Sign::from_block_entity(&sign)?, text);
+
+        // banner patterns by code and dye number, by id and dye name since 1.20.5
+        let mut banner = BlockEntity::new("minecraft:banner", chest_pos);
+        let mut layer = Compound::new();
+        layer.insert("pattern".to_string(), Tag::String("minecraft:stripe_bottom".to_string()));
+        layer.insert("color".to_string(), Tag::String("light_blue".to_string()));
+        banner.data_mut().insert("patterns".to_string(), Tag::List(vec![Tag::Compound(layer)]));
+        banner.data_mut().insert("CustomName".to_string(), Tag::Compound(hello.clone()));
+        let mut banners = Banner::from_block_entity(&banner)?;
+        assert_eq!(banners.patterns, vec![BannerPattern::new("minecraft:stripe_bottom", "light_blue")]);
+        assert_eq!(banners.custom_name, Some(Tag::Compound(hello)));
+        let current = banner.clone();
+        banners.apply(&mut banner, 3953)?;
+        assert_eq!(banner, current);
+        banners.apply(&mut banner, 3465)?;
+        assert!(!banner.data().contains_key("patterns"));
+        let layer = banner.data()["Patterns"].as_list().unwrap()[0].as_compound().unwrap();
+        assert_eq!((&layer["Pattern"], &layer["Color"]), (&Tag::String("bs".to_string()), &Tag::Int(3)));
+        assert_eq!(Banner::from_block_entity(&banner)?, banners);
+        banners.patterns.push(BannerPattern::new("minecraft:flow", "red"));
+        assert_eq!(banners.apply(&mut banner, 3465).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let mut spawner = BlockEntity::new("minecraft:mob_spawner", chest_pos);
+        let spawning = Spawner { entity_id: Some("minecraft:zombie".to_string()), ..Spawner::default() };
+        spawning.apply(&mut spawner, 2860);
+        let spawn_data = spawner.data()["SpawnData"].as_compound().unwrap();
+        assert_eq!(spawn_data["entity"].as_compound().unwrap()["id"], Tag::String("minecraft:zombie".to_string()));
+        assert_eq!(Spawner::from_block_entity(&spawner)?, spawning);
+        assert_eq!(spawner.matches_block("minecraft:spawner"), Some(true));
+        assert_eq!(spawner.matches_block("minecraft:chest"), Some(false));
+
+        // block entities must be in the chunk they are written with
+        let mut chunk = chunk::Chunk::new();
+        chunk.set_block_entity(BlockEntity::new("minecraft:chest", block::Pos::from_xyz(0, 60, 0)));
+        assert_eq!(world.write_chunk_exact(pos, &chunk).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn entities() -> io::Result<()> {
+        use crate::entity::{BoundingBox, Entity};
+        use crate::chunk::{ReadExact as _, WriteExact as _};
+        let path = copy_test_world("entities")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let dim = Dimension::Overworld;
+        let pos = chunk::Pos::from_xz(32, 32);
+        let mut chunk = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut chunk)?;
+        assert!(chunk.has_entities() && chunk.entities().is_empty());
+        // a pile of items and a zombie
+        for i in 0..5 {
+            let mut item = Entity::new("minecraft:item", Uuid::from_u128(i + 1), [520.5, 56.0, 516.5 + i as f64]);
+            item.motion = [0.0, -0.04, 0.0];
+            item.data_mut().insert("Age".to_string(), Tag::Short(10));
+            chunk.entities_mut().push(item);
+        }
+        let zombie_uuid = Uuid::parse("f3afa935-6d27-3248-8aab-796f9daf1797").unwrap();
+        let mut zombie = Entity::new("minecraft:zombie", zombie_uuid, [525.0, 56.0, 525.0]);
+        zombie.rotation = [90.0, 0.0];
+        chunk.entities_mut().push(zombie.clone());
+        let pile = BoundingBox::new([520.0, 50.0, 516.0], [521.0, 60.0, 519.0]);
+        assert_eq!(chunk.entities_in(&pile).len(), 3);
+        world.write_chunk_exact(pos, &chunk)?;
+
+        let data = world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.unwrap();
+        let level = data.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap();
+        let saved = level["Entities"].as_list().unwrap()[5].as_compound().unwrap();
+        assert_eq!(saved["UUIDMost"], Tag::Long(zombie_uuid.to_most_least().0));
+        let mut read = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut read)?;
+        assert_eq!(read.entity_by_uuid(zombie_uuid), Some(&zombie));
+        assert_eq!(read.entities(), chunk.entities());
+        let counts = world.count_entities(&dim, None)?;
+        assert_eq!((counts["minecraft:item"], counts["minecraft:zombie"], counts.len()), (5, 1, 2));
+        assert_eq!(world.count_entities(&dim, Some(&pile))?["minecraft:item"], 3);
+        // a box as large as a corrupt entity may give is cut at the world border
+        let huge = BoundingBox::new([-1.0e300, -1.0e300, f64::NEG_INFINITY], [1.0e300, 1.0e300, f64::INFINITY]);
+        assert_eq!(huge.chunk_positions().next(), Some(chunk::Pos::from_xz(-1875000, -1875000)));
+        assert_eq!(world.count_entities(&dim, Some(&huge))?, counts);
+        let removed = world.remove_entities(&dim, Some(&BoundingBox::from_chunks(pos, pos)), |e| e.id == "minecraft:item")?;
+        assert_eq!(removed, 5);
+        assert_eq!(world.read_entities(&dim, pos)?, Some(vec![zombie.clone()]));
+        assert!(world.read_entities(&dim, chunk::Pos::from_xz(0, 0))?.is_none());
+
+        // entity region files since 1.17
+        let pos = chunk::Pos::from_xz(-3, 40);
+        let data = anvil::chunk_to_anvil(&chunk::Chunk::new(), pos, world.block_ids(), world.biome_ids(), None, 2730)?;
+        world.write_chunk_nbt(&dim, RegionKind::Terrain, pos, &data)?;
+        assert_eq!(world.read_entities(&dim, pos)?, Some(Vec::new()));
+        let pig = Entity::new("minecraft:pig", zombie_uuid, [-40.0, 70.0, 645.0]);
+        world.write_entities(&dim, pos, std::slice::from_ref(&pig))?;
+        let entity_chunk = world.read_entity_chunk(&dim, pos)?.unwrap();
+        let saved = entity_chunk.to_nbt();
+        let saved = &saved.root_tag().as_compound().unwrap()["Entities"].as_list().unwrap()[0];
+        assert_eq!(saved.as_compound().unwrap()["UUID"], Tag::IntArray(vec![-206591691, 1831285320, -1968473745, -1649469545]));
+        assert_eq!(entity_chunk.entities, vec![pig.clone()]);
+        let mut read = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut read)?;
+        assert_eq!(read.entities(), &[pig][..]);
+        read.retain_entities(|_| false);
+        world.write_chunk_exact(pos, &read)?;
+        assert!(world.read_entity_chunk(&dim, pos)?.unwrap().entities.is_empty());
+        assert!(world.write_entities(&dim, pos, &[zombie]).is_err());
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn scheduled_ticks() -> io::Result<()> {
+        use crate::chunk::{ReadExact as _, ScheduledTick, TickKind, WriteExact as _};
+        let path = copy_test_world("scheduled_ticks")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let dim = Dimension::Overworld;
+        let pos = chunk::Pos::from_xz(32, 32);
+        let mut chunk = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut chunk)?;
+        assert!(chunk.has_ticks() && chunk.ticks(TickKind::Block).is_empty());
+        let repeater = ScheduledTick {
+            id: "minecraft:repeater".to_string(),
+            pos: block::Pos::from_xyz(520, 56, 516),
+            delay: 2,
+            priority: -1
+        };
+        let water = ScheduledTick {
+            id: "minecraft:water".to_string(),
+            pos: block::Pos::from_xyz(527, 60, 512),
+            delay: 5,
+            priority: 0
+        };
+        chunk.ticks_mut(TickKind::Block).push(repeater.clone());
+        chunk.ticks_mut(TickKind::Fluid).push(water.clone());
+        world.write_chunk_exact(pos, &chunk)?;
+
+        let data = world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.unwrap();
+        let level = data.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap();
+        let saved = level["TileTicks"].as_list().unwrap()[0].as_compound().unwrap();
+        assert_eq!((&saved["i"], &saved["t"], &saved["p"]), (&Tag::String("minecraft:repeater".to_string()), &Tag::Int(2), &Tag::Int(-1)));
+        let mut read = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut read)?;
+        assert_eq!(read.ticks(TickKind::Block), &[repeater.clone()][..]);
+        assert_eq!(read.ticks(TickKind::Fluid), &[water.clone()][..]);
+
+        // renamed and moved to the root when upgrading, with the rest of the saved chunk
+        let data = anvil::chunk_to_anvil(&read, pos, world.block_ids(), world.biome_ids(), None, 2860)?;
+        let root = data.root_tag().as_compound().unwrap();
+        assert!(!root.contains_key("Level") && root["fluid_ticks"].as_list().unwrap().len() == 1);
+        let original = world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.unwrap();
+        let saved = original.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap();
+        let data = anvil::chunk_to_anvil(&read, pos, world.block_ids(), world.biome_ids(), Some(&original), 2860)?;
+        let root = data.root_tag().as_compound().unwrap();
+        assert!(!root.contains_key("Level") && !root.contains_key("TileTicks"));
+        assert_eq!((&root["xPos"], &root["yPos"], &root["InhabitedTime"]), (&saved["xPos"], &Tag::Int(0), &saved["InhabitedTime"]));
+        assert_eq!(root["fluid_ticks"].as_list().unwrap().len(), 1);
+        assert!(root["sections"].as_list().unwrap().iter().all(|s| !s.as_compound().unwrap().contains_key("Palette")));
+        let mut upgraded = chunk::Chunk::new();
+        anvil::anvil_to_chunk(&data, world.block_ids(), world.biome_ids(), HeightRange::new(-64, 384).unwrap(), &mut upgraded)?;
+        assert_eq!(upgraded.ticks(TickKind::Block), &[repeater.clone()][..]);
+        assert_eq!(upgraded.ticks(TickKind::Fluid), &[water.clone()][..]);
+        for &(x, y, z) in [(0, 55, 0), (8, 3, 9), (15, 60, 15)].iter() {
+            assert_eq!(upgraded.get_block_meta(x, y, z), read.get_block_meta(x, y, z));
+        }
+        // and back under `Level`, the ticks the chunk does not hold kept as they were
+        let mut no_ticks = chunk::Chunk::new();
+        no_ticks.set_section(0, read.section(0).cloned());
+        let data = anvil::chunk_to_anvil(&no_ticks, pos, world.block_ids(), world.biome_ids(), Some(&data), 1519)?;
+        let root = data.root_tag().as_compound().unwrap();
+        let level = root["Level"].as_compound().unwrap();
+        assert!(!root.contains_key("sections") && !level.contains_key("yPos") && !level.contains_key("fluid_ticks"));
+        assert_eq!(level["LiquidTicks"].as_list().unwrap().len(), 1);
+        let mut downgraded = chunk::Chunk::new();
+        anvil::anvil_to_chunk(&data, world.block_ids(), world.biome_ids(), HeightRange::new(0, 256).unwrap(), &mut downgraded)?;
+        assert_eq!(downgraded.ticks(TickKind::Block), &[repeater][..]);
+        assert_eq!(downgraded.get_block_meta(0, 3, 0), read.get_block_meta(0, 3, 0));
+
+        read.clear_ticks();
+        world.write_chunk_exact(pos, &read)?;
+        let data = world.read_chunk_nbt(&dim, RegionKind::Terrain, pos)?.unwrap();
+        let level = data.root_tag().as_compound().unwrap()["Level"].as_compound().unwrap();
+        assert!(level["TileTicks"].as_list().unwrap().is_empty() && level["LiquidTicks"].as_list().unwrap().is_empty());
+        // ticks must be in the chunk they are written with
+        read.ticks_mut(TickKind::Block).push(ScheduledTick {
+            id: "minecraft:sand".to_string(),
+            pos: block::Pos::from_xyz(0, 60, 0),
+            delay: 1,
+            priority: 0
+        });
+        assert_eq!(world.write_chunk_exact(pos, &read).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn dimension_view() -> io::Result<()> {
+        use crate::block::ReadExact as _;
+        use crate::chunk::{ReadExact as _, WriteExact as _};
+        let path = copy_test_world("dimension_view")?;
+        let mut world = McJavaWorld::open_for_write(&path, LockPolicy::Refuse)?;
+        let pos = chunk::Pos::from_xz(32, 32);
+        let mut chunk = chunk::Chunk::new();
+        world.read_chunk_exact(pos, &mut chunk)?;
+        // level.dat is kept since opened
+        let mut level = world.read_level_data()?;
+        fs::remove_file(path.join("level.dat"))?;
+        assert_eq!(world.height_range(&Dimension::Overworld)?, HeightRange::new(0, 256).unwrap());
+
+        let stone = block::Pos::from_xyz(520, 100, 519);
+        let mut nether = world.dimension_view(Dimension::Nether);
+        assert!(!nether.contain_block_exact(stone)?);
+        nether.write_chunk_exact(pos, &chunk)?;
+        let mut cur = block::Cursor::new(nether);
+        cur.set_block_id(stone, block::Id::new("minecraft:stone"))?;
+        assert_eq!(cur.get_block_id(stone)?, "minecraft:stone");
+        assert_eq!(*cur.into_inner().dimension(), Dimension::Nether);
+        assert!(world.region_path(&Dimension::Nether, RegionKind::Terrain, 1, 1).exists());
+        let mut cur = block::Cursor::new(world);
+        assert_eq!(cur.get_block_id(stone)?, "minecraft:air");
+        let mut world = cur.into_inner();
+
+        // writing level.dat through the world updates what is kept
+        level.data_version = Some(2860);
+        world.write_level_data(&level)?;
+        assert_eq!(world.height_range(&Dimension::Overworld)?, HeightRange::new(-64, 384).unwrap());
+        let low = block::Pos::from_xyz(520, -10, 519);
+        assert!(world.contain_block_exact(low)?);
+        assert!(!world.dimension_view(Dimension::Nether).contain_block_exact(low)?);
+        drop(world);
+        fs::remove_dir_all(path)
+    }
+
+    #[test]
+    fn height_ranges() -> io::Result<()> {
+        let world = McJavaWorld::new("./test_worlds/water_only");
+        assert_eq!(world.height_range(&Dimension::Overworld)?, HeightRange::new(0, 256).unwrap());
+        let mut level = LevelData::new("tall", 1);
+        level.data_version = Some(2860);
+        assert_eq!(level.height_range(&Dimension::Overworld), HeightRange::new(-64, 384).unwrap());
+        assert_eq!(level.height_range(&Dimension::Nether), HeightRange::new(0, 256).unwrap());
+        // datapack dimensions, with their type inline or named
+        let mut deep = Compound::new();
+        deep.insert("min_y".to_string(), Tag::Int(-256));
+        deep.insert("height".to_string(), Tag::Int(512));
+        let mut dimensions = Compound::new();
+        for (id, dimension_type) in [("test:deep", Tag::Compound(deep)), ("test:void", Tag::String("minecraft:the_end".to_string()))].iter() {
+            let mut dimension = Compound::new();
+            dimension.insert("type".to_string(), dimension_type.clone());
+            dimensions.insert(id.to_string(), Tag::Compound(dimension));
+        }
+        let mut settings = Compound::new();
+        settings.insert("seed".to_string(), Tag::Long(1));
+        settings.insert("dimensions".to_string(), Tag::Compound(dimensions));
+        let mut data = level.to_nbt();
+        let root = data.root_tag_mut().as_compound_mut().unwrap();
+        root.get_mut("Data").and_then(Tag::as_compound_mut).unwrap()
+            .insert("WorldGenSettings".to_string(), Tag::Compound(settings));
+        let level = LevelData::from_nbt(data)?;
+        let deep = level.height_range(&Dimension::new_custom("test", "deep"));
+        assert_eq!((deep.min_y(), deep.max_y(), deep.min_section(), deep.section_count()), (-256, 256, -16, 32));
+        assert_eq!(level.height_range(&Dimension::new_custom("test", "void")).max_y(), 256);
+        assert_eq!(level.height_range(&Dimension::new_custom("test", "other")).min_y(), -64);
+        assert!(HeightRange::new(-2048, 16).is_none() && HeightRange::new(-64, 100).is_none());
         Ok(())
     }
 
@@ -0,0 +1,837 @@
+// blocks of anvil chunks since the flattening (1.13): sections of packed
+// indices into palettes of block states, under `Level` until 1.18.
+// https://minecraft.gamepedia.com/Chunk_format
+
+use std::io::{Error, ErrorKind, Result};
+use crate::biome;
+use crate::block::{Id, IdSystem, Meta, SharedHashSystem};
+use crate::block_entity::BlockEntity;
+use crate::entity::{self, Entity};
+use crate::chunk::{self, Biomes, Chunk, Heightmap, HeightmapKind, Packing, ScheduledTick, Section, TickKind};
+use crate::nbt::{self, Compound, Tag};
+use super::HeightRange;
+
+// first DataVersion with block state palettes (17w47a, 1.13)
+crate const DATA_VERSION_FLATTENING: i32 = 1451;
+// first DataVersion with biomes for 4*4*4 cells (19w36a, 1.15)
+crate const DATA_VERSION_3D_BIOMES: i32 = 2203;
+// first DataVersion with sections at the root as `sections` (21w43a, 1.18)
+crate const DATA_VERSION_SECTIONS_AT_ROOT: i32 = 2844;
+
+// a block state as an id, like `minecraft:oak_log[axis=y]` with properties
+// sorted by name, or only the name for blocks without properties
+crate fn block_state_to_id(state: &Compound) -> Result<String> {
+    let name = state.get("Name").and_then(Tag::as_str)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing block state name"))?;
+    let properties = match state.get("Properties") {
+        Some(Tag::Compound(properties)) if !properties.is_empty() => properties,
+        Some(Tag::Compound(_)) | None => return Ok(name.to_string()),
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid block state properties"))
+    };
+    let mut pairs = Vec::new();
+    for (key, value) in properties {
+        match value.as_str() {
+            Some(value) => pairs.push(format!("{}={}", key, value)),
+            None => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid block state property: {}", key)))
+        }
+    }
+    pairs.sort();
+    Ok(format!("{}[{}]", name, pairs.join(",")))
+}
+
+crate fn chunk_data_version(root: &Compound) -> Result<i32> {
+    match root.get("DataVersion") {
+        Some(Tag::Int(data_version)) if *data_version >= DATA_VERSION_FLATTENING => Ok(*data_version),
+        Some(Tag::Int(data_version)) => Err(Error::new(ErrorKind::InvalidData,
+            format!("Chunk before the flattening is not supported: DataVersion {}", data_version))),
+        _ => Err(Error::new(ErrorKind::InvalidData, "Chunk without DataVersion is not supported"))
+    }
+}
+
+// the compound holding `Sections` or `sections`
+fn sections_parent(root: &Compound, data_version: i32) -> Result<&Compound> {
+    if data_version >= DATA_VERSION_SECTIONS_AT_ROOT {
+        return Ok(root);
+    }
+    root.get("Level").and_then(Tag::as_compound)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing `Level` compound in chunk"))
+}
+
+// the palette and packed indices of a section, `None` for sections
+// only holding light
+fn section_block_states(section: &Compound, data_version: i32) -> Result<Option<(&[Tag], &[i64])>> {
+    let (palette, data) = if data_version >= DATA_VERSION_SECTIONS_AT_ROOT {
+        match section.get("block_states").and_then(Tag::as_compound) {
+            Some(states) => (states.get("palette"), states.get("data")),
+            None => return Ok(None)
+        }
+    } else {
+        (section.get("Palette"), section.get("BlockStates"))
+    };
+    let palette = match palette {
+        Some(Tag::List(palette)) => palette,
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid section palette")),
+        None => return Ok(None)
+    };
+    match data {
+        Some(Tag::LongArray(data)) => Ok(Some((palette, data))),
+        None => Ok(Some((palette, &[]))),
+        Some(_) => Err(Error::new(ErrorKind::InvalidData, "Invalid section block states"))
+    }
+}
+
+// bits for indices into a biome palette, none for a single biome
+fn biome_bits(palette_len: usize) -> u8 {
+    let mut bits = 0;
+    while 1 << bits < palette_len {
+        bits += 1;
+    }
+    bits
+}
+
+fn legacy_biome_meta(number: i32, biome_ids: &biome::SharedHashSystem) -> Result<biome::Meta> {
+    match biome::legacy_biome_id(number) {
+        Some(id) => Ok(biome_ids.register_biome(Id::new(id))),
+        None => Err(Error::new(ErrorKind::InvalidData, format!("Unknown biome number: {}", number)))
+    }
+}
+
+// the `Biomes` array before 1.18: 256 columns, or cells over the whole chunk since 1.15
+fn legacy_biomes(parent: &Compound, biome_ids: &biome::SharedHashSystem, range: HeightRange) -> Result<Option<Biomes>> {
+    let numbers: Vec<i32> = match parent.get("Biomes") {
+        Some(Tag::IntArray(numbers)) => numbers.clone(),
+        Some(Tag::ByteArray(numbers)) => numbers.iter().map(|&n| n as u8 as i32).collect(),
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid chunk biomes")),
+        None => return Ok(None)
+    };
+    let mut metas = Vec::with_capacity(numbers.len());
+    for number in numbers {
+        metas.push(legacy_biome_meta(number, biome_ids)?);
+    }
+    let cells = range.height() as usize / 4 * 16;
+    match metas.len() {
+        256 => Ok(Some(Biomes::Columns(metas))),
+        len if len == cells => Ok(Some(Biomes::Cells(metas))),
+        len => Err(Error::new(ErrorKind::InvalidData, format!("Biomes of {} entries, expected 256 or {}", len, cells)))
+    }
+}
+
+// the 4*4*4 cells of a section since 1.18, `None` if it holds no biomes
+fn section_biomes(section: &Compound, biome_ids: &biome::SharedHashSystem) -> Result<Option<Vec<biome::Meta>>> {
+    let biomes = match section.get("biomes") {
+        Some(Tag::Compound(biomes)) => biomes,
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid section biomes")),
+        None => return Ok(None)
+    };
+    let mut palette = Vec::new();
+    for id in biomes.get("palette").and_then(Tag::as_list).map(Vec::as_slice).unwrap_or(&[]) {
+        let id = id.as_str().ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid biome in palette"))?;
+        palette.push(biome_ids.register_biome(Id::new(id.to_string())));
+    }
+    if palette.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "Empty biome palette"));
+    }
+    if palette.len() == 1 {
+        return Ok(Some(vec![palette[0]; 64]));
+    }
+    let data = match biomes.get("data") {
+        Some(Tag::LongArray(data)) => data.as_slice(),
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Missing section biome data"))
+    };
+    let mut ans = Vec::with_capacity(64);
+    for index in chunk::unpack_longs(data, biome_bits(palette.len()), 64, Packing::Aligned)? {
+        match palette.get(index as usize) {
+            Some(&meta) => ans.push(meta),
+            None => return Err(Error::new(ErrorKind::InvalidData, "Biome palette index out of range"))
+        }
+    }
+    Ok(Some(ans))
+}
+
+// the `Entities` kept in chunks before 1.17
+fn legacy_entities(parent: &Compound) -> Result<Vec<Entity>> {
+    let entities = match parent.get("Entities") {
+        Some(Tag::List(entities)) => entities.as_slice(),
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid chunk entities")),
+        None => &[]
+    };
+    let mut ans = Vec::with_capacity(entities.len());
+    for entity in entities {
+        let entity = entity.as_compound()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid entity"))?;
+        ans.push(Entity::from_nbt(entity.clone())?);
+    }
+    Ok(ans)
+}
+
+// the entities of a chunk before 1.17, `None` for later chunks which
+// keep them in entity region files
+crate fn anvil_entities(data: &nbt::Data) -> Result<Option<Vec<Entity>>> {
+    let root = data.root_tag().as_compound()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid chunk root tag"))?;
+    let data_version = chunk_data_version(root)?;
+    if data_version >= entity::DATA_VERSION_ENTITY_CHUNKS {
+        return Ok(None);
+    }
+    legacy_entities(sections_parent(root, data_version)?).map(Some)
+}
+
+// replace the entities of a chunk before 1.17.
+// fail if the chunk keeps them in entity region files, or one is out of the chunk
+crate fn set_anvil_entities(data: &mut nbt::Data, pos: chunk::Pos, entities: &[Entity]) -> Result<()> {
+    let root = data.root_tag_mut().as_compound_mut()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid chunk root tag"))?;
+    let data_version = chunk_data_version(root)?;
+    if data_version >= entity::DATA_VERSION_ENTITY_CHUNKS {
+        return Err(Error::new(ErrorKind::InvalidInput, "Chunk keeps entities in entity region files"));
+    }
+    let level = root.get_mut("Level").and_then(Tag::as_compound_mut)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing `Level` compound in chunk"))?;
+    let mut list = Vec::with_capacity(entities.len());
+    for entity in entities {
+        if entity.chunk_pos() != pos {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Entity out of chunk: {:?}", entity.pos)));
+        }
+        list.push(Tag::Compound(entity.to_nbt(data_version)));
+    }
+    level.insert("Entities".to_string(), Tag::List(list));
+    Ok(())
+}
+
+// where the ticks of a kind are listed for the DataVersion, in the
+// compound holding the sections
+fn tick_list_name(kind: TickKind, data_version: i32) -> &'static str {
+    match (kind, data_version >= DATA_VERSION_SECTIONS_AT_ROOT) {
+        (TickKind::Block, false) => "TileTicks",
+        (TickKind::Fluid, false) => "LiquidTicks",
+        (TickKind::Block, true) => "block_ticks",
+        (TickKind::Fluid, true) => "fluid_ticks"
+    }
+}
+
+fn read_ticks(parent: &Compound, name: &str) -> Result<Vec<ScheduledTick>> {
+    let ticks = match parent.get(name) {
+        Some(Tag::List(ticks)) => ticks.as_slice(),
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid chunk {}", name))),
+        None => &[]
+    };
+    let mut ans = Vec::with_capacity(ticks.len());
+    for tick in ticks {
+        let tick = tick.as_compound()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid scheduled tick"))?;
+        let int = |key: &str| tick.get(key).and_then(Tag::as_int)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Missing scheduled tick field: {}", key)));
+        ans.push(ScheduledTick {
+            id: tick.get("i").and_then(Tag::as_str).map(String::from)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing scheduled tick field: i"))?,
+            pos: crate::block::Pos::from_xyz(int("x")?, int("y")?, int("z")?),
+            delay: int("t")?,
+            priority: tick.get("p").and_then(Tag::as_int).unwrap_or(0)
+        });
+    }
+    Ok(ans)
+}
+
+// the ticks of the chunk, and those of the original out of its height.
+// the lists are named as the DataVersion wants, so that ticks read from
+// an older chunk are converted when written into a newer one
+fn set_ticks(parent: &mut Compound, chunk: &Chunk, pos: chunk::Pos, data_version: i32) -> Result<()> {
+    for &kind in [TickKind::Block, TickKind::Fluid].iter() {
+        let name = tick_list_name(kind, data_version);
+        let mut ticks = Vec::new();
+        for tick in nbt::take_compound_list(parent, name)? {
+            match tick.get("y").and_then(Tag::as_int) {
+                Some(y) if !chunk.contains_y(y) => ticks.push(Tag::Compound(tick)),
+                _ => {}
+            }
+        }
+        for tick in chunk.ticks(kind) {
+            if tick.pos.to_chunk_pos() != pos {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("Scheduled tick out of chunk: {:?}", tick.pos)));
+            }
+            let (x, y, z) = tick.pos.to_xyz();
+            let mut map = Compound::new();
+            map.insert("i".to_string(), Tag::String(tick.id.clone()));
+            map.insert("x".to_string(), Tag::Int(x));
+            map.insert("y".to_string(), Tag::Int(y));
+            map.insert("z".to_string(), Tag::Int(z));
+            map.insert("t".to_string(), Tag::Int(tick.delay));
+            map.insert("p".to_string(), Tag::Int(tick.priority));
+            ticks.push(Tag::Compound(map));
+        }
+        parent.insert(name.to_string(), Tag::List(ticks));
+    }
+    Ok(())
+}
+
+// read the blocks and biomes of a chunk spanning `range`, giving every block
+// state and biome met a meta in `ids` and `biome_ids`. sections out of the range are skipped.
+// since 1.18 either no saved section has biomes or every one must
+crate fn anvil_to_chunk(data: &nbt::Data, ids: &SharedHashSystem, biome_ids: &biome::SharedHashSystem, range: HeightRange, buf: &mut Chunk) -> Result<()> {
+    let root = data.root_tag().as_compound()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid chunk root tag"))?;
+    let data_version = chunk_data_version(root)?;
+    let parent = sections_parent(root, data_version)?;
+    let name = if data_version >= DATA_VERSION_SECTIONS_AT_ROOT { "sections" } else { "Sections" };
+    let sections = match parent.get(name) {
+        Some(Tag::List(sections)) => sections.as_slice(),
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid chunk sections")),
+        None => &[]
+    };
+    *buf = Chunk::with_sections(range.min_section(), range.section_count());
+    if data_version < DATA_VERSION_SECTIONS_AT_ROOT {
+        buf.set_biomes(legacy_biomes(parent, biome_ids, range)?);
+    }
+    let mut cells = None;
+    let mut without_biomes = None;
+    for section in sections {
+        let section = section.as_compound()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid chunk section"))?;
+        let y = section.get("Y").and_then(Tag::as_byte)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing section Y"))?;
+        if !range.contains(y as i32 * 16) {
+            continue;
+        }
+        if data_version >= DATA_VERSION_SECTIONS_AT_ROOT {
+            match section_biomes(section, biome_ids)? {
+                Some(biomes) => {
+                    let index = (y as i32 - range.min_section()) as usize;
+                    let (cells, filled) = cells.get_or_insert_with(||
+                        (vec![biome::Meta::new(0); range.section_count() * 64], vec![false; range.section_count()]));
+                    cells[index * 64..index * 64 + 64].copy_from_slice(&biomes);
+                    filled[index] = true;
+                },
+                None => without_biomes = Some(y)
+            }
+        }
+        let (palette, states) = match section_block_states(section, data_version)? {
+            Some(block_states) => block_states,
+            None => continue
+        };
+        let mut metas = Vec::with_capacity(palette.len());
+        for state in palette {
+            let state = state.as_compound()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid block state in palette"))?;
+            metas.push(ids.register_block(Id::new(block_state_to_id(state)?)));
+        }
+        let section = Section::from_packed(metas, states, data_version)?;
+        if !section.is_empty() {
+            buf.set_section(y as i32, Some(section));
+        }
+    }
+    if let Some((mut cells, filled)) = cells {
+        // the cells would read as the zero meta, and be written back as such
+        if let Some(y) = without_biomes {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Missing biomes of section {}", y)));
+        }
+        // sections not saved at all, like those a taller range adds, take
+        // the biomes of the nearest saved section
+        for index in 0..filled.len() {
+            if filled[index] {
+                continue;
+            }
+            let nearest = (0..filled.len()).filter(|&i| filled[i])
+                .min_by_key(|&i| (i as i32 - index as i32).abs()).unwrap();
+            let (from, to) = (nearest * 64, index * 64);
+            let biomes = cells[from..from + 64].to_vec();
+            cells[to..to + 64].copy_from_slice(&biomes);
+        }
+        buf.set_biomes(Some(Biomes::Cells(cells)));
+    }
+    if data_version < entity::DATA_VERSION_ENTITY_CHUNKS {
+        buf.set_entities(Some(legacy_entities(parent)?));
+    }
+    buf.clear_ticks();
+    for &kind in [TickKind::Block, TickKind::Fluid].iter() {
+        for tick in read_ticks(parent, tick_list_name(kind, data_version))? {
+            if range.contains(tick.pos.to_xyz().1) {
+                buf.ticks_mut(kind).push(tick);
+            }
+        }
+    }
+    buf.clear_block_entities();
+    let name = if data_version >= DATA_VERSION_SECTIONS_AT_ROOT { "block_entities" } else { "TileEntities" };
+    let entities = match parent.get(name) {
+        Some(Tag::List(entities)) => entities.as_slice(),
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid chunk block entities")),
+        None => &[]
+    };
+    for entity in entities {
+        let entity = entity.as_compound()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid block entity"))?;
+        let entity = BlockEntity::from_nbt(entity.clone())?;
+        if range.contains(entity.pos.to_xyz().1) {
+            buf.set_block_entity(entity);
+        }
+    }
+    Ok(())
+}
+
+// the heightmaps stored in a chunk spanning `range`, of the kinds known here
+crate fn anvil_heightmaps(data: &nbt::Data, range: HeightRange) -> Result<Vec<(HeightmapKind, Heightmap)>> {
+    let root = data.root_tag().as_compound()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid chunk root tag"))?;
+    let data_version = chunk_data_version(root)?;
+    let heightmaps = match sections_parent(root, data_version)?.get("Heightmaps") {
+        Some(Tag::Compound(heightmaps)) => heightmaps,
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid chunk heightmaps")),
+        None => return Ok(Vec::new())
+    };
+    let mut ans = Vec::new();
+    for (name, tag) in heightmaps {
+        let kind = match HeightmapKind::from_name(name) {
+            Some(kind) => kind,
+            None => continue
+        };
+        let data = match tag {
+            Tag::LongArray(data) => data,
+            _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid heightmap: {}", name)))
+        };
+        ans.push((kind, Heightmap::from_packed(data, range.min_y(), range.height() as usize, data_version)?));
+    }
+    Ok(ans)
+}
+
+// the block state of an id made by `block_state_to_id`
+crate fn id_to_block_state(id: &str) -> Result<Compound> {
+    let invalid = || Error::new(ErrorKind::InvalidInput, format!("Invalid block state id: {}", id));
+    let mut state = Compound::new();
+    let name = match id.find('[') {
+        Some(start) if id.ends_with(']') => {
+            let mut properties = Compound::new();
+            for pair in id[start + 1..id.len() - 1].split(',') {
+                let mut parts = pair.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) if !key.is_empty() => {
+                        properties.insert(key.to_string(), Tag::String(value.to_string()))
+                    },
+                    _ => return Err(invalid())
+                };
+            }
+            state.insert("Properties".to_string(), Tag::Compound(properties));
+            &id[..start]
+        },
+        Some(_) => return Err(invalid()),
+        None => id
+    };
+    if name.is_empty() {
+        return Err(invalid());
+    }
+    state.insert("Name".to_string(), Tag::String(name.to_string()));
+    Ok(state)
+}
+
+// first DataVersion of 1.14, where a chunk done generating is `full`
+const DATA_VERSION_STATUS_FULL: i32 = 1952;
+
+// a chunk holding nothing but its position, as if just generated
+fn new_chunk_root(pos: chunk::Pos, min_section: i32, data_version: i32) -> Compound {
+    let (x, z) = pos.to_xz();
+    let mut level = Compound::new();
+    level.insert("xPos".to_string(), Tag::Int(x));
+    level.insert("zPos".to_string(), Tag::Int(z));
+    let status = if data_version >= DATA_VERSION_STATUS_FULL { "full" } else { "fullchunk" };
+    level.insert("Status".to_string(), Tag::String(status.to_string()));
+    level.insert("LastUpdate".to_string(), Tag::Long(0));
+    level.insert("InhabitedTime".to_string(), Tag::Long(0));
+    if data_version >= DATA_VERSION_SECTIONS_AT_ROOT {
+        level.insert("yPos".to_string(), Tag::Int(min_section));
+        return level;
+    }
+    let mut root = Compound::new();
+    root.insert("Level".to_string(), Tag::Compound(level));
+    root
+}
+
+// the palette and packed indices of a section, as stored for the DataVersion
+fn section_states(section: &Section, ids: &dyn IdSystem, data_version: i32) -> Result<(Tag, Vec<i64>)> {
+    let mut section = section.clone();
+    section.compact();
+    let mut palette = Vec::with_capacity(section.palette().len());
+    for &meta in section.palette() {
+        if !ids.has_block_meta(meta) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Block meta not in id system: {:?}", meta)));
+        }
+        palette.push(Tag::Compound(id_to_block_state(&ids.block_meta_to_id(meta).to_string())?));
+    }
+    Ok((Tag::List(palette), section.to_packed(data_version)))
+}
+
+fn set_section_states(section: &mut Compound, states: Option<(Tag, Vec<i64>)>, data_version: i32) {
+    if data_version >= DATA_VERSION_SECTIONS_AT_ROOT {
+        let (palette, data) = states.unwrap_or_else(|| {
+            let air = id_to_block_state("minecraft:air").unwrap();
+            (Tag::List(vec![Tag::Compound(air)]), Vec::new())
+        });
+        let mut block_states = Compound::new();
+        block_states.insert("palette".to_string(), palette);
+        if !data.is_empty() {
+            block_states.insert("data".to_string(), Tag::LongArray(data));
+        }
+        section.insert("block_states".to_string(), Tag::Compound(block_states));
+    } else if let Some((palette, data)) = states {
+        section.insert("Palette".to_string(), palette);
+        section.insert("BlockStates".to_string(), Tag::LongArray(data));
+    } else {
+        // an empty section stays only if it holds light
+        section.remove("Palette");
+        section.remove("BlockStates");
+    }
+}
+
+fn biome_number(meta: biome::Meta, biome_ids: &dyn biome::IdSystem) -> Result<i32> {
+    if !biome_ids.has_biome_meta(meta) {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Biome meta not in id system: {:?}", meta)));
+    }
+    let id = biome_ids.biome_meta_to_id(meta).to_string();
+    biome::legacy_biome_number(&id)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Biome without a number: {}", id)))
+}
+
+// the `Biomes` array before 1.18, columns taken at sea level before 1.15
+fn set_legacy_biomes(parent: &mut Compound, biomes: &Biomes, chunk: &Chunk, biome_ids: &dyn biome::IdSystem, data_version: i32) -> Result<()> {
+    let height = (chunk.max_y() - chunk.min_y()) as usize;
+    let biomes = if data_version >= DATA_VERSION_3D_BIOMES {
+        biomes.to_cells(height)
+    } else {
+        biomes.to_columns(((64 - chunk.min_y()).max(0) as usize).min(height - 1))
+    };
+    let mut numbers = Vec::with_capacity(biomes.metas().len());
+    for &meta in biomes.metas() {
+        numbers.push(biome_number(meta, biome_ids)?);
+    }
+    parent.insert("Biomes".to_string(), Tag::IntArray(numbers));
+    Ok(())
+}
+
+// the `biomes` of a section since 1.18, from its 64 cells
+fn section_biomes_tag(cells: &[biome::Meta], biome_ids: &dyn biome::IdSystem) -> Result<Tag> {
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity(cells.len());
+    for &meta in cells {
+        let index = match palette.iter().position(|&m| m == meta) {
+            Some(index) => index,
+            None => {
+                palette.push(meta);
+                palette.len() - 1
+            }
+        };
+        indices.push(index as u32);
+    }
+    let mut ids = Vec::with_capacity(palette.len());
+    for &meta in palette.iter() {
+        if !biome_ids.has_biome_meta(meta) {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Biome meta not in id system: {:?}", meta)));
+        }
+        ids.push(Tag::String(biome_ids.biome_meta_to_id(meta).to_string()));
+    }
+    let mut biomes = Compound::new();
+    let bits = biome_bits(palette.len());
+    biomes.insert("palette".to_string(), Tag::List(ids));
+    if bits > 0 {
+        biomes.insert("data".to_string(), Tag::LongArray(chunk::pack_longs(&indices, bits, Packing::Aligned)));
+    }
+    Ok(Tag::Compound(biomes))
+}
+
+// the block entities of the chunk, and those of the original out of its height
+fn set_block_entities(parent: &mut Compound, name: &str, chunk: &Chunk, pos: chunk::Pos) -> Result<()> {
+    let mut entities = Vec::new();
+    for entity in nbt::take_compound_list(parent, name)? {
+        match entity.get("y").and_then(Tag::as_int) {
+            Some(y) if !chunk.contains_y(y) => entities.push(Tag::Compound(entity)),
+            _ => {}
+        }
+    }
+    for entity in chunk.block_entities() {
+        if entity.pos.to_chunk_pos() != pos {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Block entity out of chunk: {:?}", entity.pos)));
+        }
+        entities.push(Tag::Compound(entity.to_nbt()));
+    }
+    parent.insert(name.to_string(), Tag::List(entities));
+    Ok(())
+}
+
+// keys of `Level` renamed when they moved to the root in 1.18
+static RENAMED_AT_ROOT: &[(&str, &str)] = &[
+    ("Sections", "sections"), ("TileEntities", "block_entities"), ("TileTicks", "block_ticks"),
+    ("LiquidTicks", "fluid_ticks"), ("Structures", "structures"), ("CarvingMasks", "carving_masks"),
+];
+
+// a chunk saved in the other layout, its `Level` contents moved to the root
+// under their 1.18 names or back. the blocks and biomes of its sections are
+// dropped, those of `chunk` are written in their place
+fn relayout(root: Compound, chunk: &Chunk, at_root: bool) -> Result<Compound> {
+    let (mut outer, mut inner) = if at_root {
+        let mut root = root;
+        match root.remove("Level") {
+            Some(Tag::Compound(level)) => (root, level),
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Missing `Level` compound in chunk"))
+        }
+    } else {
+        let mut level = root;
+        let mut root = Compound::new();
+        if let Some(data_version) = level.remove("DataVersion") {
+            root.insert("DataVersion".to_string(), data_version);
+        }
+        (root, level)
+    };
+    for &(level_name, root_name) in RENAMED_AT_ROOT {
+        let (old, new) = if at_root { (level_name, root_name) } else { (root_name, level_name) };
+        if let Some(tag) = inner.remove(old) {
+            inner.insert(new.to_string(), tag);
+        }
+    }
+    let (structures, old, new) = if at_root { ("structures", "Starts", "starts") } else { ("Structures", "starts", "Starts") };
+    if let Some(structures) = inner.get_mut(structures).and_then(Tag::as_compound_mut) {
+        if let Some(starts) = structures.remove(old) {
+            structures.insert(new.to_string(), starts);
+        }
+    }
+    let sections = if at_root { "sections" } else { "Sections" };
+    if let Some(sections) = inner.get_mut(sections).and_then(Tag::as_list_mut) {
+        for section in sections.iter_mut().filter_map(Tag::as_compound_mut) {
+            for key in ["Palette", "BlockStates", "block_states", "biomes"].iter() {
+                section.remove(*key);
+            }
+        }
+    }
+    if chunk.biomes().is_some() {
+        inner.remove("Biomes");
+    }
+    if !at_root {
+        inner.remove("yPos");
+        outer.insert("Level".to_string(), Tag::Compound(inner));
+        return Ok(outer);
+    }
+    // written to entity chunks instead
+    if chunk.has_entities() {
+        inner.remove("Entities");
+    }
+    inner.insert("yPos".to_string(), Tag::Int(chunk.min_section()));
+    outer.extend(inner);
+    Ok(outer)
+}
+
+// whether the saved chunk spans the same sections as `chunk`
+fn same_height(root: &Compound, chunk: &Chunk, at_root: bool) -> bool {
+    if at_root {
+        root.get("yPos").and_then(Tag::as_int) == Some(chunk.min_section())
+    } else {
+        chunk.min_section() == 0 && chunk.section_count() == 16
+    }
+}
+
+// the columns where the blocks of `chunk` differ from the saved `sections`,
+// metas not in `ids` taken as air like `Heightmap::compute` does
+fn changed_columns(chunk: &Chunk, sections: &[(i8, Compound)], ids: &dyn IdSystem, data_version: i32) -> Result<Vec<bool>> {
+    let id_of = |meta: Meta| if ids.has_block_meta(meta) {
+        ids.block_meta_to_id(meta).to_string()
+    } else {
+        "minecraft:air".to_string()
+    };
+    // saved sections with palette entries as metas of their index
+    let mut saved = Vec::new();
+    for (y, section) in sections {
+        if !chunk.contains_y(*y as i32 * 16) {
+            continue;
+        }
+        if let Some((palette, states)) = section_block_states(section, data_version)? {
+            let mut names = Vec::with_capacity(palette.len());
+            for state in palette {
+                let state = state.as_compound()
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid block state in palette"))?;
+                names.push(block_state_to_id(state)?);
+            }
+            let indices = (0..palette.len()).map(|i| Meta::new(i as u16)).collect();
+            saved.push((*y as i32, names, Section::from_packed(indices, states, data_version)?));
+        }
+    }
+    let mut changed = vec![false; 256];
+    let air = "minecraft:air".to_string();
+    for section_y in chunk.min_section()..chunk.min_section() + chunk.section_count() as i32 {
+        let old = saved.iter().find(|(y, _, _)| *y == section_y);
+        let new = chunk.section(section_y);
+        if old.is_none() && new.is_none() {
+            continue;
+        }
+        let new_names: Vec<(Meta, String)> = match new {
+            Some(section) => section.palette().iter().map(|&meta| (meta, id_of(meta))).collect(),
+            None => vec![(Meta::new(0), id_of(Meta::new(0)))]
+        };
+        for y in 0..16 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let column = z * 16 + x;
+                    if changed[column] {
+                        continue;
+                    }
+                    let old_name = match old {
+                        Some((_, names, section)) => &names[section.get_block_meta(x, y, z).to_inner() as usize],
+                        None => &air
+                    };
+                    let meta = new.map(|section| section.get_block_meta(x, y, z)).unwrap_or(Meta::new(0));
+                    let new_name = &new_names.iter().find(|(m, _)| *m == meta).unwrap().1;
+                    changed[column] = old_name != new_name;
+                }
+            }
+        }
+    }
+    Ok(changed)
+}
+
+// heightmaps of the kinds known here for the blocks of `chunk`, or of the kinds
+// the game keeps for finished chunks if there are none yet. with the saved
+// heightmaps of `saved`, their DataVersion and the columns changed since, only
+// those columns are recomputed and the rest kept as the game saved them.
+// unknown kinds are kept as is
+fn set_heightmaps(parent: &mut Compound, chunk: &Chunk, ids: &dyn IdSystem, data_version: i32,
+    saved: Option<(i32, &[bool])>) -> Result<()> {
+    let mut heightmaps = match parent.remove("Heightmaps") {
+        Some(Tag::Compound(heightmaps)) => heightmaps,
+        Some(_) => return Err(Error::new(ErrorKind::InvalidData, "Invalid chunk heightmaps")),
+        None => Compound::new()
+    };
+    let mut kinds: Vec<HeightmapKind> = heightmaps.keys().filter_map(|name| HeightmapKind::from_name(name)).collect();
+    if heightmaps.is_empty() {
+        kinds = vec![HeightmapKind::WorldSurface, HeightmapKind::OceanFloor,
+            HeightmapKind::MotionBlocking, HeightmapKind::MotionBlockingNoLeaves];
+    }
+    let height = (chunk.max_y() - chunk.min_y()) as usize;
+    for kind in kinds {
+        let old = match (saved, heightmaps.get(kind.name())) {
+            (Some((saved_version, changed)), Some(Tag::LongArray(data))) =>
+                Heightmap::from_packed(data, chunk.min_y(), height, saved_version).ok().map(|old| (old, changed)),
+            _ => None
+        };
+        let heightmap = match old {
+            Some((mut old, changed)) => {
+                if changed.iter().any(|&c| c) {
+                    let new = Heightmap::compute(chunk, kind, ids);
+                    for column in (0..256).filter(|&column| changed[column]) {
+                        let (x, z) = (column % 16, column / 16);
+                        old.set(x, z, new.get(x, z));
+                    }
+                }
+                old
+            },
+            None => Heightmap::compute(chunk, kind, ids)
+        };
+        let data = heightmap.to_packed(chunk.min_y(), height, data_version);
+        heightmaps.insert(kind.name().to_string(), Tag::LongArray(data));
+    }
+    parent.insert("Heightmaps".to_string(), Tag::Compound(heightmaps));
+    Ok(())
+}
+
+// write the blocks, biomes, block entities, scheduled ticks and, before 1.17,
+// entities of a chunk into chunk NBT for the DataVersion, naming them by their ids
+// in `ids` and `biome_ids`, with heightmaps recomputed where the blocks changed.
+// everything else of `original`, like structures, is kept as is, and so is
+// whatever the chunk holds none of; light is not updated.
+// an original laid out the other way, sections under `Level` or at the root,
+// is moved into the DataVersion's layout; sections out of the chunk's height
+// are kept too, without their blocks if moved
+crate fn chunk_to_anvil(chunk: &Chunk, pos: chunk::Pos, ids: &dyn IdSystem, biome_ids: &dyn biome::IdSystem,
+    original: Option<&nbt::Data>, data_version: i32) -> Result<nbt::Data> {
+    if data_version < DATA_VERSION_FLATTENING {
+        return Err(Error::new(ErrorKind::InvalidInput,
+            format!("Chunk before the flattening is not supported: DataVersion {}", data_version)));
+    }
+    let at_root = data_version >= DATA_VERSION_SECTIONS_AT_ROOT;
+    let (root_name, mut root, saved_version) = match original {
+        Some(original) => {
+            let root = original.root_tag().as_compound()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid chunk root tag"))?;
+            let saved_version = chunk_data_version(root)?;
+            if (saved_version >= DATA_VERSION_SECTIONS_AT_ROOT) != at_root {
+                // heightmaps are recomputed for the new height
+                (original.root_name().to_string(), relayout(root.clone(), chunk, at_root)?, None)
+            } else {
+                let saved_version = if same_height(root, chunk, at_root) { Some(saved_version) } else { None };
+                (original.root_name().to_string(), root.clone(), saved_version)
+            }
+        },
+        None => (String::new(), new_chunk_root(pos, chunk.min_section(), data_version), None)
+    };
+    root.insert("DataVersion".to_string(), Tag::Int(data_version));
+    let parent = if at_root {
+        &mut root
+    } else {
+        root.get_mut("Level").and_then(Tag::as_compound_mut)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing `Level` compound in chunk"))?
+    };
+    let name = if at_root { "sections" } else { "Sections" };
+    let mut sections = Vec::new();
+    for section in nbt::take_compound_list(parent, name)? {
+        let y = section.get("Y").and_then(Tag::as_byte)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing section Y"))?;
+        sections.push((y, section));
+    }
+    let changed = match saved_version {
+        Some(saved_version) => Some((saved_version, changed_columns(chunk, &sections, ids, saved_version)?)),
+        None => None
+    };
+    for (y, section) in sections.iter_mut() {
+        if !chunk.contains_y(*y as i32 * 16) {
+            continue;
+        }
+        let states = match chunk.section(*y as i32) {
+            Some(blocks) => Some(section_states(blocks, ids, data_version)?),
+            None => None
+        };
+        set_section_states(section, states, data_version);
+    }
+    let saved: Vec<i8> = sections.iter().map(|&(y, _)| y).collect();
+    for y in chunk.section_ys() {
+        let blocks = chunk.section(y).unwrap();
+        if saved.contains(&(y as i8)) || blocks.is_empty() {
+            continue;
+        }
+        let mut section = Compound::new();
+        section.insert("Y".to_string(), Tag::Byte(y as i8));
+        set_section_states(&mut section, Some(section_states(blocks, ids, data_version)?), data_version);
+        sections.push((y as i8, section));
+    }
+    match chunk.biomes() {
+        Some(biomes) if at_root => {
+            // every section of the chunk holds its biomes, even without blocks
+            let cells = biomes.to_cells((chunk.max_y() - chunk.min_y()) as usize);
+            let cells = cells.metas();
+            for y in chunk.min_section()..chunk.min_section() + chunk.section_count() as i32 {
+                if !sections.iter().any(|&(saved, _)| saved as i32 == y) {
+                    let mut section = Compound::new();
+                    section.insert("Y".to_string(), Tag::Byte(y as i8));
+                    set_section_states(&mut section, None, data_version);
+                    sections.push((y as i8, section));
+                }
+            }
+            for (y, section) in sections.iter_mut() {
+                if chunk.contains_y(*y as i32 * 16) {
+                    let start = (*y as i32 - chunk.min_section()) as usize * 64;
+                    section.insert("biomes".to_string(), section_biomes_tag(&cells[start..start + 64], biome_ids)?);
+                }
+            }
+        },
+        Some(biomes) => set_legacy_biomes(parent, biomes, chunk, biome_ids, data_version)?,
+        None => {}
+    }
+    sections.retain(|(_, section)| section.len() > 1);
+    sections.sort_by_key(|&(y, _)| y);
+    let sections = sections.into_iter().map(|(_, section)| Tag::Compound(section)).collect();
+    parent.insert(name.to_string(), Tag::List(sections));
+    if chunk.has_ticks() {
+        set_ticks(parent, chunk, pos, data_version)?;
+    }
+    if chunk.has_block_entities() {
+        set_block_entities(parent, if at_root { "block_entities" } else { "TileEntities" }, chunk, pos)?;
+    }
+    let changed = changed.as_ref().map(|(saved_version, changed)| (*saved_version, changed.as_slice()));
+    set_heightmaps(parent, chunk, ids, data_version, changed)?;
+    let mut data = nbt::Data::new(root_name, Tag::Compound(root));
+    if chunk.has_entities() && data_version < entity::DATA_VERSION_ENTITY_CHUNKS {
+        set_anvil_entities(&mut data, pos, chunk.entities())?;
+    }
+    Ok(data)
+}
@@ -0,0 +1,93 @@
+// incremental backups from the timestamps in region headers.
+// a delta is a folder laid out like a world, holding only the region files
+// of chunks changed since some time, payloads copied as is. deleted chunks
+// are not recorded, so restoring never removes anything.
+
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use super::{dimension, region, McJavaWorld, Region, RegionKind};
+
+const REGION_KINDS: [RegionKind; 3] = [RegionKind::Terrain, RegionKind::Entities, RegionKind::Poi];
+
+fn region_files(folder: &Path) -> Result<Vec<(PathBuf, (i32, i32))>> {
+    let mut ans = Vec::new();
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(ans),
+        Err(e) => return Err(e)
+    };
+    for entry in entries {
+        let entry = entry?;
+        if let Some(pos) = entry.file_name().to_str().and_then(|name| region::parse_region_file_name(name, "mca")) {
+            ans.push((entry.path(), pos));
+        }
+    }
+    ans.sort_by_key(|&(_, pos)| pos);
+    Ok(ans)
+}
+
+// copy every chunk of every dimension modified at or after `since`, in seconds
+// since epoch, into the delta folder. returns the number of chunks copied.
+// take `region::now_timestamp` before starting, as the `since` of the next delta.
+// the cutoff is inclusive as timestamps are whole seconds: a chunk saved in the
+// second the previous export started is copied again rather than missed
+pub fn export_changed_chunks<P: AsRef<Path>>(world: &McJavaWorld<P>, since: u32, delta: impl AsRef<Path>) -> Result<usize> {
+    let mut count = 0;
+    for dim in world.dimensions()? {
+        for &kind in REGION_KINDS.iter() {
+            let to_folder = delta.as_ref().join(dim.folder()).join(kind.folder_name());
+            for (path, _) in region_files(&world.region_folder(&dim, kind))? {
+                let mut from = Region::open(&path)?;
+                let mut to: Option<Region> = None;
+                for pos in from.chunk_positions() {
+                    let timestamp = match from.chunk_timestamp(pos) {
+                        Some(timestamp) if timestamp >= since => timestamp,
+                        _ => continue
+                    };
+                    let (compression, data) = match from.read_chunk_raw(pos)? {
+                        Some(raw) => raw,
+                        None => continue
+                    };
+                    if to.is_none() {
+                        fs::create_dir_all(&to_folder)?;
+                        to = Some(Region::open_writable(to_folder.join(path.file_name().unwrap()))?);
+                    }
+                    to.as_mut().unwrap().write_chunk_raw_at(pos, compression, &data, timestamp)?;
+                    count += 1;
+                }
+                if let Some(to) = to {
+                    to.sync_all()?;
+                }
+            }
+        }
+    }
+    Ok(count)
+}
+
+// write every chunk of a delta back into the world, keeping their timestamps.
+// apply deltas from the oldest to the newest. returns the number of chunks restored
+pub fn restore_changed_chunks<P: AsRef<Path>>(world: &mut McJavaWorld<P>, delta: impl AsRef<Path>) -> Result<usize> {
+    world.check_writable()?;
+    let mut count = 0;
+    // a delta may hold only entities or poi of a dimension
+    let folders: Vec<&str> = REGION_KINDS.iter().map(RegionKind::folder_name).collect();
+    for dim in dimension::list_dimensions_holding(delta.as_ref(), &folders)? {
+        for &kind in REGION_KINDS.iter() {
+            let from_folder = delta.as_ref().join(dim.folder()).join(kind.folder_name());
+            for (path, (region_x, region_z)) in region_files(&from_folder)? {
+                let mut from = Region::open(&path)?;
+                let mut to = world.open_region_writable(&dim, kind, region_x, region_z)?;
+                for pos in from.chunk_positions() {
+                    let timestamp = from.chunk_timestamp(pos).unwrap_or(0);
+                    if let Some((compression, data)) = from.read_chunk_raw(pos)? {
+                        to.write_chunk_raw_at(pos, compression, &data, timestamp)?;
+                        count += 1;
+                    }
+                }
+                to.sync_all()?;
+            }
+        }
+    }
+    Ok(count)
+}
@@ -0,0 +1,390 @@
+// a world wrapper that keeps recently used chunks in memory,
+// so that block-by-block access does not hit the disk every time
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+use crate::block::{self, IdOperate, IdSystem};
+use crate::chunk::{self, Chunk};
+use super::HeightRange;
+
+pub const DEFAULT_CHUNK_CAPACITY: usize = 256;
+
+struct Entry {
+    chunk: Box<Chunk>,
+    dirty: bool,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct ChunkCache {
+    entries: HashMap<chunk::Pos, Entry>,
+    tick: u64,
+}
+
+impl ChunkCache {
+    fn touch(&mut self, pos: chunk::Pos) -> Option<&mut Entry> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(&pos)?;
+        entry.last_used = self.tick;
+        Some(entry)
+    }
+
+    fn insert(&mut self, pos: chunk::Pos, chunk: Box<Chunk>, dirty: bool) {
+        self.tick += 1;
+        self.entries.insert(pos, Entry { chunk, dirty, last_used: self.tick });
+    }
+
+    // least recently used, among clean chunks only if `clean_only`
+    fn least_recently_used(&self, clean_only: bool) -> Option<chunk::Pos> {
+        self.entries.iter()
+            .filter(|(_, entry)| !clean_only || !entry.dirty)
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(&pos, _)| pos)
+    }
+}
+
+// chunks are evicted least recently used first.
+// dirty chunks are only written back on writes, flushes or drop, as reading
+// does not borrow the inner world mutably; until then the cache may hold
+// more dirty chunks than its capacity.
+pub struct BufferedWorld<W: chunk::ReadExact + chunk::WriteExact> {
+    // always `Some` except in `into_inner`
+    inner: Option<W>,
+    cache: RefCell<ChunkCache>,
+    capacity: usize,
+    // of chunks created by writing into missing ones
+    new_chunk_range: HeightRange,
+}
+
+impl<W: chunk::ReadExact + chunk::WriteExact> BufferedWorld<W> {
+    pub fn new(inner: W) -> BufferedWorld<W> {
+        BufferedWorld::with_capacity(DEFAULT_CHUNK_CAPACITY, inner)
+    }
+
+    // panic if capacity is zero
+    pub fn with_capacity(capacity: usize, inner: W) -> BufferedWorld<W> {
+        assert!(capacity > 0, "chunk capacity must not be zero");
+        BufferedWorld {
+            inner: Some(inner),
+            cache: RefCell::new(ChunkCache::default()),
+            capacity,
+            new_chunk_range: HeightRange::new(0, 256).unwrap()
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // blocks written into missing chunks create chunks spanning `range`,
+    // 0..256 unless set; use the height of the dimension written to
+    pub fn set_new_chunk_range(&mut self, range: HeightRange) {
+        self.new_chunk_range = range;
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    // writing into the inner world directly bypasses the cache
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    // write back all dirty chunks and return the inner world
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush_chunks()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    pub fn cached_chunks(&self) -> Vec<chunk::Pos> {
+        self.cache.borrow().entries.keys().cloned().collect()
+    }
+
+    pub fn dirty_chunks(&self) -> Vec<chunk::Pos> {
+        self.cache.borrow().entries.iter()
+            .filter(|(_, entry)| entry.dirty)
+            .map(|(&pos, _)| pos)
+            .collect()
+    }
+
+    pub fn flush_chunks(&mut self) -> Result<()> {
+        let inner = self.inner.as_mut().unwrap();
+        for (&pos, entry) in self.cache.get_mut().entries.iter_mut() {
+            if entry.dirty {
+                inner.write_chunk_exact(pos, &entry.chunk)?;
+                entry.dirty = false;
+            }
+        }
+        Ok(())
+    }
+
+    // drop every cached chunk without writing them back
+    pub fn discard(&mut self) {
+        self.cache.get_mut().entries.clear();
+    }
+
+    // make sure the chunk is cached; `Ok(false)` if the inner world does not have it
+    fn load(&self, pos: chunk::Pos) -> Result<bool> {
+        let mut cache = self.cache.borrow_mut();
+        if cache.touch(pos).is_some() {
+            return Ok(true);
+        }
+        let inner = self.get_ref();
+        if !inner.contains_chunk_exact(pos)? {
+            return Ok(false);
+        }
+        let mut chunk = Box::new(Chunk::new());
+        inner.read_chunk_exact(pos, &mut chunk)?;
+        while cache.entries.len() >= self.capacity {
+            match cache.least_recently_used(true) {
+                Some(old) => { cache.entries.remove(&old); },
+                None => break
+            }
+        }
+        cache.insert(pos, chunk, false);
+        Ok(true)
+    }
+
+    // make room for one more chunk, writing back if needed
+    fn evict(&mut self) -> Result<()> {
+        let inner = self.inner.as_mut().unwrap();
+        let cache = self.cache.get_mut();
+        while cache.entries.len() >= self.capacity {
+            let old = match cache.least_recently_used(false) {
+                Some(old) => old,
+                None => break
+            };
+            // written back before leaving the cache, so that it stays
+            // cached and dirty if writing fails
+            let entry = &cache.entries[&old];
+            if entry.dirty {
+                inner.write_chunk_exact(old, &entry.chunk)?;
+            }
+            cache.entries.remove(&old);
+        }
+        Ok(())
+    }
+}
+
+impl<W: chunk::ReadExact + chunk::WriteExact> Drop for BufferedWorld<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            // errors are ignored here, call `flush_chunks` to handle them
+            let _ = self.flush_chunks();
+        }
+    }
+}
+
+#[inline]
+fn split_block_pos(pos: block::Pos) -> (chunk::Pos, usize, i32, usize) {
+    let (x, y, z) = pos.to_xyz();
+    (chunk::Pos::from_xz(x >> 4, z >> 4), (x & 15) as usize, y, (z & 15) as usize)
+}
+
+fn check_height(chunk: &Chunk, y: i32) -> Result<()> {
+    if !chunk.contains_y(y) {
+        return Err(Error::new(ErrorKind::InvalidInput,
+            format!("Block y out of range: {}, chunk spans {}..{}", y, chunk.min_y(), chunk.max_y())));
+    }
+    Ok(())
+}
+
+impl<W: chunk::ReadExact + chunk::WriteExact> block::ReadExact for BufferedWorld<W> {
+    fn read_block_exact(&self, pos: block::Pos) -> Result<block::Meta> {
+        let (chunk_pos, x, y, z) = split_block_pos(pos);
+        if !self.load(chunk_pos)? {
+            return Err(Error::new(ErrorKind::NotFound, format!("Chunk not found: {:?}", chunk_pos)));
+        }
+        let cache = self.cache.borrow();
+        let chunk = &cache.entries[&chunk_pos].chunk;
+        check_height(chunk, y)?;
+        Ok(chunk.get_block_meta(x, y, z))
+    }
+
+    // loads the chunk to know its height
+    fn contain_block_exact(&self, pos: block::Pos) -> Result<bool> {
+        let (chunk_pos, _, y, _) = split_block_pos(pos);
+        if !self.load(chunk_pos)? {
+            return Ok(false);
+        }
+        Ok(self.cache.borrow().entries[&chunk_pos].chunk.contains_y(y))
+    }
+}
+
+impl<W: chunk::ReadExact + chunk::WriteExact> block::WriteExact for BufferedWorld<W> {
+    // blocks written into missing chunks create a new chunk of zeroed metas
+    fn write_block_exact(&mut self, pos: block::Pos, meta: block::Meta) -> Result<()> {
+        let (chunk_pos, x, y, z) = split_block_pos(pos);
+        if !self.cache.get_mut().entries.contains_key(&chunk_pos) {
+            self.evict()?;
+            if !self.load(chunk_pos)? {
+                let range = self.new_chunk_range;
+                let chunk = Chunk::with_sections(range.min_section(), range.section_count());
+                self.cache.get_mut().insert(chunk_pos, Box::new(chunk), true);
+            }
+        }
+        let entry = self.cache.get_mut().touch(chunk_pos).unwrap();
+        check_height(&entry.chunk, y)?;
+        entry.chunk.set_block_meta(x, y, z, meta);
+        entry.dirty = true;
+        Ok(())
+    }
+
+    fn flush_block(&mut self) -> Result<()> {
+        self.flush_chunks()
+    }
+}
+
+impl<W: chunk::ReadExact + chunk::WriteExact> chunk::ReadExact for BufferedWorld<W> {
+    fn read_chunk_exact(&self, pos: chunk::Pos, buf: &mut Chunk) -> Result<()> {
+        if !self.load(pos)? {
+            return Err(Error::new(ErrorKind::NotFound, format!("Chunk not found: {:?}", pos)));
+        }
+        buf.clone_from(&self.cache.borrow().entries[&pos].chunk);
+        Ok(())
+    }
+
+    fn contains_chunk_exact(&self, pos: chunk::Pos) -> Result<bool> {
+        Ok(self.cache.borrow().entries.contains_key(&pos) || self.get_ref().contains_chunk_exact(pos)?)
+    }
+}
+
+impl<W: chunk::ReadExact + chunk::WriteExact> chunk::WriteExact for BufferedWorld<W> {
+    fn write_chunk_exact(&mut self, pos: chunk::Pos, chunk: &Chunk) -> Result<()> {
+        match self.cache.get_mut().touch(pos) {
+            Some(entry) => {
+                (*entry.chunk).clone_from(chunk);
+                entry.dirty = true;
+            },
+            None => {
+                self.evict()?;
+                self.cache.get_mut().insert(pos, Box::new(chunk.clone()), true);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: chunk::ReadExact + chunk::WriteExact + IdOperate> IdOperate for BufferedWorld<W> {
+    fn block_id_system(&self) -> &dyn IdSystem {
+        self.get_ref().block_id_system()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use crate::block::{global_id_system, Cursor, HashSystem, Id, Pos};
+
+    // chunks kept in memory, counting accesses
+    struct MemoryWorld {
+        chunks: HashMap<chunk::Pos, Chunk>,
+        reads: Cell<usize>,
+        writes: usize,
+        // fail every write, like a locked region file
+        fail_writes: bool,
+        ids: HashSystem,
+    }
+
+    impl MemoryWorld {
+        fn new() -> MemoryWorld {
+            let mut chunks = HashMap::new();
+            for x in -2..2 {
+                for z in -2..2 {
+                    chunks.insert(chunk::Pos::from_xz(x, z), Chunk::new());
+                }
+            }
+            MemoryWorld { chunks, reads: Cell::new(0), writes: 0, fail_writes: false, ids: global_id_system() }
+        }
+    }
+
+    impl chunk::ReadExact for MemoryWorld {
+        fn read_chunk_exact(&self, pos: chunk::Pos, buf: &mut Chunk) -> Result<()> {
+            self.reads.set(self.reads.get() + 1);
+            buf.clone_from(&self.chunks[&pos]);
+            Ok(())
+        }
+
+        fn contains_chunk_exact(&self, pos: chunk::Pos) -> Result<bool> {
+            Ok(self.chunks.contains_key(&pos))
+        }
+    }
+
+    impl chunk::WriteExact for MemoryWorld {
+        fn write_chunk_exact(&mut self, pos: chunk::Pos, chunk: &Chunk) -> Result<()> {
+            if self.fail_writes {
+                return Err(Error::new(ErrorKind::PermissionDenied, "Region file is locked"));
+            }
+            self.writes += 1;
+            self.chunks.insert(pos, chunk.clone());
+            Ok(())
+        }
+    }
+
+    impl IdOperate for MemoryWorld {
+        fn block_id_system(&self) -> &dyn IdSystem {
+            &self.ids
+        }
+    }
+
+    #[test]
+    fn cache_and_write_back() -> Result<()> {
+        let mut cur = Cursor::new(BufferedWorld::with_capacity(2, MemoryWorld::new()));
+        let stone = Id::new("minecraft:stone");
+        for x in 0..16 {
+            cur.set_block_id(Pos::from_xyz(x, 10, -1), stone.clone())?;
+        }
+        assert_eq!(cur.get_block_id(Pos::from_xyz(3, 10, -1))?, "minecraft:stone");
+        assert_eq!(cur.get_block_id(Pos::from_xyz(3, 11, -1))?, "minecraft:air");
+        let world = cur.into_inner();
+        assert_eq!(world.get_ref().reads.get(), 1); // one chunk read once
+        assert_eq!(world.get_ref().writes, 0);
+        assert_eq!(world.dirty_chunks(), vec![chunk::Pos::from_xz(0, -1)]);
+        let mut cur = Cursor::new(world);
+        // touch two more chunks, the dirty one gets evicted and written back
+        cur.set_block_id(Pos::from_xyz(-1, 0, 0), stone.clone())?;
+        cur.set_block_id(Pos::from_xyz(-17, 0, 0), stone.clone())?;
+        let mut world = cur.into_inner();
+        assert_eq!(world.get_ref().writes, 1);
+        assert_eq!(world.cached_chunks().len(), 2);
+        block::WriteExact::flush_block(&mut world)?;
+        assert_eq!(world.get_ref().writes, 3);
+        assert!(world.dirty_chunks().is_empty());
+        // missing chunks are created on write, and written back on drop
+        world.set_new_chunk_range(HeightRange::new(-64, 384).unwrap());
+        let mut cur = Cursor::new(world);
+        assert!(cur.get_block_meta(Pos::from_xyz(100, 0, 100)).is_err());
+        cur.set_block_id(Pos::from_xyz(100, -64, 100), stone.clone())?;
+        assert_eq!(cur.get_block_meta(Pos::from_xyz(100, -65, 100)).unwrap_err().kind(), ErrorKind::InvalidInput);
+        // chunks read keep their own height
+        assert!(cur.set_block_id(Pos::from_xyz(3, -1, -1), stone.clone()).is_err());
+        assert!(cur.set_block_id(Pos::from_xyz(3, 256, -1), stone).is_err());
+        let inner = cur.into_inner().into_inner()?;
+        assert_eq!(inner.writes, 4);
+        assert_eq!(inner.chunks[&chunk::Pos::from_xz(6, 6)].min_y(), -64);
+        Ok(())
+    }
+
+    #[test]
+    fn failed_write_back() -> Result<()> {
+        let mut cur = Cursor::new(BufferedWorld::with_capacity(1, MemoryWorld::new()));
+        let stone = Id::new("minecraft:stone");
+        cur.set_block_id(Pos::from_xyz(0, 10, 0), stone.clone())?;
+        let mut world = cur.into_inner();
+        world.get_mut().fail_writes = true;
+        // evicting the dirty chunk fails, and the edit is kept
+        let mut cur = Cursor::new(world);
+        assert!(cur.set_block_id(Pos::from_xyz(-1, 10, 0), stone.clone()).is_err());
+        let mut world = cur.into_inner();
+        assert_eq!(world.dirty_chunks(), vec![chunk::Pos::from_xz(0, 0)]);
+        world.get_mut().fail_writes = false;
+        let inner = world.into_inner()?;
+        assert_eq!(inner.writes, 1);
+        let meta = inner.chunks[&chunk::Pos::from_xz(0, 0)].get_block_meta(0, 10, 0);
+        assert_eq!(inner.ids.block_id_to_meta(stone), meta);
+        Ok(())
+    }
+}
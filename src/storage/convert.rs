@@ -0,0 +1,148 @@
+// upgrade McRegion worlds into Anvil as of 1.13: 128 high chunks are cut
+// into 16 high sections, and their numeric blocks are named by `LegacyIdSystem`
+// and written as block state palettes by `anvil::chunk_to_anvil`.
+// `.mca` files are written next to the untouched `.mcr` files.
+// only blocks and light are converted. entities, tile entities and ticks are
+// copied in the format of their time, which the game does not upgrade any
+// more, and biomes are left for the game to fill in
+// https://minecraft.gamepedia.com/Anvil_file_format
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use crate::{biome, chunk};
+use crate::block::LegacyIdSystem;
+use crate::chunk::Chunk;
+use crate::nbt::{self, Compound, Tag};
+use super::{Dimension, McJavaWorld, McRegionWorld, Region, RegionKind};
+use super::anvil;
+use super::legacy::{LEGACY_HEIGHT, legacy_block_index, legacy_level};
+use super::region;
+
+// `version` in level.dat of Anvil worlds
+crate const VERSION_ANVIL: i32 = 19133;
+// the DataVersion converted chunks are written for (1.13)
+crate const DATA_VERSION_CONVERTED: i32 = 1519;
+
+#[inline]
+fn get_nibble(array: &[i8], index: usize) -> u8 {
+    (array[index >> 1] as u8 >> ((index & 1) * 4)) & 0xF
+}
+
+#[inline]
+fn set_nibble(array: &mut [i8], index: usize, value: u8) {
+    let shift = (index & 1) * 4;
+    let byte = array[index >> 1] as u8 & !(0xF << shift);
+    array[index >> 1] = (byte | ((value & 0xF) << shift)) as i8;
+}
+
+// convert a McRegion chunk into an Anvil one of `DATA_VERSION_CONVERTED`.
+// `remap` maps legacy (id, data) before the blocks are named; blocks
+// `LegacyIdSystem` has no name for are refused
+pub fn convert_legacy_chunk(legacy: &nbt::Data, remap: &dyn Fn(u8, u8) -> (u8, u8)) -> Result<nbt::Data> {
+    let mut level = legacy_level(legacy)?.clone();
+    let byte_array = |level: &mut Compound, name: &str, len: usize| match level.remove(name) {
+        Some(Tag::ByteArray(array)) if array.len() == len => Ok(array),
+        _ => Err(Error::new(ErrorKind::InvalidData, format!("Invalid `{}` in legacy chunk", name)))
+    };
+    let pos = match (level.get("xPos"), level.get("zPos")) {
+        (Some(Tag::Int(x)), Some(Tag::Int(z))) => chunk::Pos::from_xz(*x, *z),
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Missing position in legacy chunk"))
+    };
+    let volume = 16 * LEGACY_HEIGHT * 16;
+    let blocks = byte_array(&mut level, "Blocks", volume)?;
+    let data = byte_array(&mut level, "Data", volume / 2)?;
+    let sky_light = byte_array(&mut level, "SkyLight", volume / 2)?;
+    let block_light = byte_array(&mut level, "BlockLight", volume / 2)?;
+    byte_array(&mut level, "HeightMap", 256)?;
+    // the chunk spans the height of the world it goes into
+    let mut chunk = Chunk::with_sections(0, 16);
+    let mut sections = Vec::new();
+    for section_y in 0..LEGACY_HEIGHT / 16 {
+        let mut section_sky_light = vec![0i8; 2048];
+        let mut section_block_light = vec![0i8; 2048];
+        let mut has_blocks = false;
+        for y in 0..16 {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let legacy_index = legacy_block_index(x, section_y * 16 + y, z);
+                    let index = (y << 8) | (z << 4) | x;
+                    let (id, value) = remap(blocks[legacy_index] as u8, get_nibble(&data, legacy_index));
+                    has_blocks |= id != 0;
+                    chunk.set_block_meta(x, (section_y * 16 + y) as i32, z, LegacyIdSystem::meta_from_legacy(id, value));
+                    set_nibble(&mut section_sky_light, index, get_nibble(&sky_light, legacy_index));
+                    set_nibble(&mut section_block_light, index, get_nibble(&block_light, legacy_index));
+                }
+            }
+        }
+        // sections of only air are not saved
+        if !has_blocks {
+            continue;
+        }
+        // blocks are added by `chunk_to_anvil`
+        let mut section = Compound::new();
+        section.insert("Y".to_string(), Tag::Byte(section_y as i8));
+        section.insert("SkyLight".to_string(), Tag::ByteArray(section_sky_light));
+        section.insert("BlockLight".to_string(), Tag::ByteArray(section_block_light));
+        sections.push(Tag::Compound(section));
+    }
+    level.insert("Sections".to_string(), Tag::List(sections));
+    // done generating, as it was
+    level.insert("Status".to_string(), Tag::String("postprocessed".to_string()));
+    let mut root = legacy.root_tag().as_compound().cloned().unwrap_or_default();
+    root.insert("Level".to_string(), Tag::Compound(level));
+    root.insert("DataVersion".to_string(), Tag::Int(DATA_VERSION_CONVERTED));
+    let original = nbt::Data::new(legacy.root_name(), Tag::Compound(root));
+    anvil::chunk_to_anvil(&chunk, pos, &LegacyIdSystem::new(), &biome::SharedHashSystem::new(), Some(&original), DATA_VERSION_CONVERTED)
+}
+
+// convert every dimension of a McRegion world, blocks named as they were
+pub fn convert_mcregion_world<P, Q>(from: &McRegionWorld<P>, to: &mut McJavaWorld<Q>) -> Result<usize>
+where P: AsRef<Path>, Q: AsRef<Path> {
+    convert_mcregion_world_with(from, to, &|id, data| (id, data))
+}
+
+// convert every dimension of a McRegion world into `to`, which may be the same folder.
+// level.dat is copied over if `to` has none, and marked as Anvil of the
+// converted chunks' DataVersion.
+// returns the number of chunks converted
+pub fn convert_mcregion_world_with<P, Q>(from: &McRegionWorld<P>, to: &mut McJavaWorld<Q>, remap: &dyn Fn(u8, u8) -> (u8, u8)) -> Result<usize>
+where P: AsRef<Path>, Q: AsRef<Path> {
+    to.check_writable()?;
+    let mut count = 0;
+    for dim in [Dimension::Overworld, Dimension::Nether, Dimension::End].iter() {
+        let dir = from.path().join(dim.folder()).join("region");
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let (region_x, region_z) = match entry.file_name().to_str().and_then(|name| region::parse_region_file_name(name, "mcr")) {
+                Some(pos) => pos,
+                None => continue
+            };
+            let mut legacy_region = Region::open(entry.path())?;
+            let mut anvil_region = to.open_region_writable(dim, RegionKind::Terrain, region_x, region_z)?;
+            for pos in legacy_region.chunk_positions() {
+                if let Some(legacy) = legacy_region.read_chunk_nbt(pos)? {
+                    anvil_region.write_chunk_nbt(pos, &convert_legacy_chunk(&legacy, remap)?)?;
+                    count += 1;
+                }
+            }
+            anvil_region.sync_all()?;
+        }
+    }
+    let level_path = to.path().join("level.dat");
+    let level_path = if level_path.exists() { level_path } else { from.path().join("level.dat") };
+    if level_path.exists() {
+        let mut level = super::read_gzip_nbt(level_path)?;
+        if let Some(data) = level.root_tag_mut().as_compound_mut()
+            .and_then(|root| root.get_mut("Data"))
+            .and_then(Tag::as_compound_mut) {
+            data.insert("version".to_string(), Tag::Int(VERSION_ANVIL));
+            data.insert("DataVersion".to_string(), Tag::Int(DATA_VERSION_CONVERTED));
+        }
+        to.write_level_nbt(&level)?;
+    }
+    Ok(count)
+}
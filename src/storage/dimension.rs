@@ -0,0 +1,203 @@
+// dimensions and where their data lives inside a world folder
+
+use std::fmt;
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Dimension {
+    Overworld,
+    Nether,
+    End,
+    // datapack dimensions since 1.16, `namespace:path`
+    Custom { namespace: String, path: String },
+}
+
+impl Dimension {
+    pub fn new_custom(namespace: impl Into<String>, path: impl Into<String>) -> Dimension {
+        Dimension::Custom {
+            namespace: namespace.into(),
+            path: path.into()
+        }
+    }
+
+    // parse a resource location like `minecraft:the_nether`.
+    // the namespace defaults to `minecraft` as in vanilla
+    pub fn from_id(id: &str) -> Option<Dimension> {
+        let (namespace, path) = match id.find(':') {
+            Some(i) => (&id[..i], &id[i + 1..]),
+            None => ("minecraft", id)
+        };
+        if namespace.is_empty() || path.is_empty() {
+            return None;
+        }
+        Some(match (namespace, path) {
+            ("minecraft", "overworld") => Dimension::Overworld,
+            ("minecraft", "the_nether") => Dimension::Nether,
+            ("minecraft", "the_end") => Dimension::End,
+            _ => Dimension::new_custom(namespace, path)
+        })
+    }
+
+    // numeric ids used before 1.16, e.g. by player data
+    pub fn from_legacy_id(id: i32) -> Option<Dimension> {
+        match id {
+            0 => Some(Dimension::Overworld),
+            -1 => Some(Dimension::Nether),
+            1 => Some(Dimension::End),
+            _ => None
+        }
+    }
+
+    pub fn legacy_id(&self) -> Option<i32> {
+        match self {
+            Dimension::Overworld => Some(0),
+            Dimension::Nether => Some(-1),
+            Dimension::End => Some(1),
+            Dimension::Custom { .. } => None
+        }
+    }
+
+    pub fn id(&self) -> String {
+        match self {
+            Dimension::Overworld => "minecraft:overworld".to_string(),
+            Dimension::Nether => "minecraft:the_nether".to_string(),
+            Dimension::End => "minecraft:the_end".to_string(),
+            Dimension::Custom { namespace, path } => format!("{}:{}", namespace, path),
+        }
+    }
+
+    // folder of this dimension relative to the world folder
+    pub fn folder(&self) -> PathBuf {
+        match self {
+            Dimension::Overworld => PathBuf::new(),
+            Dimension::Nether => PathBuf::from("DIM-1"),
+            Dimension::End => PathBuf::from("DIM1"),
+            Dimension::Custom { namespace, path } => {
+                let mut ans = PathBuf::from("dimensions");
+                ans.push(namespace);
+                // paths may be nested like `foo/bar`
+                ans.extend(path.split('/'));
+                ans
+            }
+        }
+    }
+}
+
+// the blocks a dimension spans vertically, in whole sections.
+// 0..256 before 1.18, and any range within -2032..2032 for datapack dimensions
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct HeightRange {
+    min_y: i32,
+    height: i32,
+}
+
+// first DataVersion where the overworld spans -64..320 (21w37a, 1.18)
+const DATA_VERSION_EXTENDED_HEIGHT: i32 = 2834;
+
+impl HeightRange {
+    // `None` unless both are multiples of 16 within the limits of the game
+    pub fn new(min_y: i32, height: i32) -> Option<HeightRange> {
+        if min_y % 16 != 0 || height % 16 != 0 || height < 16 || min_y < -2032 || min_y + height > 2032 {
+            return None;
+        }
+        Some(HeightRange { min_y, height })
+    }
+
+    // vanilla dimensions; datapack dimensions are taken as the overworld
+    pub fn vanilla(dim: &Dimension, data_version: Option<i32>) -> HeightRange {
+        match (dim, data_version) {
+            (Dimension::Nether, _) | (Dimension::End, _) => HeightRange { min_y: 0, height: 256 },
+            (_, Some(v)) if v >= DATA_VERSION_EXTENDED_HEIGHT => HeightRange { min_y: -64, height: 384 },
+            _ => HeightRange { min_y: 0, height: 256 }
+        }
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    // exclusive
+    pub fn max_y(&self) -> i32 {
+        self.min_y + self.height
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn contains(&self, y: i32) -> bool {
+        y >= self.min_y && y < self.max_y()
+    }
+
+    pub fn min_section(&self) -> i32 {
+        self.min_y >> 4
+    }
+
+    pub fn section_count(&self) -> usize {
+        (self.height >> 4) as usize
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.id())
+    }
+}
+
+// every dimension that has been saved at least once
+crate fn list_dimensions(world_path: &Path) -> Result<Vec<Dimension>> {
+    list_dimensions_holding(world_path, &["region"])
+}
+
+// every dimension with any of `folders`, like "region" or "poi", in it.
+// the overworld is always listed
+crate fn list_dimensions_holding(world_path: &Path, folders: &[&str]) -> Result<Vec<Dimension>> {
+    let mut ans = vec![Dimension::Overworld];
+    for dim in [Dimension::Nether, Dimension::End].iter() {
+        if world_path.join(dim.folder()).is_dir() {
+            ans.push(dim.clone());
+        }
+    }
+    let dimensions_path = world_path.join("dimensions");
+    if !dimensions_path.is_dir() {
+        return Ok(ans);
+    }
+    for entry in fs::read_dir(dimensions_path)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Some(namespace) = entry.file_name().to_str() {
+            let mut paths = Vec::new();
+            find_dimension_paths(&entry.path(), "", folders, &mut paths)?;
+            paths.sort();
+            paths.dedup();
+            ans.extend(paths.into_iter().map(|path| Dimension::new_custom(namespace, path)));
+        }
+    }
+    Ok(ans)
+}
+
+// a folder is a dimension if it contains one of `folders`
+fn find_dimension_paths(dir: &Path, prefix: &str, folders: &[&str], ans: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = match entry.file_name().to_str() {
+            Some(name) => name.to_string(),
+            None => continue
+        };
+        let marks = folders.contains(&name.as_str());
+        if marks && !prefix.is_empty() {
+            ans.push(prefix.to_string());
+        } else if !marks {
+            let path = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+            find_dimension_paths(&entry.path(), &path, folders, ans)?;
+        }
+    }
+    Ok(())
+}
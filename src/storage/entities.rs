@@ -0,0 +1,61 @@
+// chunks of the `entities` region folder, since 1.17
+// https://minecraft.gamepedia.com/Entity_format#Entity_chunk_format
+
+use std::io::{Error, ErrorKind, Result};
+use crate::{chunk, entity::Entity};
+use crate::nbt::{self, Compound, Tag, take_field};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct EntityChunk {
+    pub data_version: i32,
+    pub pos: chunk::Pos,
+    // unknown fields of each entity are kept, see `Entity::data`
+    pub entities: Vec<Entity>,
+    other: Compound,
+}
+
+impl EntityChunk {
+    pub fn new(pos: chunk::Pos, data_version: i32) -> EntityChunk {
+        EntityChunk {
+            data_version,
+            pos,
+            entities: Vec::new(),
+            other: Compound::new()
+        }
+    }
+
+    pub fn from_nbt(data: nbt::Data) -> Result<EntityChunk> {
+        let mut map = match data.into_root_tag() {
+            Tag::Compound(map) => map,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid entity chunk root tag"))
+        };
+        let data_version = take_field(&mut map, "DataVersion", Tag::as_int)?;
+        let pos = match take_field(&mut map, "Position", |t| t.as_int_array().cloned())?.as_slice() {
+            &[x, z] => chunk::Pos::from_xz(x, z),
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid entity chunk position"))
+        };
+        let mut entities = Vec::new();
+        for entity in take_field(&mut map, "Entities", |t| t.as_list().cloned())? {
+            match entity {
+                Tag::Compound(entity) => entities.push(Entity::from_nbt(entity)?),
+                _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid entity in entity chunk"))
+            }
+        }
+        Ok(EntityChunk {
+            data_version,
+            pos,
+            entities,
+            other: map
+        })
+    }
+
+    pub fn to_nbt(&self) -> nbt::Data {
+        let mut map = self.other.clone();
+        let (x, z) = self.pos.to_xz();
+        map.insert("DataVersion".to_string(), Tag::Int(self.data_version));
+        map.insert("Position".to_string(), Tag::IntArray(vec![x, z]));
+        let entities = self.entities.iter().map(|entity| Tag::Compound(entity.to_nbt(self.data_version))).collect();
+        map.insert("Entities".to_string(), Tag::List(entities));
+        nbt::Data::new("", Tag::Compound(map))
+    }
+}
@@ -0,0 +1,175 @@
+// iterate over every chunk saved in a region folder, one region file at a
+// time, read plainly or memory mapped.
+// the parallel variant hands whole region files to worker threads, which
+// read them either way, decode chunks and send them back over a bounded channel.
+
+use std::io::Result;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::vec;
+use crate::chunk;
+use crate::nbt;
+use super::mapped::MappedRegion;
+use super::region::Region;
+
+// chunks decoded ahead of the consumer, per worker thread
+const CHANNEL_BOUND_PER_THREAD: usize = 64;
+
+// region origin, reported along with errors that are not tied to one chunk
+fn region_origin(region_x: i32, region_z: i32) -> chunk::Pos {
+    chunk::Pos::from_xz(region_x << 5, region_z << 5)
+}
+
+enum RegionReader {
+    Plain(Region),
+    Mapped(MappedRegion),
+}
+
+impl RegionReader {
+    fn open(path: PathBuf, mapped: bool) -> Result<RegionReader> {
+        if mapped {
+            MappedRegion::open(path).map(RegionReader::Mapped)
+        } else {
+            Region::open(path).map(RegionReader::Plain)
+        }
+    }
+
+    fn chunk_positions(&self) -> Vec<chunk::Pos> {
+        match self {
+            RegionReader::Plain(region) => region.chunk_positions(),
+            RegionReader::Mapped(region) => region.chunk_positions()
+        }
+    }
+
+    fn read_chunk_nbt(&mut self, pos: chunk::Pos) -> Result<Option<nbt::Data>> {
+        match self {
+            RegionReader::Plain(region) => region.read_chunk_nbt(pos),
+            RegionReader::Mapped(region) => region.read_chunk_nbt(pos)
+        }
+    }
+}
+
+// calls `f` for every chunk of the region file, stops if `f` returns false.
+// failing to open the region is reported at the region origin
+fn for_each_region_chunk(path: PathBuf, position: (i32, i32), mapped: bool, mut f: impl FnMut(chunk::Pos, Result<nbt::Data>) -> bool) {
+    let mut region = match RegionReader::open(path, mapped) {
+        Ok(region) => region,
+        Err(e) => {
+            f(region_origin(position.0, position.1), Err(e));
+            return;
+        }
+    };
+    for pos in region.chunk_positions() {
+        let keep_going = match region.read_chunk_nbt(pos) {
+            Ok(Some(data)) => f(pos, Ok(data)),
+            Ok(None) => true,
+            Err(e) => f(pos, Err(e))
+        };
+        if !keep_going {
+            return;
+        }
+    }
+}
+
+// see `McJavaWorld::chunks` and `McJavaWorld::chunks_mapped`
+pub struct ChunkIter {
+    regions: vec::IntoIter<(PathBuf, (i32, i32))>,
+    mapped: bool,
+    current: Option<(RegionReader, vec::IntoIter<chunk::Pos>)>,
+}
+
+impl ChunkIter {
+    crate fn new(regions: Vec<(PathBuf, (i32, i32))>, mapped: bool) -> ChunkIter {
+        ChunkIter {
+            regions: regions.into_iter(),
+            mapped,
+            current: None
+        }
+    }
+}
+
+impl Iterator for ChunkIter {
+    type Item = (chunk::Pos, Result<nbt::Data>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((region, positions)) = &mut self.current {
+                for pos in positions {
+                    match region.read_chunk_nbt(pos) {
+                        Ok(Some(data)) => return Some((pos, Ok(data))),
+                        Ok(None) => continue,
+                        Err(e) => return Some((pos, Err(e)))
+                    }
+                }
+            }
+            self.current = None;
+            let (path, (region_x, region_z)) = self.regions.next()?;
+            match RegionReader::open(path, self.mapped) {
+                Ok(region) => {
+                    let positions = region.chunk_positions().into_iter();
+                    self.current = Some((region, positions));
+                },
+                Err(e) => return Some((region_origin(region_x, region_z), Err(e)))
+            }
+        }
+    }
+}
+
+// see `McJavaWorld::par_map_chunks` and `McJavaWorld::par_map_chunks_mapped`.
+// items come in no particular order; dropping the iterator stops the workers
+// once they try to send their next item.
+pub struct ParChunkIter<T> {
+    receiver: mpsc::Receiver<T>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> ParChunkIter<T> {
+    // panic if threads is zero
+    crate fn new<F>(regions: Vec<(PathBuf, (i32, i32))>, mapped: bool, threads: usize, f: F) -> ParChunkIter<T>
+    where F: Fn(chunk::Pos, Result<nbt::Data>) -> T + Send + Sync + 'static {
+        assert!(threads > 0, "thread count must not be zero");
+        let (sender, receiver) = mpsc::sync_channel(threads * CHANNEL_BOUND_PER_THREAD);
+        let queue = Arc::new(Mutex::new(regions.into_iter()));
+        let f = Arc::new(f);
+        let workers = (0..threads).map(|_| {
+            let (sender, queue, f) = (sender.clone(), Arc::clone(&queue), Arc::clone(&f));
+            thread::spawn(move || loop {
+                let next = queue.lock().unwrap().next();
+                let (path, position) = match next {
+                    Some(region) => region,
+                    None => return
+                };
+                let mut disconnected = false;
+                for_each_region_chunk(path, position, mapped, |pos, data| {
+                    disconnected = sender.send(f(pos, data)).is_err();
+                    !disconnected
+                });
+                if disconnected {
+                    return;
+                }
+            })
+        }).collect();
+        ParChunkIter { receiver, workers }
+    }
+}
+
+impl<T> Iterator for ParChunkIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match self.receiver.recv() {
+            Ok(item) => Some(item),
+            // every worker is done
+            Err(_) => {
+                for worker in self.workers.drain(..) {
+                    // a panic in `f` is passed on to the consumer
+                    if let Err(e) = worker.join() {
+                        std::panic::resume_unwind(e);
+                    }
+                }
+                None
+            }
+        }
+    }
+}
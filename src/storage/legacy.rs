@@ -0,0 +1,271 @@
+// worlds saved before Anvil (1.2): McRegion from Beta 1.3 to 1.1,
+// and the even older Alpha format keeping one file per chunk.
+// both store 16*128*16 chunks of byte block ids under a `Level` compound.
+// https://minecraft.gamepedia.com/Region_file_format#McRegion
+// https://minecraft.gamepedia.com/Java_Edition_Alpha_level_format
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use crate::block::{self, IdSystem, LegacyIdSystem};
+use crate::chunk::{self, Chunk};
+use crate::nbt::{self, Tag};
+use super::region::{self, Region};
+
+crate const LEGACY_HEIGHT: usize = 128;
+
+// index into `Blocks` and `Data` of a legacy chunk
+#[inline]
+crate fn legacy_block_index(x: usize, y: usize, z: usize) -> usize {
+    y + z * LEGACY_HEIGHT + x * LEGACY_HEIGHT * 16
+}
+
+// the `Level` compound of a legacy chunk
+crate fn legacy_level(data: &nbt::Data) -> Result<&nbt::Compound> {
+    data.root_tag().as_compound()
+        .and_then(|root| root.get("Level"))
+        .and_then(Tag::as_compound)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing `Level` compound in legacy chunk"))
+}
+
+// blocks are placed in a chunk of y 0..128 with metas of `LegacyIdSystem`
+crate fn legacy_level_to_chunk(level: &nbt::Compound, buf: &mut Chunk) -> Result<()> {
+    let blocks = level.get("Blocks").and_then(Tag::as_byte_array);
+    let data = level.get("Data").and_then(Tag::as_byte_array);
+    let (blocks, data) = match (blocks, data) {
+        (Some(blocks), Some(data)) if blocks.len() == 16 * LEGACY_HEIGHT * 16 && data.len() == blocks.len() / 2 => (blocks, data),
+        _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid block data in legacy chunk"))
+    };
+    *buf = Chunk::with_sections(0, LEGACY_HEIGHT / 16);
+    for x in 0..16 {
+        for z in 0..16 {
+            for y in 0..LEGACY_HEIGHT {
+                let index = legacy_block_index(x, y, z);
+                let nibble = (data[index >> 1] as u8 >> ((index & 1) * 4)) & 0xF;
+                buf.set_block_meta(x, y as i32, z, LegacyIdSystem::meta_from_legacy(blocks[index] as u8, nibble));
+            }
+        }
+    }
+    Ok(())
+}
+
+// read a block out of a legacy world by loading its whole chunk
+fn read_legacy_block<W: chunk::ReadExact>(world: &W, pos: block::Pos) -> Result<block::Meta> {
+    let (x, y, z) = pos.to_xyz();
+    if y < 0 || y >= LEGACY_HEIGHT as i32 {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Block y out of range: {}", y)));
+    }
+    let mut buf = Chunk::new();
+    world.read_chunk_exact(chunk::Pos::from_xz(x >> 4, z >> 4), &mut buf)?;
+    Ok(buf.get_block_meta((x & 15) as usize, y, (z & 15) as usize))
+}
+
+fn contains_legacy_block<W: chunk::ReadExact>(world: &W, pos: block::Pos) -> Result<bool> {
+    let (x, y, z) = pos.to_xyz();
+    if y < 0 || y >= LEGACY_HEIGHT as i32 {
+        return Ok(false);
+    }
+    world.contains_chunk_exact(chunk::Pos::from_xz(x >> 4, z >> 4))
+}
+
+fn chunk_not_found(pos: chunk::Pos) -> Error {
+    Error::new(ErrorKind::NotFound, format!("Chunk not found: {:?}", pos))
+}
+
+// region files named `r.X.Z.mcr` under `region/`
+pub struct McRegionWorld<P: AsRef<Path>> {
+    path: P,
+    ids: LegacyIdSystem,
+}
+
+impl<P: AsRef<Path>> McRegionWorld<P> {
+    pub fn new(path: P) -> McRegionWorld<P> {
+        McRegionWorld {
+            path,
+            ids: LegacyIdSystem::new()
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_ref()
+    }
+
+    pub fn region_path(&self, region_x: i32, region_z: i32) -> PathBuf {
+        self.path.as_ref().join("region").join(format!("r.{}.{}.mcr", region_x, region_z))
+    }
+
+    pub fn region_positions(&self) -> Result<Vec<(i32, i32)>> {
+        let dir = self.path.as_ref().join("region");
+        let mut ans = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file_name = entry?.file_name();
+            if let Some(pos) = file_name.to_str().and_then(|name| region::parse_region_file_name(name, "mcr")) {
+                ans.push(pos);
+            }
+        }
+        ans.sort();
+        Ok(ans)
+    }
+
+    pub fn open_region(&self, region_x: i32, region_z: i32) -> Result<Region> {
+        Region::open(self.region_path(region_x, region_z))
+    }
+
+    pub fn read_chunk_nbt(&self, pos: chunk::Pos) -> Result<Option<nbt::Data>> {
+        let (region_x, region_z) = region::region_xz(pos);
+        match self.open_region(region_x, region_z) {
+            Ok(mut region) => region.read_chunk_nbt(pos),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+impl<P: AsRef<Path>> chunk::ReadExact for McRegionWorld<P> {
+    fn read_chunk_exact(&self, pos: chunk::Pos, buf: &mut Chunk) -> Result<()> {
+        match self.read_chunk_nbt(pos)? {
+            Some(data) => legacy_level_to_chunk(legacy_level(&data)?, buf),
+            None => Err(chunk_not_found(pos))
+        }
+    }
+
+    fn contains_chunk_exact(&self, pos: chunk::Pos) -> Result<bool> {
+        let (region_x, region_z) = region::region_xz(pos);
+        match self.open_region(region_x, region_z) {
+            Ok(region) => Ok(region.contains_chunk(pos)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+impl<P: AsRef<Path>> block::ReadExact for McRegionWorld<P> {
+    fn read_block_exact(&self, pos: block::Pos) -> Result<block::Meta> {
+        read_legacy_block(self, pos)
+    }
+
+    fn contain_block_exact(&self, pos: block::Pos) -> Result<bool> {
+        contains_legacy_block(self, pos)
+    }
+}
+
+impl<P: AsRef<Path>> block::IdOperate for McRegionWorld<P> {
+    fn block_id_system(&self) -> &dyn IdSystem {
+        &self.ids
+    }
+}
+
+// chunks as gzipped files under `<x mod 64>/<z mod 64>/c.X.Z.dat`, numbers in base 36
+pub struct AlphaWorld<P: AsRef<Path>> {
+    path: P,
+    ids: LegacyIdSystem,
+}
+
+impl<P: AsRef<Path>> AlphaWorld<P> {
+    pub fn new(path: P) -> AlphaWorld<P> {
+        AlphaWorld {
+            path,
+            ids: LegacyIdSystem::new()
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_ref()
+    }
+
+    pub fn chunk_path(&self, pos: chunk::Pos) -> PathBuf {
+        let (x, z) = pos.to_xz();
+        self.path.as_ref()
+            .join(to_base36(x & 63))
+            .join(to_base36(z & 63))
+            .join(format!("c.{}.{}.dat", to_base36(x), to_base36(z)))
+    }
+
+    pub fn chunk_positions(&self) -> Result<Vec<chunk::Pos>> {
+        let mut ans = Vec::new();
+        for dir_x in fs::read_dir(self.path.as_ref())? {
+            let dir_x = dir_x?;
+            if !dir_x.file_type()?.is_dir() {
+                continue;
+            }
+            for dir_z in fs::read_dir(dir_x.path())? {
+                let dir_z = dir_z?;
+                if !dir_z.file_type()?.is_dir() {
+                    continue;
+                }
+                for file in fs::read_dir(dir_z.path())? {
+                    if let Some(pos) = file?.file_name().to_str().and_then(parse_alpha_file_name) {
+                        ans.push(pos);
+                    }
+                }
+            }
+        }
+        ans.sort_by_key(|pos| pos.to_xz());
+        Ok(ans)
+    }
+
+    pub fn read_chunk_nbt(&self, pos: chunk::Pos) -> Result<Option<nbt::Data>> {
+        match super::read_gzip_nbt(self.chunk_path(pos)) {
+            Ok(data) => Ok(Some(data)),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+        }
+    }
+}
+
+impl<P: AsRef<Path>> chunk::ReadExact for AlphaWorld<P> {
+    fn read_chunk_exact(&self, pos: chunk::Pos, buf: &mut Chunk) -> Result<()> {
+        match self.read_chunk_nbt(pos)? {
+            Some(data) => legacy_level_to_chunk(legacy_level(&data)?, buf),
+            None => Err(chunk_not_found(pos))
+        }
+    }
+
+    fn contains_chunk_exact(&self, pos: chunk::Pos) -> Result<bool> {
+        Ok(self.chunk_path(pos).is_file())
+    }
+}
+
+impl<P: AsRef<Path>> block::ReadExact for AlphaWorld<P> {
+    fn read_block_exact(&self, pos: block::Pos) -> Result<block::Meta> {
+        read_legacy_block(self, pos)
+    }
+
+    fn contain_block_exact(&self, pos: block::Pos) -> Result<bool> {
+        contains_legacy_block(self, pos)
+    }
+}
+
+impl<P: AsRef<Path>> block::IdOperate for AlphaWorld<P> {
+    fn block_id_system(&self) -> &dyn IdSystem {
+        &self.ids
+    }
+}
+
+// like java's `Integer.toString(v, 36)`
+fn to_base36(v: i32) -> String {
+    let digits = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut n = (v as i64).abs();
+    let mut buf = Vec::new();
+    loop {
+        buf.push(digits[(n % 36) as usize]);
+        n /= 36;
+        if n == 0 {
+            break;
+        }
+    }
+    if v < 0 {
+        buf.push(b'-');
+    }
+    buf.reverse();
+    String::from_utf8(buf).unwrap()
+}
+
+fn parse_alpha_file_name(name: &str) -> Option<chunk::Pos> {
+    let mut parts = name.split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("c"), Some(x), Some(z), Some("dat"), None) =>
+            Some(chunk::Pos::from_xz(i32::from_str_radix(x, 36).ok()?, i32::from_str_radix(z, 36).ok()?)),
+        _ => None
+    }
+}
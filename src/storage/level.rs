@@ -0,0 +1,391 @@
+// level.dat, the global information of a world
+// https://minecraft.gamepedia.com/Level_format#level.dat_format
+
+use std::collections::{HashMap, HashSet};
+use std::io::{Error, ErrorKind, Result};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::nbt::{self, Compound, Tag, take_field, take_field_opt};
+use crate::pos::BlockPos;
+use super::{Dimension, HeightRange};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum GameType {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameType {
+    pub fn from_id(id: i32) -> Option<GameType> {
+        use self::GameType::*;
+        match id {
+            0 => Some(Survival),
+            1 => Some(Creative),
+            2 => Some(Adventure),
+            3 => Some(Spectator),
+            _ => None
+        }
+    }
+
+    pub fn to_id(&self) -> i32 {
+        *self as i32
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_id(id: i8) -> Option<Difficulty> {
+        use self::Difficulty::*;
+        match id {
+            0 => Some(Peaceful),
+            1 => Some(Easy),
+            2 => Some(Normal),
+            3 => Some(Hard),
+            _ => None
+        }
+    }
+
+    pub fn to_id(&self) -> i8 {
+        *self as i8
+    }
+}
+
+// the `Version` compound, absent before 1.9
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct VersionInfo {
+    pub id: i32,
+    pub name: String,
+    pub snapshot: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct WorldBorder {
+    pub center_x: f64,
+    pub center_z: f64,
+    pub size: f64,
+    pub size_lerp_target: f64,
+    pub size_lerp_time: i64,
+    pub safe_zone: f64,
+    pub damage_per_block: f64,
+    pub warning_blocks: f64,
+    pub warning_time: f64,
+}
+
+impl Default for WorldBorder {
+    // vanilla values for a newly created world
+    fn default() -> WorldBorder {
+        WorldBorder {
+            center_x: 0.0,
+            center_z: 0.0,
+            size: 60_000_000.0,
+            size_lerp_target: 60_000_000.0,
+            size_lerp_time: 0,
+            safe_zone: 5.0,
+            damage_per_block: 0.2,
+            warning_blocks: 5.0,
+            warning_time: 15.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Debug)]
+pub struct Weather {
+    pub raining: bool,
+    pub rain_time: i32,
+    pub thundering: bool,
+    pub thunder_time: i32,
+    pub clear_weather_time: i32,
+}
+
+// world generator of worlds before 1.16, which moved it into `WorldGenSettings`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Generator {
+    // `default`, `flat`, `largeBiomes`, `amplified`, `buffet` or `customized`
+    pub name: String,
+    pub version: i32,
+    // a string before 1.13 and a compound since, left as is
+    pub options: Option<Tag>,
+}
+
+impl Generator {
+    pub fn new(name: impl Into<String>) -> Generator {
+        let name = name.into();
+        Generator {
+            // only the default generator has ever been bumped
+            version: if name == "default" { 1 } else { 0 },
+            name,
+            options: None
+        }
+    }
+}
+
+// keys that old or hand made level.dat files may lack. they are written back
+// only if they were read, or if their value is no longer the one read
+const OPTIONAL_KEYS: &[&str] = &[
+    "hardcore", "Difficulty", "GameRules", "BorderCenterX", "BorderCenterZ", "BorderSize", "BorderSizeLerpTarget",
+    "BorderSizeLerpTime", "BorderSafeZone", "BorderDamagePerBlock", "BorderWarningBlocks", "BorderWarningTime",
+    "raining", "rainTime", "thundering", "thunderTime", "clearWeatherTime", "DayTime", "LastPlayed",
+];
+
+// the `Data` compound of level.dat.
+// keys not modelled here are kept as they are and written back on save.
+#[derive(Clone, PartialEq, Debug)]
+pub struct LevelData {
+    pub level_name: String,
+    pub seed: i64,
+    pub spawn: BlockPos,
+    pub game_type: GameType,
+    // `None` for worlds made by 1.16 or later
+    pub generator: Option<Generator>,
+    pub hardcore: bool,
+    pub difficulty: Difficulty,
+    pub data_version: Option<i32>,
+    pub version: Option<VersionInfo>,
+    pub game_rules: HashMap<String, String>,
+    pub world_border: WorldBorder,
+    pub weather: Weather,
+    // total ticks elapsed
+    pub time: i64,
+    // time of day, `time` modulo 24000 unless changed by commands
+    pub day_time: i64,
+    // unix time in milliseconds
+    pub last_played: i64,
+    root_name: String,
+    // which of `OPTIONAL_KEYS` were read
+    present: HashSet<&'static str>,
+    other_data: Compound,
+    other_root: Compound,
+}
+
+impl LevelData {
+    // a world not yet loaded by the game, which places the spawn point
+    // and fills in the remaining keys on first load
+    pub fn new(level_name: impl Into<String>, seed: i64) -> LevelData {
+        let mut other_data = Compound::new();
+        other_data.insert("version".to_string(), Tag::Int(super::convert::VERSION_ANVIL));
+        other_data.insert("initialized".to_string(), Tag::Byte(0));
+        other_data.insert("MapFeatures".to_string(), Tag::Byte(1));
+        other_data.insert("allowCommands".to_string(), Tag::Byte(0));
+        let last_played = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        LevelData {
+            level_name: level_name.into(),
+            seed,
+            spawn: BlockPos::from_xyz(0, 64, 0),
+            game_type: GameType::Survival,
+            generator: Some(Generator::new("default")),
+            hardcore: false,
+            difficulty: Difficulty::Normal,
+            data_version: None,
+            version: None,
+            game_rules: HashMap::new(),
+            world_border: WorldBorder::default(),
+            weather: Weather::default(),
+            time: 0,
+            day_time: 0,
+            last_played,
+            root_name: String::new(),
+            present: OPTIONAL_KEYS.iter().cloned().collect(),
+            other_data,
+            other_root: Compound::new(),
+        }
+    }
+
+    pub fn from_nbt(data: nbt::Data) -> Result<LevelData> {
+        let root_name = data.root_name().to_string();
+        let mut other_root = match data.into_root_tag() {
+            Tag::Compound(map) => map,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid level.dat root tag"))
+        };
+        let mut map = match other_root.remove("Data") {
+            Some(Tag::Compound(map)) => map,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Missing `Data` compound in level.dat"))
+        };
+        let present = OPTIONAL_KEYS.iter().cloned().filter(|&key| map.contains_key(key)).collect();
+        let level_name = take_field(&mut map, "LevelName", |t| t.as_str().map(String::from))?;
+        // 1.16+ moved the seed into `WorldGenSettings`, which is kept as an unknown key
+        let seed = match map.get("WorldGenSettings").and_then(|t| t.as_compound()) {
+            Some(settings) => settings.get("seed").and_then(Tag::as_long)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing NBT field: seed"))?,
+            None => take_field(&mut map, "RandomSeed", Tag::as_long)?
+        };
+        let spawn = BlockPos::from_xyz(
+            take_field(&mut map, "SpawnX", Tag::as_int)?,
+            take_field(&mut map, "SpawnY", Tag::as_int)?,
+            take_field(&mut map, "SpawnZ", Tag::as_int)?,
+        );
+        let game_type = take_field(&mut map, "GameType", Tag::as_int)?;
+        let game_type = GameType::from_id(game_type).ok_or_else(||
+            Error::new(ErrorKind::InvalidData, format!("Invalid game type: {}", game_type)))?;
+        let generator = match take_field_opt(&mut map, "generatorName", |t| t.as_str().map(String::from))? {
+            Some(name) => Some(Generator {
+                name,
+                version: take_field_opt(&mut map, "generatorVersion", Tag::as_int)?.unwrap_or(0),
+                options: map.remove("generatorOptions"),
+            }),
+            None => None
+        };
+        let difficulty = take_field_opt(&mut map, "Difficulty", Tag::as_byte)?.unwrap_or(2);
+        let difficulty = Difficulty::from_id(difficulty).ok_or_else(||
+            Error::new(ErrorKind::InvalidData, format!("Invalid difficulty: {}", difficulty)))?;
+        let version = match take_field_opt(&mut map, "Version", |t| t.as_compound().cloned())? {
+            Some(mut version) => Some(VersionInfo {
+                id: take_field(&mut version, "Id", Tag::as_int)?,
+                name: take_field(&mut version, "Name", |t| t.as_str().map(String::from))?,
+                snapshot: take_field(&mut version, "Snapshot", Tag::as_bool)?,
+            }),
+            None => None
+        };
+        let mut game_rules = HashMap::new();
+        if let Some(rules) = take_field_opt(&mut map, "GameRules", |t| t.as_compound().cloned())? {
+            for (name, value) in rules {
+                match value {
+                    Tag::String(value) => game_rules.insert(name, value),
+                    _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid game rule: {}", name)))
+                };
+            }
+        }
+        let default_border = WorldBorder::default();
+        let world_border = WorldBorder {
+            center_x: take_field_opt(&mut map, "BorderCenterX", Tag::as_double)?.unwrap_or(default_border.center_x),
+            center_z: take_field_opt(&mut map, "BorderCenterZ", Tag::as_double)?.unwrap_or(default_border.center_z),
+            size: take_field_opt(&mut map, "BorderSize", Tag::as_double)?.unwrap_or(default_border.size),
+            size_lerp_target: take_field_opt(&mut map, "BorderSizeLerpTarget", Tag::as_double)?.unwrap_or(default_border.size_lerp_target),
+            size_lerp_time: take_field_opt(&mut map, "BorderSizeLerpTime", Tag::as_long)?.unwrap_or(default_border.size_lerp_time),
+            safe_zone: take_field_opt(&mut map, "BorderSafeZone", Tag::as_double)?.unwrap_or(default_border.safe_zone),
+            damage_per_block: take_field_opt(&mut map, "BorderDamagePerBlock", Tag::as_double)?.unwrap_or(default_border.damage_per_block),
+            warning_blocks: take_field_opt(&mut map, "BorderWarningBlocks", Tag::as_double)?.unwrap_or(default_border.warning_blocks),
+            warning_time: take_field_opt(&mut map, "BorderWarningTime", Tag::as_double)?.unwrap_or(default_border.warning_time),
+        };
+        let weather = Weather {
+            raining: take_field_opt(&mut map, "raining", Tag::as_bool)?.unwrap_or(false),
+            rain_time: take_field_opt(&mut map, "rainTime", Tag::as_int)?.unwrap_or(0),
+            thundering: take_field_opt(&mut map, "thundering", Tag::as_bool)?.unwrap_or(false),
+            thunder_time: take_field_opt(&mut map, "thunderTime", Tag::as_int)?.unwrap_or(0),
+            clear_weather_time: take_field_opt(&mut map, "clearWeatherTime", Tag::as_int)?.unwrap_or(0),
+        };
+        let time = take_field(&mut map, "Time", Tag::as_long)?;
+        Ok(LevelData {
+            level_name,
+            seed,
+            spawn,
+            game_type,
+            generator,
+            hardcore: take_field_opt(&mut map, "hardcore", Tag::as_bool)?.unwrap_or(false),
+            difficulty,
+            data_version: take_field_opt(&mut map, "DataVersion", Tag::as_int)?,
+            version,
+            game_rules,
+            world_border,
+            weather,
+            time,
+            day_time: take_field_opt(&mut map, "DayTime", Tag::as_long)?.unwrap_or(time),
+            last_played: take_field_opt(&mut map, "LastPlayed", Tag::as_long)?.unwrap_or(0),
+            root_name,
+            present,
+            other_data: map,
+            other_root,
+        })
+    }
+
+    // the height of a dimension as `WorldGenSettings` defines it since 1.16,
+    // falling back to vanilla heights for types only a datapack knows
+    pub fn height_range(&self, dim: &Dimension) -> HeightRange {
+        let dimension_type = self.other_data.get("WorldGenSettings")
+            .and_then(Tag::as_compound)
+            .and_then(|settings| settings.get("dimensions"))
+            .and_then(Tag::as_compound)
+            .and_then(|dimensions| dimensions.get(&dim.id()))
+            .and_then(Tag::as_compound)
+            .and_then(|dimension| dimension.get("type"));
+        match dimension_type {
+            Some(Tag::Compound(dimension_type)) => {
+                let min_y = dimension_type.get("min_y").and_then(Tag::as_int);
+                let height = dimension_type.get("height").and_then(Tag::as_int);
+                min_y.and_then(|min_y| HeightRange::new(min_y, height?))
+                    .unwrap_or_else(|| HeightRange::vanilla(dim, self.data_version))
+            },
+            Some(Tag::String(id)) => match Dimension::from_id(id) {
+                Some(vanilla) => HeightRange::vanilla(&vanilla, self.data_version),
+                None => HeightRange::vanilla(dim, self.data_version)
+            },
+            _ => HeightRange::vanilla(dim, self.data_version)
+        }
+    }
+
+    pub fn to_nbt(&self) -> nbt::Data {
+        let mut map = self.other_data.clone();
+        map.insert("LevelName".to_string(), Tag::String(self.level_name.clone()));
+        match map.get_mut("WorldGenSettings").and_then(Tag::as_compound_mut) {
+            Some(settings) => { settings.insert("seed".to_string(), Tag::Long(self.seed)); },
+            None => { map.insert("RandomSeed".to_string(), Tag::Long(self.seed)); }
+        }
+        let (spawn_x, spawn_y, spawn_z) = self.spawn.to_xyz();
+        map.insert("SpawnX".to_string(), Tag::Int(spawn_x));
+        map.insert("SpawnY".to_string(), Tag::Int(spawn_y));
+        map.insert("SpawnZ".to_string(), Tag::Int(spawn_z));
+        map.insert("GameType".to_string(), Tag::Int(self.game_type.to_id()));
+        if let Some(generator) = &self.generator {
+            map.insert("generatorName".to_string(), Tag::String(generator.name.clone()));
+            map.insert("generatorVersion".to_string(), Tag::Int(generator.version));
+            if let Some(options) = &generator.options {
+                map.insert("generatorOptions".to_string(), options.clone());
+            }
+        }
+        if let Some(data_version) = self.data_version {
+            map.insert("DataVersion".to_string(), Tag::Int(data_version));
+        }
+        if let Some(version) = &self.version {
+            let mut version_map = Compound::new();
+            version_map.insert("Id".to_string(), Tag::Int(version.id));
+            version_map.insert("Name".to_string(), Tag::String(version.name.clone()));
+            version_map.insert("Snapshot".to_string(), Tag::Byte(version.snapshot as i8));
+            map.insert("Version".to_string(), Tag::Compound(version_map));
+        }
+        map.insert("Time".to_string(), Tag::Long(self.time));
+        // as read when absent
+        let default_border = WorldBorder::default();
+        let default_weather = Weather::default();
+        let mut put = |name: &'static str, tag: Tag, default: Tag| {
+            if self.present.contains(name) || tag != default {
+                map.insert(name.to_string(), tag);
+            }
+        };
+        put("hardcore", Tag::Byte(self.hardcore as i8), Tag::Byte(0));
+        put("Difficulty", Tag::Byte(self.difficulty.to_id()), Tag::Byte(Difficulty::Normal.to_id()));
+        let game_rules = self.game_rules.iter()
+            .map(|(name, value)| (name.clone(), Tag::String(value.clone())))
+            .collect();
+        put("GameRules", Tag::Compound(game_rules), Tag::Compound(Compound::new()));
+        let (border, default) = (&self.world_border, &default_border);
+        put("BorderCenterX", Tag::Double(border.center_x), Tag::Double(default.center_x));
+        put("BorderCenterZ", Tag::Double(border.center_z), Tag::Double(default.center_z));
+        put("BorderSize", Tag::Double(border.size), Tag::Double(default.size));
+        put("BorderSizeLerpTarget", Tag::Double(border.size_lerp_target), Tag::Double(default.size_lerp_target));
+        put("BorderSizeLerpTime", Tag::Long(border.size_lerp_time), Tag::Long(default.size_lerp_time));
+        put("BorderSafeZone", Tag::Double(border.safe_zone), Tag::Double(default.safe_zone));
+        put("BorderDamagePerBlock", Tag::Double(border.damage_per_block), Tag::Double(default.damage_per_block));
+        put("BorderWarningBlocks", Tag::Double(border.warning_blocks), Tag::Double(default.warning_blocks));
+        put("BorderWarningTime", Tag::Double(border.warning_time), Tag::Double(default.warning_time));
+        let (weather, default) = (&self.weather, &default_weather);
+        put("raining", Tag::Byte(weather.raining as i8), Tag::Byte(default.raining as i8));
+        put("rainTime", Tag::Int(weather.rain_time), Tag::Int(default.rain_time));
+        put("thundering", Tag::Byte(weather.thundering as i8), Tag::Byte(default.thundering as i8));
+        put("thunderTime", Tag::Int(weather.thunder_time), Tag::Int(default.thunder_time));
+        put("clearWeatherTime", Tag::Int(weather.clear_weather_time), Tag::Int(default.clear_weather_time));
+        put("DayTime", Tag::Long(self.day_time), Tag::Long(self.time));
+        put("LastPlayed", Tag::Long(self.last_played), Tag::Long(0));
+        let mut root = self.other_root.clone();
+        root.insert("Data".to_string(), Tag::Compound(map));
+        nbt::Data::new(self.root_name.clone(), Tag::Compound(root))
+    }
+}
@@ -0,0 +1,116 @@
+// read-only region files mapped into memory, for scanning whole worlds.
+// chunk payloads are sliced out of the map and decompressed straight from there,
+// with no seeking or reading into intermediate buffers.
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use byteorder::{BigEndian, ByteOrder};
+use memmap2::Mmap;
+use crate::chunk;
+use crate::nbt;
+use super::region::{self, SECTOR_SIZE, COMPRESSION_EXTERNAL};
+
+pub struct MappedRegion {
+    // `None` for empty region files
+    map: Option<Mmap>,
+    path: PathBuf,
+    region_x: i32,
+    region_z: i32,
+}
+
+impl MappedRegion {
+    // the file must not be written to while it is mapped, by this process or
+    // any other (e.g. a running server): reads may see torn chunks, and if the
+    // file is truncated they fault. `McJavaWorld::chunks` reads plainly instead
+    pub fn open(path: impl AsRef<Path>) -> Result<MappedRegion> {
+        let path = path.as_ref();
+        let (region_x, region_z) = region::region_file_position(path)?;
+        let file = fs::File::open(path)?;
+        // a zero sized file cannot be mapped; short ones are left by crashed servers
+        let map = if file.metadata()?.len() >= 2 * SECTOR_SIZE as u64 {
+            // SAFETY: the map is only read, and only within its length. it stays
+            // valid as long as nothing truncates the file, which the caller makes
+            // sure of as said above, e.g. by scanning while the game is stopped
+            Some(unsafe { Mmap::map(&file)? })
+        } else {
+            None
+        };
+        Ok(MappedRegion {
+            map,
+            path: path.to_path_buf(),
+            region_x,
+            region_z
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.region_x, self.region_z)
+    }
+
+    fn location(&self, index: usize) -> u32 {
+        match &self.map {
+            Some(map) => BigEndian::read_u32(&map[index * 4..]),
+            None => 0
+        }
+    }
+
+    pub fn contains_chunk(&self, pos: chunk::Pos) -> bool {
+        self.location(region::chunk_index(pos)) != 0
+    }
+
+    // see `Region::chunk_timestamp`
+    pub fn chunk_timestamp(&self, pos: chunk::Pos) -> Option<u32> {
+        let (index, map) = (region::chunk_index(pos), self.map.as_ref()?);
+        if self.location(index) == 0 {
+            return None;
+        }
+        Some(BigEndian::read_u32(&map[SECTOR_SIZE + index * 4..]))
+    }
+
+    pub fn chunk_positions(&self) -> Vec<chunk::Pos> {
+        (0..1024).filter(|&i| self.location(i) != 0)
+            .map(|i| region::chunk_pos(self.region_x, self.region_z, i))
+            .collect()
+    }
+
+    // compression type and the compressed payload, borrowed from the map.
+    // the payload is empty and the compression has `128` set if the chunk
+    // is kept in an external `.mcc` file, see `read_chunk_nbt`
+    pub fn chunk_payload(&self, pos: chunk::Pos) -> Result<Option<(u8, &[u8])>> {
+        let location = self.location(region::chunk_index(pos));
+        let map = match &self.map {
+            Some(map) if location != 0 => map,
+            _ => return Ok(None)
+        };
+        let (offset, count) = ((location >> 8) as usize * SECTOR_SIZE, (location & 0xFF) as usize);
+        let invalid = || Error::new(ErrorKind::InvalidData, format!("Invalid chunk location {:#x} in {}", location, self.path.display()));
+        if offset + 5 > map.len() {
+            return Err(invalid());
+        }
+        let len = BigEndian::read_u32(&map[offset..]) as usize;
+        if len == 0 || len + 4 > count * SECTOR_SIZE || offset + 4 + len > map.len() {
+            return Err(invalid());
+        }
+        let compression = map[offset + 4];
+        if compression & COMPRESSION_EXTERNAL != 0 {
+            return Ok(Some((compression, &[])));
+        }
+        Ok(Some((compression, &map[offset + 5..offset + 4 + len])))
+    }
+
+    pub fn read_chunk_nbt(&self, pos: chunk::Pos) -> Result<Option<nbt::Data>> {
+        match self.chunk_payload(pos)? {
+            Some((compression, _)) if compression & COMPRESSION_EXTERNAL != 0 => {
+                let data = fs::read(region::external_path(&self.path, pos))?;
+                region::decode_chunk(compression & !COMPRESSION_EXTERNAL, &data).map(Some)
+            },
+            Some((compression, data)) => region::decode_chunk(compression, data).map(Some),
+            None => Ok(None)
+        }
+    }
+}
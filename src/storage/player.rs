@@ -0,0 +1,428 @@
+// players saved as `playerdata/<uuid>.dat`, gzipped like level.dat
+// https://minecraft.gamepedia.com/Player.dat_format
+
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use crate::nbt::{self, Compound, Tag, take_compound_list, take_field, take_field_opt};
+use crate::pos::BlockPos;
+use super::{Dimension, GameType};
+
+// player uuids, as used in file names
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Uuid(u128);
+
+impl Uuid {
+    pub fn from_u128(value: u128) -> Uuid {
+        Uuid(value)
+    }
+
+    pub fn to_u128(&self) -> u128 {
+        self.0
+    }
+
+    // `UUIDMost` and `UUIDLeast` of entities before 1.16
+    pub fn from_most_least(most: i64, least: i64) -> Uuid {
+        Uuid(((most as u64 as u128) << 64) | least as u64 as u128)
+    }
+
+    pub fn to_most_least(&self) -> (i64, i64) {
+        ((self.0 >> 64) as i64, self.0 as i64)
+    }
+
+    // the hyphenated form, like `f3afa935-6d27-3248-8aab-796f9daf1797`
+    pub fn parse(s: &str) -> Option<Uuid> {
+        let groups: Vec<&str> = s.split('-').collect();
+        let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+        if lengths != [8, 4, 4, 4, 12] || !groups.iter().all(|g| g.chars().all(|c| c.is_ascii_hexdigit())) {
+            return None;
+        }
+        u128::from_str_radix(&groups.concat(), 16).ok().map(Uuid)
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = format!("{:032x}", self.0);
+        write!(f, "{}-{}-{}-{}-{}", &s[..8], &s[8..12], &s[12..16], &s[16..20], &s[20..])
+    }
+}
+
+// first DataVersion with effects as `active_effects` and string ids (23w31a, 1.20.2)
+const DATA_VERSION_EFFECT_IDS: i32 = 3567;
+// first DataVersion with item `components` and an int `count` (24w09a, 1.20.5)
+const DATA_VERSION_ITEM_COMPONENTS: i32 = 3819;
+// first DataVersion with `SpawnDimension` and `SpawnAngle` (1.16)
+const DATA_VERSION_SPAWN_DIMENSION: i32 = 2566;
+// first DataVersion with the spawn in a `respawn` compound (1.21.5)
+const DATA_VERSION_RESPAWN_COMPOUND: i32 = 4325;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ItemStack {
+    pub id: String,
+    // a byte before 1.20.5
+    pub count: i32,
+    // absent for items outside of inventories
+    pub slot: Option<i8>,
+    // damage, enchantments, custom names and so on, before 1.20.5
+    pub tag: Option<Compound>,
+    // the same since 1.20.5, in another layout; neither is converted into the other
+    pub components: Option<Compound>,
+    other: Compound,
+}
+
+impl ItemStack {
+    pub fn new(id: impl Into<String>, count: i32) -> ItemStack {
+        ItemStack {
+            id: id.into(),
+            count,
+            slot: None,
+            tag: None,
+            components: None,
+            other: Compound::new()
+        }
+    }
+
+    // either layout, whatever the DataVersion
+    pub fn from_nbt(mut map: Compound) -> Result<ItemStack> {
+        let count = match take_field_opt(&mut map, "count", Tag::as_int)? {
+            Some(count) => count,
+            None if map.contains_key("components") || !map.contains_key("Count") => 1,
+            None => take_field(&mut map, "Count", Tag::as_byte)? as i32
+        };
+        Ok(ItemStack {
+            id: take_field(&mut map, "id", |t| t.as_str().map(String::from))?,
+            count,
+            slot: take_field_opt(&mut map, "Slot", Tag::as_byte)?,
+            tag: take_field_opt(&mut map, "tag", |t| t.as_compound().cloned())?,
+            components: take_field_opt(&mut map, "components", |t| t.as_compound().cloned())?,
+            other: map
+        })
+    }
+
+    // the count is written as the DataVersion stores it
+    pub fn to_nbt(&self, data_version: i32) -> Compound {
+        let mut map = self.other.clone();
+        map.insert("id".to_string(), Tag::String(self.id.clone()));
+        if data_version >= DATA_VERSION_ITEM_COMPONENTS {
+            map.insert("count".to_string(), Tag::Int(self.count));
+        } else {
+            map.insert("Count".to_string(), Tag::Byte(self.count as i8));
+        }
+        if let Some(slot) = self.slot {
+            map.insert("Slot".to_string(), Tag::Byte(slot));
+        }
+        if let Some(tag) = &self.tag {
+            map.insert("tag".to_string(), Tag::Compound(tag.clone()));
+        }
+        if let Some(components) = &self.components {
+            map.insert("components".to_string(), Tag::Compound(components.clone()));
+        }
+        map
+    }
+}
+
+// effects by their numbers before 1.20.2
+static LEGACY_EFFECTS: &[&str] = &[
+    "speed", "slowness", "haste", "mining_fatigue", "strength", "instant_health",
+    "instant_damage", "jump_boost", "nausea", "regeneration", "resistance", "fire_resistance",
+    "water_breathing", "invisibility", "blindness", "night_vision", "hunger", "weakness",
+    "poison", "wither", "health_boost", "absorption", "saturation", "glowing",
+    "levitation", "luck", "unluck", "slow_falling", "conduit_power", "dolphins_grace",
+    "bad_omen", "hero_of_the_village", "darkness",
+];
+
+// the id of an effect number, starting from 1 for speed
+pub fn legacy_effect_id(number: i8) -> Option<String> {
+    if number < 1 {
+        return None;
+    }
+    LEGACY_EFFECTS.get(number as usize - 1).map(|name| format!("minecraft:{}", name))
+}
+
+pub fn legacy_effect_number(id: &str) -> Option<i8> {
+    let name = id.trim_start_matches("minecraft:");
+    LEGACY_EFFECTS.iter().position(|&n| n == name).map(|i| i as i8 + 1)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct StatusEffect {
+    // like "minecraft:speed"; a number before 1.20.2
+    pub id: String,
+    // zero for level I
+    pub amplifier: i8,
+    // in ticks
+    pub duration: i32,
+    pub ambient: bool,
+    pub show_particles: bool,
+    other: Compound,
+}
+
+impl StatusEffect {
+    pub fn new(id: impl Into<String>, amplifier: i8, duration: i32) -> StatusEffect {
+        StatusEffect {
+            id: id.into(),
+            amplifier,
+            duration,
+            ambient: false,
+            show_particles: true,
+            other: Compound::new()
+        }
+    }
+
+    // either layout, whatever the DataVersion
+    pub fn from_nbt(mut map: Compound) -> Result<StatusEffect> {
+        if !map.contains_key("id") {
+            let number = take_field(&mut map, "Id", Tag::as_byte)?;
+            let id = legacy_effect_id(number)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Unknown effect number: {}", number)))?;
+            return Ok(StatusEffect {
+                id,
+                amplifier: take_field_opt(&mut map, "Amplifier", Tag::as_byte)?.unwrap_or(0),
+                duration: take_field_opt(&mut map, "Duration", Tag::as_int)?.unwrap_or(0),
+                ambient: take_field_opt(&mut map, "Ambient", Tag::as_bool)?.unwrap_or(false),
+                show_particles: take_field_opt(&mut map, "ShowParticles", Tag::as_bool)?.unwrap_or(true),
+                other: map
+            });
+        }
+        Ok(StatusEffect {
+            id: take_field(&mut map, "id", |t| t.as_str().map(String::from))?,
+            amplifier: take_field_opt(&mut map, "amplifier", Tag::as_byte)?.unwrap_or(0),
+            duration: take_field_opt(&mut map, "duration", Tag::as_int)?.unwrap_or(0),
+            ambient: take_field_opt(&mut map, "ambient", Tag::as_bool)?.unwrap_or(false),
+            show_particles: take_field_opt(&mut map, "show_particles", Tag::as_bool)?.unwrap_or(true),
+            other: map
+        })
+    }
+
+    // in the layout of the DataVersion; effects without a number can't be
+    // written before 1.20.2
+    pub fn to_nbt(&self, data_version: i32) -> Result<Compound> {
+        let mut map = self.other.clone();
+        if data_version >= DATA_VERSION_EFFECT_IDS {
+            map.insert("id".to_string(), Tag::String(self.id.clone()));
+            map.insert("amplifier".to_string(), Tag::Byte(self.amplifier));
+            map.insert("duration".to_string(), Tag::Int(self.duration));
+            map.insert("ambient".to_string(), Tag::Byte(self.ambient as i8));
+            map.insert("show_particles".to_string(), Tag::Byte(self.show_particles as i8));
+            return Ok(map);
+        }
+        let number = legacy_effect_number(&self.id)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Effect has no number: {}", self.id)))?;
+        map.insert("Id".to_string(), Tag::Byte(number));
+        map.insert("Amplifier".to_string(), Tag::Byte(self.amplifier));
+        map.insert("Duration".to_string(), Tag::Int(self.duration));
+        map.insert("Ambient".to_string(), Tag::Byte(self.ambient as i8));
+        map.insert("ShowParticles".to_string(), Tag::Byte(self.show_particles as i8));
+        Ok(map)
+    }
+}
+
+// a bed or respawn anchor, or a point set by /spawnpoint
+#[derive(Clone, PartialEq, Debug)]
+pub struct RespawnPoint {
+    pub pos: BlockPos,
+    // always the overworld before 1.16
+    pub dimension: Dimension,
+    // yaw in degrees
+    pub angle: f32,
+    // respawn there even without a bed or anchor
+    pub forced: bool,
+}
+
+impl RespawnPoint {
+    pub fn new(pos: BlockPos, dimension: Dimension) -> RespawnPoint {
+        RespawnPoint { pos, dimension, angle: 0.0, forced: false }
+    }
+
+    // either layout, `None` when the player has no spawn
+    fn take(map: &mut Compound) -> Result<Option<RespawnPoint>> {
+        if let Some(mut respawn) = take_field_opt(map, "respawn", |t| t.as_compound().cloned())? {
+            let pos = match take_field(&mut respawn, "pos", |t| t.as_int_array().cloned())?.as_slice() {
+                [x, y, z] => BlockPos::from_xyz(*x, *y, *z),
+                _ => return Err(invalid_field("pos"))
+            };
+            let dimension = take_field_opt(&mut respawn, "dimension", |t| t.as_str().map(String::from))?;
+            return Ok(Some(RespawnPoint {
+                pos,
+                dimension: take_dimension(dimension)?,
+                angle: take_field_opt(&mut respawn, "angle", Tag::as_float)?.unwrap_or(0.0),
+                forced: take_field_opt(&mut respawn, "forced", Tag::as_bool)?.unwrap_or(false),
+            }));
+        }
+        let pos = match (map.get("SpawnX"), map.get("SpawnY"), map.get("SpawnZ")) {
+            (Some(Tag::Int(x)), Some(Tag::Int(y)), Some(Tag::Int(z))) => BlockPos::from_xyz(*x, *y, *z),
+            _ => return Ok(None)
+        };
+        for name in ["SpawnX", "SpawnY", "SpawnZ"].iter() {
+            map.remove(*name);
+        }
+        let dimension = take_field_opt(map, "SpawnDimension", |t| t.as_str().map(String::from))?;
+        Ok(Some(RespawnPoint {
+            pos,
+            dimension: take_dimension(dimension)?,
+            angle: take_field_opt(map, "SpawnAngle", Tag::as_float)?.unwrap_or(0.0),
+            forced: take_field_opt(map, "SpawnForced", Tag::as_bool)?.unwrap_or(false),
+        }))
+    }
+
+    // in the layout of the DataVersion
+    fn put(&self, map: &mut Compound, data_version: i32) {
+        let (x, y, z) = self.pos.to_xyz();
+        if data_version >= DATA_VERSION_RESPAWN_COMPOUND {
+            let mut respawn = Compound::new();
+            respawn.insert("pos".to_string(), Tag::IntArray(vec![x, y, z]));
+            respawn.insert("dimension".to_string(), Tag::String(self.dimension.id()));
+            respawn.insert("angle".to_string(), Tag::Float(self.angle));
+            // vanilla leaves it out when false
+            if self.forced {
+                respawn.insert("forced".to_string(), Tag::Byte(1));
+            }
+            map.insert("respawn".to_string(), Tag::Compound(respawn));
+            return;
+        }
+        map.insert("SpawnX".to_string(), Tag::Int(x));
+        map.insert("SpawnY".to_string(), Tag::Int(y));
+        map.insert("SpawnZ".to_string(), Tag::Int(z));
+        map.insert("SpawnForced".to_string(), Tag::Byte(self.forced as i8));
+        if data_version >= DATA_VERSION_SPAWN_DIMENSION {
+            map.insert("SpawnDimension".to_string(), Tag::String(self.dimension.id()));
+            map.insert("SpawnAngle".to_string(), Tag::Float(self.angle));
+        }
+    }
+}
+
+fn take_dimension(id: Option<String>) -> Result<Dimension> {
+    match id {
+        Some(id) => Dimension::from_id(&id).ok_or_else(|| invalid_field("dimension")),
+        None => Ok(Dimension::Overworld)
+    }
+}
+
+fn compound_list<T>(map: &mut Compound, name: &str, f: impl Fn(Compound) -> Result<T>) -> Result<Vec<T>> {
+    take_compound_list(map, name)?.into_iter().map(f).collect()
+}
+
+fn invalid_field(name: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Invalid NBT field: {}", name))
+}
+
+// keys not modelled here are kept as they are and written back on save
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerData {
+    pub data_version: Option<i32>,
+    pub pos: [f64; 3],
+    // yaw and pitch in degrees
+    pub rotation: [f32; 2],
+    pub dimension: Dimension,
+    pub health: f32,
+    pub food_level: i32,
+    pub food_saturation: f32,
+    pub xp_level: i32,
+    // progress towards the next level, from 0 to 1
+    pub xp_progress: f32,
+    pub xp_total: i32,
+    pub game_type: GameType,
+    pub inventory: Vec<ItemStack>,
+    pub ender_items: Vec<ItemStack>,
+    pub active_effects: Vec<StatusEffect>,
+    // `None` to respawn at the world spawn
+    pub spawn: Option<RespawnPoint>,
+    // `Dimension` was a number before 1.16
+    dimension_as_number: bool,
+    root_name: String,
+    other: Compound,
+}
+
+impl PlayerData {
+    pub fn from_nbt(data: nbt::Data) -> Result<PlayerData> {
+        let root_name = data.root_name().to_string();
+        let mut map = match data.into_root_tag() {
+            Tag::Compound(map) => map,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid player data root tag"))
+        };
+        let pos = match take_field(&mut map, "Pos", |t| t.as_list().cloned())?.as_slice() {
+            [Tag::Double(x), Tag::Double(y), Tag::Double(z)] => [*x, *y, *z],
+            _ => return Err(invalid_field("Pos"))
+        };
+        let rotation = match take_field_opt(&mut map, "Rotation", |t| t.as_list().cloned())?.unwrap_or_default().as_slice() {
+            [Tag::Float(yaw), Tag::Float(pitch)] => [*yaw, *pitch],
+            [] => [0.0, 0.0],
+            _ => return Err(invalid_field("Rotation"))
+        };
+        let (dimension, dimension_as_number) = match map.remove("Dimension") {
+            Some(Tag::Int(id)) => (Dimension::from_legacy_id(id).ok_or_else(|| invalid_field("Dimension"))?, true),
+            Some(Tag::String(id)) => (Dimension::from_id(&id).ok_or_else(|| invalid_field("Dimension"))?, false),
+            None => (Dimension::Overworld, false),
+            _ => return Err(invalid_field("Dimension"))
+        };
+        let game_type = take_field_opt(&mut map, "playerGameType", Tag::as_int)?.unwrap_or(0);
+        let game_type = GameType::from_id(game_type).ok_or_else(||
+            Error::new(ErrorKind::InvalidData, format!("Invalid game type: {}", game_type)))?;
+        let spawn = RespawnPoint::take(&mut map)?;
+        Ok(PlayerData {
+            data_version: take_field_opt(&mut map, "DataVersion", Tag::as_int)?,
+            pos,
+            rotation,
+            dimension,
+            health: take_field_opt(&mut map, "Health", Tag::as_float)?.unwrap_or(20.0),
+            food_level: take_field_opt(&mut map, "foodLevel", Tag::as_int)?.unwrap_or(20),
+            food_saturation: take_field_opt(&mut map, "foodSaturationLevel", Tag::as_float)?.unwrap_or(5.0),
+            xp_level: take_field_opt(&mut map, "XpLevel", Tag::as_int)?.unwrap_or(0),
+            xp_progress: take_field_opt(&mut map, "XpP", Tag::as_float)?.unwrap_or(0.0),
+            xp_total: take_field_opt(&mut map, "XpTotal", Tag::as_int)?.unwrap_or(0),
+            game_type,
+            inventory: compound_list(&mut map, "Inventory", ItemStack::from_nbt)?,
+            ender_items: compound_list(&mut map, "EnderItems", ItemStack::from_nbt)?,
+            active_effects: if map.contains_key("active_effects") {
+                compound_list(&mut map, "active_effects", StatusEffect::from_nbt)?
+            } else {
+                compound_list(&mut map, "ActiveEffects", StatusEffect::from_nbt)?
+            },
+            spawn,
+            dimension_as_number,
+            root_name,
+            other: map
+        })
+    }
+
+    // items and effects are written as the DataVersion stores them, fails
+    // for effects that have no number in it
+    pub fn to_nbt(&self) -> Result<nbt::Data> {
+        // players without a DataVersion are far older than any change here
+        let data_version = self.data_version.unwrap_or(0);
+        let mut map = self.other.clone();
+        if let Some(data_version) = self.data_version {
+            map.insert("DataVersion".to_string(), Tag::Int(data_version));
+        }
+        map.insert("Pos".to_string(), Tag::List(self.pos.iter().map(|&v| Tag::Double(v)).collect()));
+        map.insert("Rotation".to_string(), Tag::List(self.rotation.iter().map(|&v| Tag::Float(v)).collect()));
+        let dimension = match self.dimension.legacy_id() {
+            Some(id) if self.dimension_as_number => Tag::Int(id),
+            _ => Tag::String(self.dimension.id())
+        };
+        map.insert("Dimension".to_string(), dimension);
+        map.insert("Health".to_string(), Tag::Float(self.health));
+        map.insert("foodLevel".to_string(), Tag::Int(self.food_level));
+        map.insert("foodSaturationLevel".to_string(), Tag::Float(self.food_saturation));
+        map.insert("XpLevel".to_string(), Tag::Int(self.xp_level));
+        map.insert("XpP".to_string(), Tag::Float(self.xp_progress));
+        map.insert("XpTotal".to_string(), Tag::Int(self.xp_total));
+        map.insert("playerGameType".to_string(), Tag::Int(self.game_type.to_id()));
+        let items = |items: &[ItemStack]| Tag::List(items.iter().map(|item| Tag::Compound(item.to_nbt(data_version))).collect());
+        map.insert("Inventory".to_string(), items(&self.inventory));
+        map.insert("EnderItems".to_string(), items(&self.ender_items));
+        // vanilla leaves the list out when there are no effects
+        if !self.active_effects.is_empty() {
+            let effects = self.active_effects.iter()
+                .map(|effect| effect.to_nbt(data_version).map(Tag::Compound))
+                .collect::<Result<_>>()?;
+            let name = if data_version >= DATA_VERSION_EFFECT_IDS { "active_effects" } else { "ActiveEffects" };
+            map.insert(name.to_string(), Tag::List(effects));
+        }
+        // vanilla writes none of the keys without a spawn
+        if let Some(spawn) = &self.spawn {
+            spawn.put(&mut map, data_version);
+        }
+        Ok(nbt::Data::new(self.root_name.clone(), Tag::Compound(map)))
+    }
+}
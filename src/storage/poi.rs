@@ -0,0 +1,128 @@
+// chunks of the `poi` region folder: beds, job sites, portals and so on
+// https://minecraft.gamepedia.com/Point_of_Interest_format
+
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use crate::nbt::{self, Compound, Tag, take_field, take_field_opt};
+use crate::pos::BlockPos;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PoiRecord {
+    pub pos: BlockPos,
+    // point of interest type, like `minecraft:home`
+    pub kind: String,
+    // how many more villagers may claim this
+    pub free_tickets: i32,
+    other: Compound,
+}
+
+impl PoiRecord {
+    pub fn new(pos: BlockPos, kind: impl Into<String>, free_tickets: i32) -> PoiRecord {
+        PoiRecord {
+            pos,
+            kind: kind.into(),
+            free_tickets,
+            other: Compound::new()
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct PoiSection {
+    // false if the records need to be rebuilt from blocks
+    pub valid: bool,
+    pub records: Vec<PoiRecord>,
+    other: Compound,
+}
+
+impl PoiSection {
+    pub fn new(valid: bool, records: Vec<PoiRecord>) -> PoiSection {
+        PoiSection {
+            valid,
+            records,
+            other: Compound::new()
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PoiChunk {
+    pub data_version: i32,
+    // keyed by section y
+    pub sections: BTreeMap<i32, PoiSection>,
+    other: Compound,
+}
+
+impl PoiChunk {
+    pub fn new(data_version: i32) -> PoiChunk {
+        PoiChunk {
+            data_version,
+            sections: BTreeMap::new(),
+            other: Compound::new()
+        }
+    }
+
+    pub fn from_nbt(data: nbt::Data) -> Result<PoiChunk> {
+        let mut map = match data.into_root_tag() {
+            Tag::Compound(map) => map,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid POI chunk root tag"))
+        };
+        let data_version = take_field(&mut map, "DataVersion", Tag::as_int)?;
+        let mut sections = BTreeMap::new();
+        for (y, section) in take_field(&mut map, "Sections", |t| t.as_compound().cloned())? {
+            let y = y.parse().map_err(|_| Error::new(ErrorKind::InvalidData, format!("Invalid POI section: {}", y)))?;
+            let mut section = match section {
+                Tag::Compound(section) => section,
+                _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid POI section"))
+            };
+            let mut records = Vec::new();
+            for record in take_field_opt(&mut section, "Records", |t| t.as_list().cloned())?.unwrap_or_default() {
+                let mut record = match record {
+                    Tag::Compound(record) => record,
+                    _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid POI record"))
+                };
+                let pos = match take_field(&mut record, "pos", |t| t.as_int_array().cloned())?.as_slice() {
+                    &[x, y, z] => BlockPos::from_xyz(x, y, z),
+                    _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid POI record position"))
+                };
+                records.push(PoiRecord {
+                    pos,
+                    kind: take_field(&mut record, "type", |t| t.as_str().map(String::from))?,
+                    free_tickets: take_field(&mut record, "free_tickets", Tag::as_int)?,
+                    other: record
+                });
+            }
+            sections.insert(y, PoiSection {
+                valid: take_field_opt(&mut section, "Valid", Tag::as_bool)?.unwrap_or(false),
+                records,
+                other: section
+            });
+        }
+        Ok(PoiChunk {
+            data_version,
+            sections,
+            other: map
+        })
+    }
+
+    pub fn to_nbt(&self) -> nbt::Data {
+        let mut map = self.other.clone();
+        map.insert("DataVersion".to_string(), Tag::Int(self.data_version));
+        let sections = self.sections.iter().map(|(y, section)| {
+            let records = section.records.iter().map(|record| {
+                let (x, y, z) = record.pos.to_xyz();
+                let mut record_map = record.other.clone();
+                record_map.insert("pos".to_string(), Tag::IntArray(vec![x, y, z]));
+                record_map.insert("type".to_string(), Tag::String(record.kind.clone()));
+                record_map.insert("free_tickets".to_string(), Tag::Int(record.free_tickets));
+                Tag::Compound(record_map)
+            }).collect();
+            let mut section_map = section.other.clone();
+            section_map.insert("Valid".to_string(), Tag::Byte(section.valid as i8));
+            section_map.insert("Records".to_string(), Tag::List(records));
+            (y.to_string(), Tag::Compound(section_map))
+        }).collect();
+        map.insert("Sections".to_string(), Tag::Compound(sections));
+        nbt::Data::new("", Tag::Compound(map))
+    }
+}
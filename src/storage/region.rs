@@ -0,0 +1,342 @@
+// region files, each holding 32*32 chunks
+// https://minecraft.gamepedia.com/Region_file_format
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{Compression, read::{GzDecoder, ZlibDecoder}, write::ZlibEncoder};
+use crate::chunk;
+use crate::nbt::{self, Read as NbtRead, Write as NbtWrite};
+
+crate const SECTOR_SIZE: usize = 4096;
+const HEADER_SECTORS: u32 = 2;
+const MAX_SECTOR_COUNT: usize = 255;
+
+crate const COMPRESSION_GZIP: u8 = 1;
+crate const COMPRESSION_ZLIB: u8 = 2;
+crate const COMPRESSION_NONE: u8 = 3;
+// set if the payload is stored in an external `c.X.Z.mcc` file
+crate const COMPRESSION_EXTERNAL: u8 = 128;
+
+// each dimension keeps several kinds of region files side by side
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum RegionKind {
+    // blocks, and everything else before 1.17
+    Terrain,
+    // since 1.17
+    Entities,
+    // points of interest, since 1.14
+    Poi,
+}
+
+impl RegionKind {
+    pub fn folder_name(&self) -> &'static str {
+        match self {
+            RegionKind::Terrain => "region",
+            RegionKind::Entities => "entities",
+            RegionKind::Poi => "poi",
+        }
+    }
+}
+
+// region coordinates the chunk falls in
+pub fn region_xz(pos: chunk::Pos) -> (i32, i32) {
+    let (x, z) = pos.to_xz();
+    (x >> 5, z >> 5)
+}
+
+// parse `r.X.Z.mca` (or `.mcr`) into region coordinates
+crate fn parse_region_file_name(name: &str, extension: &str) -> Option<(i32, i32)> {
+    let mut parts = name.split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("r"), Some(x), Some(z), Some(ext), None) if ext == extension =>
+            Some((x.parse().ok()?, z.parse().ok()?)),
+        _ => None
+    }
+}
+
+// region coordinates from a file name like `r.X.Z.mca`, whatever the extension
+crate fn region_file_position(path: &Path) -> Result<(i32, i32)> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| {
+            let extension = path.extension()?.to_str()?;
+            parse_region_file_name(name, extension)
+        })
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("Invalid region file name: {}", path.display())))
+}
+
+// the current time as stored in region headers
+pub fn now_timestamp() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or(0)
+}
+
+#[inline]
+crate fn chunk_index(pos: chunk::Pos) -> usize {
+    let (x, z) = pos.to_xz();
+    ((x & 31) + (z & 31) * 32) as usize
+}
+
+#[inline]
+crate fn chunk_pos(region_x: i32, region_z: i32, index: usize) -> chunk::Pos {
+    chunk::Pos::from_xz(region_x * 32 + (index % 32) as i32, region_z * 32 + (index / 32) as i32)
+}
+
+// `c.X.Z.mcc` next to the region file, for chunks too big to fit in it
+crate fn external_path(region_path: &Path, pos: chunk::Pos) -> PathBuf {
+    let (x, z) = pos.to_xz();
+    region_path.with_file_name(format!("c.{}.{}.mcc", x, z))
+}
+
+#[derive(Debug)]
+pub struct Region {
+    file: fs::File,
+    path: PathBuf,
+    region_x: i32,
+    region_z: i32,
+    // sector offset << 8 | sector count; zero if the chunk is absent
+    locations: Vec<u32>,
+    // last modification time in seconds since epoch
+    timestamps: Vec<u32>,
+}
+
+impl Region {
+    // open an existing region file for reading
+    pub fn open(path: impl AsRef<Path>) -> Result<Region> {
+        let file = fs::File::open(path.as_ref())?;
+        Region::from_file(file, path.as_ref())
+    }
+
+    // open a region file for reading and writing, creating it if missing
+    pub fn open_writable(path: impl AsRef<Path>) -> Result<Region> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path.as_ref())?;
+        if file.metadata()?.len() == 0 {
+            (&file).write_all(&[0u8; SECTOR_SIZE * HEADER_SECTORS as usize])?;
+        }
+        Region::from_file(file, path.as_ref())
+    }
+
+    fn from_file(mut file: fs::File, path: &Path) -> Result<Region> {
+        let (region_x, region_z) = region_file_position(path)?;
+        let mut header = vec![0u8; SECTOR_SIZE * HEADER_SECTORS as usize];
+        match file.read_exact(&mut header) {
+            Ok(()) => {},
+            // empty files are left by crashed servers, treat as no chunks
+            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => header.iter_mut().for_each(|b| *b = 0),
+            Err(e) => return Err(e)
+        }
+        let mut cur = io::Cursor::new(header);
+        let mut locations = vec![0u32; 1024];
+        cur.read_u32_into::<BigEndian>(&mut locations)?;
+        let mut timestamps = vec![0u32; 1024];
+        cur.read_u32_into::<BigEndian>(&mut timestamps)?;
+        Ok(Region {
+            file,
+            path: path.to_path_buf(),
+            region_x,
+            region_z,
+            locations,
+            timestamps
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.region_x, self.region_z)
+    }
+
+    pub fn contains_chunk(&self, pos: chunk::Pos) -> bool {
+        self.locations[chunk_index(pos)] != 0
+    }
+
+    // last modification time of a chunk in seconds since epoch, `None` if absent
+    pub fn chunk_timestamp(&self, pos: chunk::Pos) -> Option<u32> {
+        let index = chunk_index(pos);
+        if self.locations[index] == 0 {
+            return None;
+        }
+        Some(self.timestamps[index])
+    }
+
+    // positions of all chunks present in this region
+    pub fn chunk_positions(&self) -> Vec<chunk::Pos> {
+        (0..1024).filter(|&i| self.locations[i] != 0)
+            .map(|i| chunk_pos(self.region_x, self.region_z, i))
+            .collect()
+    }
+
+    // compression type and the still compressed payload of a chunk
+    pub fn read_chunk_raw(&mut self, pos: chunk::Pos) -> Result<Option<(u8, Vec<u8>)>> {
+        let location = self.locations[chunk_index(pos)];
+        if location == 0 {
+            return Ok(None);
+        }
+        let (offset, count) = ((location >> 8) as u64, (location & 0xFF) as usize);
+        self.file.seek(SeekFrom::Start(offset * SECTOR_SIZE as u64))?;
+        let len = self.file.read_u32::<BigEndian>()? as usize;
+        if len == 0 || len + 4 > count * SECTOR_SIZE {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Invalid chunk length {} in {}", len, self.path.display())));
+        }
+        let compression = self.file.read_u8()?;
+        if compression & COMPRESSION_EXTERNAL != 0 {
+            let data = fs::read(self.external_path(pos))?;
+            return Ok(Some((compression & !COMPRESSION_EXTERNAL, data)));
+        }
+        let mut data = vec![0u8; len - 1];
+        self.file.read_exact(&mut data)?;
+        Ok(Some((compression, data)))
+    }
+
+    pub fn read_chunk_nbt(&mut self, pos: chunk::Pos) -> Result<Option<nbt::Data>> {
+        match self.read_chunk_raw(pos)? {
+            Some((compression, data)) => decode_chunk(compression, &data).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    // write an already compressed payload, stamping it with the current time
+    pub fn write_chunk_raw(&mut self, pos: chunk::Pos, compression: u8, data: &[u8]) -> Result<()> {
+        self.write_chunk_raw_at(pos, compression, data, now_timestamp())
+    }
+
+    // write an already compressed payload, keeping the given modification time
+    pub fn write_chunk_raw_at(&mut self, pos: chunk::Pos, compression: u8, data: &[u8], timestamp: u32) -> Result<()> {
+        let index = chunk_index(pos);
+        let external_path = self.external_path(pos);
+        let external = data.len() + 5 > MAX_SECTOR_COUNT * SECTOR_SIZE;
+        let mut buf = Vec::with_capacity(data.len() + 5);
+        if external {
+            // the region only keeps a stub pointing at `c.X.Z.mcc`
+            fs::write(&external_path, data)?;
+            buf.write_u32::<BigEndian>(1)?;
+            buf.write_u8(compression | COMPRESSION_EXTERNAL)?;
+        } else {
+            buf.write_u32::<BigEndian>(data.len() as u32 + 1)?;
+            buf.write_u8(compression)?;
+            buf.extend_from_slice(data);
+        }
+        let count = (buf.len() + SECTOR_SIZE - 1) / SECTOR_SIZE;
+        buf.resize(count * SECTOR_SIZE, 0);
+        let offset = self.allocate(index, count as u32);
+        self.file.seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE as u64))?;
+        self.file.write_all(&buf)?;
+        self.set_header(index, (offset << 8) | count as u32, timestamp)?;
+        if !external && external_path.exists() {
+            fs::remove_file(external_path)?;
+        }
+        Ok(())
+    }
+
+    // chunks are always written zlib compressed like vanilla does
+    pub fn write_chunk_nbt(&mut self, pos: chunk::Pos, data: &nbt::Data) -> Result<()> {
+        self.write_chunk_raw(pos, COMPRESSION_ZLIB, &encode_chunk(data)?)
+    }
+
+    // the sectors are left as garbage, reused by later writes or freed by `compact`
+    pub fn remove_chunk(&mut self, pos: chunk::Pos) -> Result<()> {
+        let index = chunk_index(pos);
+        if self.locations[index] == 0 {
+            return Ok(());
+        }
+        self.set_header(index, 0, 0)?;
+        let external_path = self.external_path(pos);
+        if external_path.exists() {
+            fs::remove_file(external_path)?;
+        }
+        Ok(())
+    }
+
+    // move every chunk down into the sectors freed by removed or moved chunks,
+    // in the order they are stored, and cut the file after the last one.
+    // fails without moving anything if two chunks share sectors
+    pub fn compact(&mut self) -> Result<()> {
+        let mut chunks: Vec<usize> = (0..1024).filter(|&i| self.locations[i] != 0).collect();
+        chunks.sort_by_key(|&i| self.locations[i] >> 8);
+        let mut end = HEADER_SECTORS;
+        for &index in chunks.iter() {
+            let (offset, count) = (self.locations[index] >> 8, self.locations[index] & 0xFF);
+            if offset < end {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Overlapping chunks in {}", self.path.display())));
+            }
+            end = offset + count;
+        }
+        let mut next = HEADER_SECTORS;
+        for index in chunks {
+            let (offset, count) = (self.locations[index] >> 8, self.locations[index] & 0xFF);
+            if offset != next {
+                // the last sector may be cut short
+                let mut buf = Vec::with_capacity(count as usize * SECTOR_SIZE);
+                self.file.seek(SeekFrom::Start(offset as u64 * SECTOR_SIZE as u64))?;
+                (&mut self.file).take(count as u64 * SECTOR_SIZE as u64).read_to_end(&mut buf)?;
+                buf.resize(count as usize * SECTOR_SIZE, 0);
+                self.file.seek(SeekFrom::Start(next as u64 * SECTOR_SIZE as u64))?;
+                self.file.write_all(&buf)?;
+                let timestamp = self.timestamps[index];
+                self.set_header(index, (next << 8) | count, timestamp)?;
+            }
+            next += count;
+        }
+        self.file.set_len(next as u64 * SECTOR_SIZE as u64)
+    }
+
+    pub fn sync_all(&self) -> Result<()> {
+        self.file.sync_all()
+    }
+
+    fn external_path(&self, pos: chunk::Pos) -> PathBuf {
+        external_path(&self.path, pos)
+    }
+
+    // find room for `count` sectors, reusing the chunk's own sectors if they fit
+    fn allocate(&self, index: usize, count: u32) -> u32 {
+        let location = self.locations[index];
+        if location != 0 && location & 0xFF >= count {
+            return location >> 8;
+        }
+        let mut used: Vec<(u32, u32)> = self.locations.iter().enumerate()
+            .filter(|&(i, &l)| i != index && l != 0)
+            .map(|(_, &l)| (l >> 8, (l >> 8) + (l & 0xFF)))
+            .collect();
+        used.sort();
+        let mut start = HEADER_SECTORS;
+        for (used_start, used_end) in used {
+            if used_start >= start + count {
+                break;
+            }
+            start = start.max(used_end);
+        }
+        start
+    }
+
+    fn set_header(&mut self, index: usize, location: u32, timestamp: u32) -> Result<()> {
+        self.locations[index] = location;
+        self.timestamps[index] = timestamp;
+        self.file.seek(SeekFrom::Start(index as u64 * 4))?;
+        self.file.write_u32::<BigEndian>(location)?;
+        self.file.seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_u32::<BigEndian>(timestamp)
+    }
+}
+
+crate fn decode_chunk(compression: u8, data: &[u8]) -> Result<nbt::Data> {
+    match compression {
+        // the nbt reader asks for a few bytes at a time, keep it off the decompressor
+        COMPRESSION_GZIP => io::BufReader::new(GzDecoder::new(data)).read_nbt_data(),
+        COMPRESSION_ZLIB => io::BufReader::new(ZlibDecoder::new(data)).read_nbt_data(),
+        COMPRESSION_NONE => { let mut data = data; data.read_nbt_data() },
+        other => Err(Error::new(ErrorKind::InvalidData, format!("Unsupported chunk compression: {}", other)))
+    }
+}
+
+crate fn encode_chunk(data: &nbt::Data) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_nbt_data(data)?;
+    encoder.finish()
+}
@@ -0,0 +1,513 @@
+// saved data of a world or dimension, `data/<name>.dat`.
+// each file wraps its content in a `data` compound next to `DataVersion`.
+// https://minecraft.gamepedia.com/Java_Edition_level_format#data
+
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use crate::nbt::{self, Compound, Tag, take_compound_list, take_field, take_field_opt};
+use crate::pos::BlockPos;
+use super::Dimension;
+
+// the content of the `data` compound; `Compound` reads any file untyped
+pub trait SavedDataKind: Sized {
+    fn from_data(map: Compound) -> Result<Self>;
+
+    fn to_data(&self) -> Compound;
+}
+
+impl SavedDataKind for Compound {
+    fn from_data(map: Compound) -> Result<Compound> {
+        Ok(map)
+    }
+
+    fn to_data(&self) -> Compound {
+        self.clone()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SavedData<T> {
+    // absent before 1.13
+    pub data_version: Option<i32>,
+    pub data: T,
+    root_name: String,
+    other: Compound,
+}
+
+impl<T: SavedDataKind> SavedData<T> {
+    pub fn new(data_version: Option<i32>, data: T) -> SavedData<T> {
+        SavedData {
+            data_version,
+            data,
+            root_name: String::new(),
+            other: Compound::new()
+        }
+    }
+
+    pub fn from_nbt(data: nbt::Data) -> Result<SavedData<T>> {
+        let root_name = data.root_name().to_string();
+        let mut map = match data.into_root_tag() {
+            Tag::Compound(map) => map,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid saved data root tag"))
+        };
+        let data_version = take_field_opt(&mut map, "DataVersion", Tag::as_int)?;
+        let data = match map.remove("data") {
+            Some(Tag::Compound(data)) => T::from_data(data)?,
+            _ => return Err(Error::new(ErrorKind::InvalidData, "Missing `data` compound in saved data"))
+        };
+        Ok(SavedData { data_version, data, root_name, other: map })
+    }
+
+    pub fn to_nbt(&self) -> nbt::Data {
+        let mut map = self.other.clone();
+        if let Some(data_version) = self.data_version {
+            map.insert("DataVersion".to_string(), Tag::Int(data_version));
+        }
+        map.insert("data".to_string(), Tag::Compound(self.data.to_data()));
+        nbt::Data::new(self.root_name.clone(), Tag::Compound(map))
+    }
+}
+
+fn take_string(map: &mut Compound, name: &str) -> Result<String> {
+    take_field(map, name, |t| t.as_str().map(String::from))
+}
+
+fn take_center(map: &mut Compound) -> Result<BlockPos> {
+    Ok(BlockPos::from_xyz(
+        take_field(map, "CX", Tag::as_int)?,
+        take_field(map, "CY", Tag::as_int)?,
+        take_field(map, "CZ", Tag::as_int)?,
+    ))
+}
+
+fn insert_center(map: &mut Compound, center: BlockPos) {
+    let (x, y, z) = center.to_xyz();
+    map.insert("CX".to_string(), Tag::Int(x));
+    map.insert("CY".to_string(), Tag::Int(y));
+    map.insert("CZ".to_string(), Tag::Int(z));
+}
+
+fn compound_list(items: impl Iterator<Item = Compound>) -> Tag {
+    Tag::List(items.map(Tag::Compound).collect())
+}
+
+// `villages.dat`, `villages_nether.dat` and `villages_end.dat` before 1.14
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Villages {
+    pub tick: i32,
+    pub villages: Vec<Village>,
+    other: Compound,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Village {
+    pub center: BlockPos,
+    pub radius: i32,
+    pub golems: i32,
+    // doors and player reputations, as stored
+    pub doors: Vec<Compound>,
+    pub players: Vec<Compound>,
+    other: Compound,
+}
+
+impl SavedDataKind for Villages {
+    fn from_data(mut map: Compound) -> Result<Villages> {
+        let mut villages = Vec::new();
+        for mut village in take_compound_list(&mut map, "Villages")? {
+            villages.push(Village {
+                center: take_center(&mut village)?,
+                radius: take_field_opt(&mut village, "Radius", Tag::as_int)?.unwrap_or(0),
+                golems: take_field_opt(&mut village, "Golems", Tag::as_int)?.unwrap_or(0),
+                doors: take_compound_list(&mut village, "Doors")?,
+                players: take_compound_list(&mut village, "Players")?,
+                other: village
+            });
+        }
+        Ok(Villages {
+            tick: take_field_opt(&mut map, "Tick", Tag::as_int)?.unwrap_or(0),
+            villages,
+            other: map
+        })
+    }
+
+    fn to_data(&self) -> Compound {
+        let mut map = self.other.clone();
+        map.insert("Tick".to_string(), Tag::Int(self.tick));
+        map.insert("Villages".to_string(), compound_list(self.villages.iter().map(|village| {
+            let mut map = village.other.clone();
+            insert_center(&mut map, village.center);
+            map.insert("Radius".to_string(), Tag::Int(village.radius));
+            map.insert("Golems".to_string(), Tag::Int(village.golems));
+            map.insert("Doors".to_string(), compound_list(village.doors.iter().cloned()));
+            map.insert("Players".to_string(), compound_list(village.players.iter().cloned()));
+            map
+        })));
+        map
+    }
+}
+
+// `raids.dat` (and `raids_end.dat` before 1.16) since 1.14
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Raids {
+    pub tick: i32,
+    pub next_available_id: i32,
+    pub raids: Vec<Raid>,
+    other: Compound,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Raid {
+    pub id: i32,
+    pub center: BlockPos,
+    pub started: bool,
+    pub active: bool,
+    // `ongoing`, `victory`, `loss` or `stopped`
+    pub status: String,
+    pub bad_omen_level: i32,
+    pub groups_spawned: i32,
+    pub ticks_active: i64,
+    other: Compound,
+}
+
+impl SavedDataKind for Raids {
+    fn from_data(mut map: Compound) -> Result<Raids> {
+        let mut raids = Vec::new();
+        for mut raid in take_compound_list(&mut map, "Raids")? {
+            raids.push(Raid {
+                id: take_field(&mut raid, "Id", Tag::as_int)?,
+                center: take_center(&mut raid)?,
+                started: take_field_opt(&mut raid, "Started", Tag::as_bool)?.unwrap_or(false),
+                active: take_field_opt(&mut raid, "Active", Tag::as_bool)?.unwrap_or(false),
+                status: take_field_opt(&mut raid, "Status", |t| t.as_str().map(String::from))?.unwrap_or_default(),
+                bad_omen_level: take_field_opt(&mut raid, "BadOmenLevel", Tag::as_int)?.unwrap_or(0),
+                groups_spawned: take_field_opt(&mut raid, "GroupsSpawned", Tag::as_int)?.unwrap_or(0),
+                ticks_active: take_field_opt(&mut raid, "TicksActive", Tag::as_long)?.unwrap_or(0),
+                other: raid
+            });
+        }
+        Ok(Raids {
+            tick: take_field_opt(&mut map, "Tick", Tag::as_int)?.unwrap_or(0),
+            next_available_id: take_field_opt(&mut map, "NextAvailableID", Tag::as_int)?.unwrap_or(0),
+            raids,
+            other: map
+        })
+    }
+
+    fn to_data(&self) -> Compound {
+        let mut map = self.other.clone();
+        map.insert("Tick".to_string(), Tag::Int(self.tick));
+        map.insert("NextAvailableID".to_string(), Tag::Int(self.next_available_id));
+        map.insert("Raids".to_string(), compound_list(self.raids.iter().map(|raid| {
+            let mut map = raid.other.clone();
+            map.insert("Id".to_string(), Tag::Int(raid.id));
+            insert_center(&mut map, raid.center);
+            map.insert("Started".to_string(), Tag::Byte(raid.started as i8));
+            map.insert("Active".to_string(), Tag::Byte(raid.active as i8));
+            map.insert("Status".to_string(), Tag::String(raid.status.clone()));
+            map.insert("BadOmenLevel".to_string(), Tag::Int(raid.bad_omen_level));
+            map.insert("GroupsSpawned".to_string(), Tag::Int(raid.groups_spawned));
+            map.insert("TicksActive".to_string(), Tag::Long(raid.ticks_active));
+            map
+        })));
+        map
+    }
+}
+
+// `scoreboard.dat`
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Scoreboard {
+    pub objectives: Vec<Objective>,
+    pub scores: Vec<Score>,
+    pub teams: Vec<Team>,
+    // objective shown in each slot, like `slot_1` for the sidebar
+    pub display_slots: BTreeMap<String, String>,
+    other: Compound,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Objective {
+    pub name: String,
+    // like `dummy` or `minecraft.mined:minecraft.stone`
+    pub criteria: String,
+    // a text component, a json string before 1.21.5 and NBT since
+    pub display_name: Tag,
+    // `integer` or `hearts`
+    pub render_type: String,
+    other: Compound,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Score {
+    // a player name or an entity uuid
+    pub name: String,
+    pub objective: String,
+    pub score: i32,
+    pub locked: bool,
+    other: Compound,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Team {
+    pub name: String,
+    // a text component like the objective's, `None` if not saved
+    pub display_name: Option<Tag>,
+    pub players: Vec<String>,
+    other: Compound,
+}
+
+impl Score {
+    pub fn new(name: impl Into<String>, objective: impl Into<String>, score: i32) -> Score {
+        Score {
+            name: name.into(),
+            objective: objective.into(),
+            score,
+            locked: true,
+            other: Compound::new()
+        }
+    }
+}
+
+impl Scoreboard {
+    pub fn score(&self, name: &str, objective: &str) -> Option<i32> {
+        self.scores.iter()
+            .find(|score| score.name == name && score.objective == objective)
+            .map(|score| score.score)
+    }
+}
+
+impl SavedDataKind for Scoreboard {
+    fn from_data(mut map: Compound) -> Result<Scoreboard> {
+        let mut objectives = Vec::new();
+        for mut objective in take_compound_list(&mut map, "Objectives")? {
+            objectives.push(Objective {
+                name: take_string(&mut objective, "Name")?,
+                criteria: take_string(&mut objective, "CriteriaName")?,
+                display_name: take_field(&mut objective, "DisplayName", |t| Some(t.clone()))?,
+                render_type: take_field_opt(&mut objective, "RenderType", |t| t.as_str().map(String::from))?
+                    .unwrap_or_else(|| "integer".to_string()),
+                other: objective
+            });
+        }
+        let mut scores = Vec::new();
+        for mut score in take_compound_list(&mut map, "PlayerScores")? {
+            scores.push(Score {
+                name: take_string(&mut score, "Name")?,
+                objective: take_string(&mut score, "Objective")?,
+                score: take_field(&mut score, "Score", Tag::as_int)?,
+                locked: take_field_opt(&mut score, "Locked", Tag::as_bool)?.unwrap_or(true),
+                other: score
+            });
+        }
+        let mut teams = Vec::new();
+        for mut team in take_compound_list(&mut map, "Teams")? {
+            let mut players = Vec::new();
+            for player in take_field_opt(&mut team, "Players", |t| t.as_list().cloned())?.unwrap_or_default() {
+                match player {
+                    Tag::String(player) => players.push(player),
+                    _ => return Err(Error::new(ErrorKind::InvalidData, "Invalid team member in scoreboard"))
+                }
+            }
+            teams.push(Team {
+                name: take_string(&mut team, "Name")?,
+                display_name: take_field_opt(&mut team, "DisplayName", |t| Some(t.clone()))?,
+                players,
+                other: team
+            });
+        }
+        let mut display_slots = BTreeMap::new();
+        if let Some(slots) = take_field_opt(&mut map, "DisplaySlots", |t| t.as_compound().cloned())? {
+            for (slot, objective) in slots {
+                match objective {
+                    Tag::String(objective) => display_slots.insert(slot, objective),
+                    _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid display slot: {}", slot)))
+                };
+            }
+        }
+        Ok(Scoreboard { objectives, scores, teams, display_slots, other: map })
+    }
+
+    fn to_data(&self) -> Compound {
+        let mut map = self.other.clone();
+        map.insert("Objectives".to_string(), compound_list(self.objectives.iter().map(|objective| {
+            let mut map = objective.other.clone();
+            map.insert("Name".to_string(), Tag::String(objective.name.clone()));
+            map.insert("CriteriaName".to_string(), Tag::String(objective.criteria.clone()));
+            map.insert("DisplayName".to_string(), objective.display_name.clone());
+            map.insert("RenderType".to_string(), Tag::String(objective.render_type.clone()));
+            map
+        })));
+        map.insert("PlayerScores".to_string(), compound_list(self.scores.iter().map(|score| {
+            let mut map = score.other.clone();
+            map.insert("Name".to_string(), Tag::String(score.name.clone()));
+            map.insert("Objective".to_string(), Tag::String(score.objective.clone()));
+            map.insert("Score".to_string(), Tag::Int(score.score));
+            map.insert("Locked".to_string(), Tag::Byte(score.locked as i8));
+            map
+        })));
+        map.insert("Teams".to_string(), compound_list(self.teams.iter().map(|team| {
+            let mut map = team.other.clone();
+            map.insert("Name".to_string(), Tag::String(team.name.clone()));
+            if let Some(display_name) = &team.display_name {
+                map.insert("DisplayName".to_string(), display_name.clone());
+            }
+            map.insert("Players".to_string(), Tag::List(team.players.iter().cloned().map(Tag::String).collect()));
+            map
+        })));
+        let slots = self.display_slots.iter()
+            .map(|(slot, objective)| (slot.clone(), Tag::String(objective.clone())))
+            .collect();
+        map.insert("DisplaySlots".to_string(), Tag::Compound(slots));
+        map
+    }
+}
+
+// `idcounts.dat`, the last id given out per kind, like `map`
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct IdCounts {
+    pub counts: BTreeMap<String, i32>,
+}
+
+impl SavedDataKind for IdCounts {
+    fn from_data(map: Compound) -> Result<IdCounts> {
+        let mut counts = BTreeMap::new();
+        for (name, count) in map {
+            // a short before 1.13
+            let count = match count {
+                Tag::Int(count) => count,
+                Tag::Short(count) => count as i32,
+                _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid id count: {}", name)))
+            };
+            counts.insert(name, count);
+        }
+        Ok(IdCounts { counts })
+    }
+
+    fn to_data(&self) -> Compound {
+        self.counts.iter().map(|(name, &count)| (name.clone(), Tag::Int(count))).collect()
+    }
+}
+
+// `map_<id>.dat`, a filled map item
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapData {
+    // zero for 1:1, up to 4 for 1:16
+    pub scale: i8,
+    pub dimension: Dimension,
+    pub x_center: i32,
+    pub z_center: i32,
+    pub locked: bool,
+    pub tracking_position: bool,
+    // 128 * 128 map colors, row by row
+    pub colors: Vec<u8>,
+    // `dimension` was a byte, then an int, before 1.16
+    dimension_tag: Tag,
+    other: Compound,
+}
+
+impl SavedDataKind for MapData {
+    fn from_data(mut map: Compound) -> Result<MapData> {
+        let dimension_tag = map.remove("dimension").unwrap_or(Tag::Byte(0));
+        let dimension = match &dimension_tag {
+            Tag::Byte(id) => Dimension::from_legacy_id(*id as i32),
+            Tag::Int(id) => Dimension::from_legacy_id(*id),
+            Tag::String(id) => Dimension::from_id(id),
+            _ => None
+        }.ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid map dimension"))?;
+        let colors = take_field_opt(&mut map, "colors", |t| t.as_byte_array().cloned())?
+            .unwrap_or_else(|| vec![0; 128 * 128]);
+        Ok(MapData {
+            scale: take_field_opt(&mut map, "scale", Tag::as_byte)?.unwrap_or(0),
+            dimension,
+            x_center: take_field(&mut map, "xCenter", Tag::as_int)?,
+            z_center: take_field(&mut map, "zCenter", Tag::as_int)?,
+            locked: take_field_opt(&mut map, "locked", Tag::as_bool)?.unwrap_or(false),
+            tracking_position: take_field_opt(&mut map, "trackingPosition", Tag::as_bool)?.unwrap_or(true),
+            colors: colors.into_iter().map(|c| c as u8).collect(),
+            dimension_tag,
+            other: map
+        })
+    }
+
+    fn to_data(&self) -> Compound {
+        let mut map = self.other.clone();
+        let dimension = match (&self.dimension_tag, self.dimension.legacy_id()) {
+            (Tag::Byte(_), Some(id)) => Tag::Byte(id as i8),
+            (Tag::Int(_), Some(id)) => Tag::Int(id),
+            _ => Tag::String(self.dimension.id())
+        };
+        map.insert("dimension".to_string(), dimension);
+        map.insert("scale".to_string(), Tag::Byte(self.scale));
+        map.insert("xCenter".to_string(), Tag::Int(self.x_center));
+        map.insert("zCenter".to_string(), Tag::Int(self.z_center));
+        map.insert("locked".to_string(), Tag::Byte(self.locked as i8));
+        map.insert("trackingPosition".to_string(), Tag::Byte(self.tracking_position as i8));
+        map.insert("colors".to_string(), Tag::ByteArray(self.colors.iter().map(|&c| c as i8).collect()));
+        map
+    }
+}
+
+// `command_storage_<namespace>.dat`, what `/data modify storage` keeps,
+// by path within the namespace
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct CommandStorage {
+    pub contents: BTreeMap<String, Compound>,
+    other: Compound,
+}
+
+impl SavedDataKind for CommandStorage {
+    fn from_data(mut map: Compound) -> Result<CommandStorage> {
+        let mut contents = BTreeMap::new();
+        for (path, value) in take_field_opt(&mut map, "contents", |t| t.as_compound().cloned())?.unwrap_or_default() {
+            match value {
+                Tag::Compound(value) => contents.insert(path, value),
+                _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid command storage: {}", path)))
+            };
+        }
+        Ok(CommandStorage { contents, other: map })
+    }
+
+    fn to_data(&self) -> Compound {
+        let mut map = self.other.clone();
+        let contents = self.contents.iter()
+            .map(|(path, value)| (path.clone(), Tag::Compound(value.clone())))
+            .collect();
+        map.insert("contents".to_string(), Tag::Compound(contents));
+        map
+    }
+}
+
+// `random_sequences.dat` since 1.20, state of each named random sequence
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct RandomSequences {
+    pub salt: i64,
+    // the `source` of each sequence is kept as stored
+    pub sequences: BTreeMap<String, Compound>,
+    other: Compound,
+}
+
+impl SavedDataKind for RandomSequences {
+    fn from_data(mut map: Compound) -> Result<RandomSequences> {
+        let mut sequences = BTreeMap::new();
+        for (id, value) in take_field_opt(&mut map, "sequences", |t| t.as_compound().cloned())?.unwrap_or_default() {
+            match value {
+                Tag::Compound(value) => sequences.insert(id, value),
+                _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid random sequence: {}", id)))
+            };
+        }
+        Ok(RandomSequences {
+            salt: take_field_opt(&mut map, "salt", Tag::as_long)?.unwrap_or(0),
+            sequences,
+            other: map
+        })
+    }
+
+    fn to_data(&self) -> Compound {
+        let mut map = self.other.clone();
+        map.insert("salt".to_string(), Tag::Long(self.salt));
+        let sequences = self.sequences.iter()
+            .map(|(id, value)| (id.clone(), Tag::Compound(value.clone())))
+            .collect();
+        map.insert("sequences".to_string(), Tag::Compound(sequences));
+        map
+    }
+}
@@ -0,0 +1,184 @@
+// session.lock, guarding a world from being modified by two processes at once
+// https://minecraft.gamepedia.com/Java_Edition_level_format#session.lock_format
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+// first DataVersion that uses the snowman lock (1.16)
+const DATA_VERSION_SNOWMAN: i32 = 2566;
+
+const SNOWMAN: &str = "\u{2603}";
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum LockFormat {
+    // before 1.16: the opening time in milliseconds, as a big endian long.
+    // the latest process to open the world wins, others find the timestamp
+    // changed and must stop saving.
+    Timestamp,
+    // since 1.16: a snowman character with an OS file lock held on the file
+    Snowman,
+}
+
+impl LockFormat {
+    // worlds without a DataVersion are far older than 1.16
+    pub fn for_data_version(data_version: Option<i32>) -> LockFormat {
+        match data_version {
+            Some(v) if v >= DATA_VERSION_SNOWMAN => LockFormat::Snowman,
+            _ => LockFormat::Timestamp
+        }
+    }
+}
+
+// what to do when another process holds the world
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum LockPolicy {
+    Refuse,
+    ReadOnly,
+}
+
+#[derive(Debug)]
+pub struct SessionLock {
+    file: fs::File,
+    format: LockFormat,
+    timestamp: i64,
+}
+
+impl SessionLock {
+    // fails with `ErrorKind::WouldBlock` if another process holds the lock
+    pub fn acquire(world_path: impl AsRef<Path>, format: LockFormat) -> Result<SessionLock> {
+        let path = world_path.as_ref().join("session.lock");
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        // vanilla only locks the file since 1.16, but holding an OS lock
+        // costs nothing and also keeps newer servers away from older worlds.
+        // nothing is written before the lock is ours
+        if !try_lock(&file)? {
+            return Err(Error::new(ErrorKind::WouldBlock,
+                format!("{}: already locked (possibly by other Minecraft instance?)", path.display())));
+        }
+        let mut timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        if format == LockFormat::Timestamp {
+            // an older game keeps saving while the stamp is the one it wrote,
+            // so ours must differ from it even within the same millisecond
+            let mut old = Vec::new();
+            file.read_to_end(&mut old)?;
+            if let Ok(previous) = io::Cursor::new(old).read_i64::<BigEndian>() {
+                if previous >= timestamp {
+                    timestamp = previous.wrapping_add(1);
+                }
+            }
+            file.seek(SeekFrom::Start(0))?;
+        }
+        file.set_len(0)?;
+        match format {
+            LockFormat::Timestamp => file.write_i64::<BigEndian>(timestamp)?,
+            LockFormat::Snowman => file.write_all(SNOWMAN.as_bytes())?,
+        }
+        file.sync_all()?;
+        Ok(SessionLock { file, format, timestamp })
+    }
+
+    pub fn format(&self) -> LockFormat {
+        self.format
+    }
+
+    // check that no other process has taken the world over since we acquired it
+    pub fn check(&mut self) -> Result<()> {
+        if self.format == LockFormat::Snowman {
+            return Ok(()); // guarded by the OS lock
+        }
+        self.file.seek(SeekFrom::Start(0))?;
+        let timestamp = match self.file.read_i64::<BigEndian>() {
+            Ok(timestamp) => timestamp,
+            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => -1,
+            Err(e) => return Err(e)
+        };
+        if timestamp != self.timestamp {
+            return Err(Error::new(ErrorKind::PermissionDenied,
+                "The save is being accessed from another location, aborting"));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        // closing the file releases the lock as well, this is just being explicit
+        let _ = unlock(&self.file);
+    }
+}
+
+// open file description locks conflict with the record locks the JVM takes,
+// but unlike those they are not released when this process closes another
+// handle of the same file, and they keep two handles of one process apart
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const LOCK_COMMAND: libc::c_int = libc::F_OFD_SETLK;
+// elsewhere the lock is a plain record lock, the same one the JVM takes. it
+// belongs to the whole process: opening the world twice from one process does
+// not fail, and closing any other handle of session.lock in this process,
+// like `read_session_lock` does, releases it. other processes are kept out
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+const LOCK_COMMAND: libc::c_int = libc::F_SETLK;
+
+// lock the whole file the way `FileChannel.tryLock` does, with an fcntl
+// lock on unix: a running game never sees a `flock` one.
+// false if another process holds it
+#[cfg(unix)]
+fn try_lock(file: &fs::File) -> Result<bool> {
+    match set_lock(file, libc::F_WRLCK) {
+        Ok(()) => Ok(true),
+        Err(ref e) if e.raw_os_error() == Some(libc::EAGAIN) || e.raw_os_error() == Some(libc::EACCES) => Ok(false),
+        Err(e) => Err(e)
+    }
+}
+
+#[cfg(unix)]
+fn unlock(file: &fs::File) -> Result<()> {
+    set_lock(file, libc::F_UNLCK)
+}
+
+#[cfg(unix)]
+fn set_lock(file: &fs::File, lock_type: libc::c_int) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+    // zero start and length cover the whole file however long it grows
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = lock_type as _;
+    lock.l_whence = libc::SEEK_SET as _;
+    if unsafe { libc::fcntl(file.as_raw_fd(), LOCK_COMMAND, &lock) } == -1 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+// `LockFileEx` over the whole file, which is what the JVM does on windows
+#[cfg(windows)]
+fn try_lock(file: &fs::File) -> Result<bool> {
+    use fs2::FileExt;
+    match file.try_lock_exclusive() {
+        Ok(()) => Ok(true),
+        Err(ref e) if e.raw_os_error() == fs2::lock_contended_error().raw_os_error() => Ok(false),
+        Err(e) => Err(e)
+    }
+}
+
+#[cfg(windows)]
+fn unlock(file: &fs::File) -> Result<()> {
+    fs2::FileExt::unlock(file)
+}
+
+// read what's in session.lock without locking it
+crate fn read_session_lock(world_path: impl AsRef<Path>) -> io::Result<(LockFormat, Option<i64>)> {
+    let mut buf = Vec::new();
+    fs::File::open(world_path.as_ref().join("session.lock"))?.read_to_end(&mut buf)?;
+    if buf.starts_with(SNOWMAN.as_bytes()) {
+        return Ok((LockFormat::Snowman, None));
+    }
+    match io::Cursor::new(buf).read_i64::<BigEndian>() {
+        Ok(timestamp) => Ok((LockFormat::Timestamp, Some(timestamp))),
+        Err(_) => Err(Error::new(ErrorKind::InvalidData, "Invalid session.lock"))
+    }
+}
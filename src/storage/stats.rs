@@ -0,0 +1,172 @@
+// per player statistics and advancements, kept as json since 1.12
+// https://minecraft.gamepedia.com/Statistics
+// https://minecraft.gamepedia.com/Advancement#JSON_Format
+
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use serde_json::{Map, Value};
+use super::Uuid;
+
+fn invalid_json(what: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Invalid json: {}", what))
+}
+
+fn data_version(root: &Map<String, Value>) -> Result<Option<i32>> {
+    match root.get("DataVersion") {
+        Some(v) => v.as_i64().map(|v| Some(v as i32)).ok_or_else(|| invalid_json("DataVersion")),
+        None => Ok(None)
+    }
+}
+
+// `stats/<uuid>.json` in the 1.13 format, values by category then key,
+// like `minecraft:mined` and `minecraft:stone`
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct PlayerStats {
+    pub data_version: Option<i32>,
+    pub stats: BTreeMap<String, BTreeMap<String, i64>>,
+}
+
+impl PlayerStats {
+    // absent statistics count as zero
+    pub fn get(&self, category: &str, key: &str) -> i64 {
+        self.stats.get(category).and_then(|keys| keys.get(key)).cloned().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, category: &str, key: &str, value: i64) {
+        self.stats.entry(category.to_string()).or_default().insert(key.to_string(), value);
+    }
+
+    pub fn from_json(json: &str) -> Result<PlayerStats> {
+        let root: Map<String, Value> = serde_json::from_str(json)?;
+        let categories = root.get("stats").and_then(Value::as_object)
+            .ok_or_else(|| invalid_json("missing `stats`, statistics before 1.13 are not supported"))?;
+        let mut stats = BTreeMap::new();
+        for (category, keys) in categories {
+            let keys = keys.as_object().ok_or_else(|| invalid_json(category))?;
+            let mut values = BTreeMap::new();
+            for (key, value) in keys {
+                values.insert(key.clone(), value.as_i64().ok_or_else(|| invalid_json(key))?);
+            }
+            stats.insert(category.clone(), values);
+        }
+        Ok(PlayerStats {
+            data_version: data_version(&root)?,
+            stats
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut root = Map::new();
+        let stats = self.stats.iter()
+            .map(|(category, keys)| {
+                let keys = keys.iter().map(|(key, &value)| (key.clone(), Value::from(value))).collect();
+                (category.clone(), Value::Object(keys))
+            })
+            .collect();
+        root.insert("stats".to_string(), Value::Object(stats));
+        if let Some(data_version) = self.data_version {
+            root.insert("DataVersion".to_string(), Value::from(data_version));
+        }
+        Value::Object(root).to_string()
+    }
+}
+
+// players ranked by one statistic, highest first; ties keep uuid order
+pub fn stats_leaderboard(players: &[(Uuid, PlayerStats)], category: &str, key: &str) -> Vec<(Uuid, i64)> {
+    let mut ans: Vec<(Uuid, i64)> = players.iter()
+        .map(|(uuid, stats)| (*uuid, stats.get(category, key)))
+        .collect();
+    ans.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    ans
+}
+
+// sum of every player's statistics
+pub fn stats_total(players: &[(Uuid, PlayerStats)]) -> PlayerStats {
+    let mut ans = PlayerStats::default();
+    for (_, stats) in players {
+        for (category, keys) in &stats.stats {
+            for (key, value) in keys {
+                let total = ans.get(category, key) + value;
+                ans.set(category, key, total);
+            }
+        }
+    }
+    ans
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct AdvancementProgress {
+    // criteria met so far, with when they were met
+    // like `2018-08-15 15:22:40 +0800`
+    pub criteria: BTreeMap<String, String>,
+    pub done: bool,
+}
+
+// `advancements/<uuid>.json`, progress by advancement id
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct PlayerAdvancements {
+    pub data_version: Option<i32>,
+    pub advancements: BTreeMap<String, AdvancementProgress>,
+}
+
+impl PlayerAdvancements {
+    // recipes are unlocked as advancements too, but rarely count as such
+    pub fn done_count(&self, include_recipes: bool) -> usize {
+        self.advancements.iter()
+            .filter(|(id, progress)| progress.done && (include_recipes || !id.contains(":recipes/")))
+            .count()
+    }
+
+    pub fn from_json(json: &str) -> Result<PlayerAdvancements> {
+        let root: Map<String, Value> = serde_json::from_str(json)?;
+        let mut advancements = BTreeMap::new();
+        for (id, value) in &root {
+            if id == "DataVersion" {
+                continue;
+            }
+            let value = value.as_object().ok_or_else(|| invalid_json(id))?;
+            let mut criteria = BTreeMap::new();
+            if let Some(values) = value.get("criteria") {
+                for (name, time) in values.as_object().ok_or_else(|| invalid_json(id))? {
+                    criteria.insert(name.clone(), time.as_str().ok_or_else(|| invalid_json(name))?.to_string());
+                }
+            }
+            let done = match value.get("done") {
+                Some(done) => done.as_bool().ok_or_else(|| invalid_json(id))?,
+                None => false
+            };
+            advancements.insert(id.clone(), AdvancementProgress { criteria, done });
+        }
+        Ok(PlayerAdvancements {
+            data_version: data_version(&root)?,
+            advancements
+        })
+    }
+
+    // pretty printed as vanilla does
+    pub fn to_json(&self) -> String {
+        let mut root = Map::new();
+        for (id, progress) in &self.advancements {
+            let criteria = progress.criteria.iter()
+                .map(|(name, time)| (name.clone(), Value::from(time.as_str())))
+                .collect();
+            let mut value = Map::new();
+            value.insert("criteria".to_string(), Value::Object(criteria));
+            value.insert("done".to_string(), Value::from(progress.done));
+            root.insert(id.clone(), Value::Object(value));
+        }
+        if let Some(data_version) = self.data_version {
+            root.insert("DataVersion".to_string(), Value::from(data_version));
+        }
+        serde_json::to_string_pretty(&Value::Object(root)).unwrap()
+    }
+}
+
+// players ranked by advancements done, highest first; ties keep uuid order
+pub fn advancements_leaderboard(players: &[(Uuid, PlayerAdvancements)], include_recipes: bool) -> Vec<(Uuid, usize)> {
+    let mut ans: Vec<(Uuid, usize)> = players.iter()
+        .map(|(uuid, advancements)| (*uuid, advancements.done_count(include_recipes)))
+        .collect();
+    ans.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    ans
+}
@@ -0,0 +1,153 @@
+// shrink a world by deleting chunks nobody cares about.
+// deleted chunks are dropped from the region headers, along with their
+// entities and points of interest. the chunks left are moved into the freed
+// sectors so that region files shrink, and those left empty are removed.
+// the game generates deleted chunks again when they are next visited.
+
+use std::fs;
+use std::io::Result;
+use std::path::Path;
+use crate::chunk;
+use crate::nbt::{self, Tag};
+use super::{Dimension, McJavaWorld, RegionKind};
+
+// chunks from `min` to `max`, both inclusive
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ChunkArea {
+    min: chunk::Pos,
+    max: chunk::Pos,
+}
+
+impl ChunkArea {
+    // any two opposite corners
+    pub fn new(a: chunk::Pos, b: chunk::Pos) -> ChunkArea {
+        let ((ax, az), (bx, bz)) = (a.to_xz(), b.to_xz());
+        ChunkArea {
+            min: chunk::Pos::from_xz(ax.min(bx), az.min(bz)),
+            max: chunk::Pos::from_xz(ax.max(bx), az.max(bz))
+        }
+    }
+
+    // a square of `2 * radius + 1` chunks wide
+    pub fn around(center: chunk::Pos, radius: i32) -> ChunkArea {
+        let (x, z) = center.to_xz();
+        ChunkArea::new(chunk::Pos::from_xz(x - radius, z - radius), chunk::Pos::from_xz(x + radius, z + radius))
+    }
+
+    pub fn contains(&self, pos: chunk::Pos) -> bool {
+        let ((x, z), (min_x, min_z), (max_x, max_z)) = (pos.to_xz(), self.min.to_xz(), self.max.to_xz());
+        min_x <= x && x <= max_x && min_z <= z && z <= max_z
+    }
+}
+
+// a chunk inside any of `keep_areas` is always kept; any other chunk is
+// deleted if it matches at least one of the other options
+#[derive(Clone, Debug, Default)]
+pub struct TrimOptions {
+    pub keep_areas: Vec<ChunkArea>,
+    // delete every chunk outside `keep_areas`
+    pub outside_keep_areas: bool,
+    // delete chunks players spent less than this many ticks in
+    pub inhabited_below: Option<i64>,
+    // delete chunks of these generation statuses, like `empty` or `features`;
+    // the `minecraft:` prefix is optional
+    pub statuses: Vec<String>,
+    // only count what would be deleted
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TrimReport {
+    pub chunks_kept: usize,
+    pub chunks_removed: usize,
+    // chunks that failed to load are never deleted
+    pub chunks_unreadable: usize,
+    pub regions_removed: usize,
+}
+
+// fields of the chunk sit under `Level` before 1.18, and at the root since
+fn chunk_field<'a>(data: &'a nbt::Data, name: &str) -> Option<&'a Tag> {
+    let root = data.root_tag().as_compound()?;
+    root.get("Level").and_then(Tag::as_compound)
+        .and_then(|level| level.get(name))
+        .or_else(|| root.get(name))
+}
+
+fn strip_namespace(id: &str) -> &str {
+    id.trim_start_matches("minecraft:")
+}
+
+impl TrimOptions {
+    fn should_remove(&self, pos: chunk::Pos, data: &nbt::Data) -> bool {
+        if self.keep_areas.iter().any(|area| area.contains(pos)) {
+            return false;
+        }
+        if self.outside_keep_areas {
+            return true;
+        }
+        if let Some(threshold) = self.inhabited_below {
+            if chunk_field(data, "InhabitedTime").and_then(Tag::as_long).unwrap_or(0) < threshold {
+                return true;
+            }
+        }
+        match chunk_field(data, "Status").and_then(Tag::as_str) {
+            Some(status) => self.statuses.iter().any(|s| strip_namespace(s) == strip_namespace(status)),
+            None => false
+        }
+    }
+}
+
+fn remove_from_region<P: AsRef<Path>>(world: &mut McJavaWorld<P>, dim: &Dimension, kind: RegionKind,
+    region_x: i32, region_z: i32, positions: &[chunk::Pos]) -> Result<bool> {
+    let path = world.region_path(dim, kind, region_x, region_z);
+    if !path.is_file() {
+        return Ok(false);
+    }
+    let mut region = world.open_region_writable(dim, kind, region_x, region_z)?;
+    for &pos in positions {
+        region.remove_chunk(pos)?;
+    }
+    let empty = region.chunk_positions().is_empty();
+    if !empty {
+        region.compact()?;
+    }
+    region.sync_all()?;
+    drop(region);
+    if empty {
+        fs::remove_file(path)?;
+    }
+    Ok(empty)
+}
+
+// delete the chunks of a dimension matching `options`
+pub fn trim_world<P: AsRef<Path>>(world: &mut McJavaWorld<P>, dim: &Dimension, options: &TrimOptions) -> Result<TrimReport> {
+    world.check_writable()?;
+    let mut report = TrimReport::default();
+    for (region_x, region_z) in world.region_positions(dim, RegionKind::Terrain)? {
+        let mut region = world.open_region(dim, RegionKind::Terrain, region_x, region_z)?;
+        let mut remove = Vec::new();
+        for pos in region.chunk_positions() {
+            match region.read_chunk_nbt(pos) {
+                Ok(Some(data)) if options.should_remove(pos, &data) => remove.push(pos),
+                Ok(_) => report.chunks_kept += 1,
+                Err(_) => report.chunks_unreadable += 1
+            }
+        }
+        report.chunks_removed += remove.len();
+        let empty = remove.len() == region.chunk_positions().len();
+        drop(region);
+        if remove.is_empty() {
+            continue;
+        }
+        if options.dry_run {
+            report.regions_removed += empty as usize;
+            continue;
+        }
+        for &kind in [RegionKind::Terrain, RegionKind::Entities, RegionKind::Poi].iter() {
+            if remove_from_region(world, dim, kind, region_x, region_z, &remove)? && kind == RegionKind::Terrain {
+                report.regions_removed += 1;
+            }
+        }
+    }
+    Ok(report)
+}